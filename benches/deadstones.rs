@@ -13,7 +13,7 @@ pub fn dead_stones() {
 pub fn dead_bench(_c: &mut Criterion) {
     let c = Criterion::default();
     c.sample_size(10)
-        .bench_function("dead_stones_mcts", |b| b.iter(|| dead_stones()));
+        .bench_function("dead_stones_mcts", |b| b.iter(dead_stones));
 }
 
 criterion_group!(benches, dead_bench);
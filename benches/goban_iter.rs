@@ -0,0 +1,46 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::{black_box, Criterion};
+
+use goban::pieces::goban::Goban;
+use goban::pieces::stones::Color;
+
+/// A 19x19 goban with 3 stones out of 4, in stripes so no chain is captured.
+fn dense_goban() -> Goban {
+    let mut goban = Goban::new((19, 19));
+    for row in 0..19 {
+        for column in 0..19 {
+            if column % 4 == 3 {
+                continue;
+            }
+            let color = if (column / 4) % 2 == 0 {
+                Color::Black
+            } else {
+                Color::White
+            };
+            goban.push((row, column), color);
+        }
+    }
+    goban
+}
+
+pub fn goban_iter_bench(c: &mut Criterion) {
+    let goban = dense_goban();
+    c.bench_function("get_stones_count", |b| {
+        b.iter(|| black_box(&goban).get_stones().count())
+    });
+    c.bench_function("get_empty_coords_count", |b| {
+        b.iter(|| black_box(&goban).get_empty_coords().count())
+    });
+    c.bench_function("get_stones_by_color_count", |b| {
+        b.iter(|| {
+            black_box(&goban)
+                .get_stones_by_color(Some(Color::White))
+                .count()
+        })
+    });
+}
+
+criterion_group!(benches, goban_iter_bench);
+criterion_main!(benches);
@@ -2,8 +2,10 @@
 extern crate criterion;
 
 use criterion::Criterion;
-use rand::prelude::{SliceRandom, ThreadRng};
+use rand::prelude::{IndexedRandom, SliceRandom, ThreadRng};
 use rand::rng;
+use goban::analysis::{estimate_score, local_moves, GamePool, LOCAL_RADIUS};
+use goban::pieces::util::coord::Coord;
 use goban::pieces::stones::Stone;
 use goban::rules::game::Game;
 use goban::rules::{GobanSizes, Move, Rule, CHINESE, JAPANESE};
@@ -414,6 +416,76 @@ fn some_plays_from_sgf() {
     }
 }
 
+/// Random game where the legal moves are computed from scratch or from the cache.
+fn play_game_legals(cached: bool) {
+    let mut thread_rng = rng();
+    let mut g = Game::new(GobanSizes::Nineteen, CHINESE);
+    if cached {
+        g.enable_legal_move_cache();
+    }
+    while !g.is_over() && g.moves().count() < 400 {
+        let legals: Vec<_> = if cached {
+            g.legal_moves_cached(g.turn()).collect()
+        } else {
+            g.legals().collect()
+        };
+        g.play(legals.choose(&mut thread_rng).map_or(Move::Pass, |&point| point.into()));
+    }
+}
+
+/// Random game answering near the last move when it can, like a playout policy.
+fn play_game_local() {
+    let mut thread_rng = rng();
+    let mut g = Game::new(GobanSizes::Nineteen, CHINESE);
+    while !g.is_over() && g.moves().count() < 400 {
+        let turn = g.turn();
+        let not_eye = |&point: &Coord| {
+            !g.check_eye(Stone {
+                coord: point,
+                color: turn,
+            })
+        };
+        let local: Vec<Coord> = local_moves(&g, LOCAL_RADIUS).into_iter().filter(not_eye).collect();
+        let played = match local.choose(&mut thread_rng) {
+            Some(&point) => point.into(),
+            None => fast_play_random(&g, &mut thread_rng),
+        };
+        g.play(played);
+    }
+}
+
+/// Short random playouts from a middle game position, each from a copy of the root.
+fn playouts(root: &Game, pool: Option<&GamePool>) {
+    let mut thread_rng = rng();
+    for _ in 0..20 {
+        let mut pooled;
+        let mut cloned;
+        let game = match pool {
+            Some(pool) => {
+                pooled = pool.acquire();
+                &mut *pooled
+            }
+            None => {
+                cloned = root.clone();
+                &mut cloned
+            }
+        };
+        for _ in 0..20 {
+            let played = fast_play_random(game, &mut thread_rng);
+            game.play(played);
+        }
+    }
+}
+
+fn middle_game() -> Game {
+    let record = Game::from_sgf(include_str!("../sgf/ShusakuvsInseki.sgf")).unwrap();
+    let mut game = Game::new(GobanSizes::Nineteen, JAPANESE);
+    for m in record.moves().take(150) {
+        game.play(m);
+    }
+    game
+}
+
 pub fn game_play_bench(_c: &mut Criterion) {
     let c = Criterion::default();
     c.sample_size(100)
@@ -426,6 +498,23 @@ pub fn game_play_bench(_c: &mut Criterion) {
         })
         .bench_function("play_sgf_game", |b| {
             b.iter(some_plays_from_sgf);
+        })
+        .bench_function("play_game_legals", |b| b.iter(|| play_game_legals(false)))
+        .bench_function("play_game_legals_cached", |b| {
+            b.iter(|| play_game_legals(true))
+        })
+        .bench_function("play_game_local_moves", |b| b.iter(play_game_local))
+        .bench_function("playouts_cloned", |b| {
+            let root = middle_game();
+            b.iter(|| playouts(&root, None))
+        })
+        .bench_function("playouts_pooled", |b| {
+            let pool = GamePool::new(middle_game(), 4);
+            b.iter(|| playouts(pool.template(), Some(&pool)))
+        })
+        .bench_function("estimate_score", |b| {
+            let game = Game::from_sgf(include_str!("../sgf/ShusakuvsInseki.sgf")).unwrap();
+            b.iter(|| estimate_score(game.goban(), game.komi()))
         });
     Criterion::default()
         .sample_size(10)
@@ -0,0 +1,65 @@
+#[macro_use]
+extern crate criterion;
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+
+use criterion::{black_box, Criterion};
+
+use goban::analysis::ConcurrentTranspositionTable;
+
+const THREADS: u64 = 8;
+const OPERATIONS: u64 = 10_000;
+
+/// Random keys of the thread, a xorshift so the keys don't depend on the version of rand.
+fn keys(thread: u64) -> impl Iterator<Item = u64> {
+    let mut state = 0x9e3779b97f4a7c15 ^ (thread + 1);
+    (0..OPERATIONS).map(move |_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    })
+}
+
+/// Each thread inserts a key then reads a key inserted a bit before.
+fn hammer(insert: impl Fn(u64) + Sync, get: impl Fn(u64) -> Option<u64> + Sync) {
+    thread::scope(|scope| {
+        for thread in 0..THREADS {
+            let (insert, get) = (&insert, &get);
+            scope.spawn(move || {
+                let mut previous = 0;
+                for key in keys(thread) {
+                    insert(key);
+                    black_box(get(previous));
+                    previous = key >> 1;
+                }
+            });
+        }
+    });
+}
+
+pub fn transposition_bench(c: &mut Criterion) {
+    let table = ConcurrentTranspositionTable::new(1 << 16, 64);
+    c.bench_function("sharded_table_8_threads", |b| {
+        b.iter(|| hammer(|key| table.insert(key, key), |key| table.get(key)))
+    });
+    c.bench_function("sharded_table_probe_8_threads", |b| {
+        b.iter(|| hammer(|key| table.insert(key, key), |key| table.probe(key)))
+    });
+    let map = Mutex::new(HashMap::with_capacity(1 << 16));
+    c.bench_function("mutex_hashmap_8_threads", |b| {
+        b.iter(|| {
+            hammer(
+                |key| {
+                    map.lock().unwrap().insert(key, key);
+                },
+                |key| map.lock().unwrap().get(&key).copied(),
+            )
+        })
+    });
+}
+
+criterion_group!(benches, transposition_bench);
+criterion_main!(benches);
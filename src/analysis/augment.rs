@@ -0,0 +1,94 @@
+//! Augmentation of the training samples by the 8 symmetries of the goban.
+//!
+//! [`augment`] moves the goban itself. For the inputs of a neural network, [`SymmetryTable`]
+//! precomputes for a goban size where each flat index goes, so the planes and the policy are
+//! moved by copying values.
+
+use crate::pieces::goban::Goban;
+use crate::pieces::symmetry::Symmetry;
+use crate::pieces::util::coord::{one_to_2dim, two_to_1dim, Coord, Size};
+
+/// Returns the goban and the point of the policy moved by each symmetry, in the order of
+/// [`Symmetry::ALL`] so the identity is first.
+pub fn augment(goban: &Goban, policy_target: Coord) -> impl Iterator<Item = (Goban, Coord)> + '_ {
+    Symmetry::ALL.into_iter().map(move |symmetry| {
+        (
+            goban.transform(symmetry),
+            symmetry.apply(goban.size(), policy_target),
+        )
+    })
+}
+
+/// Where each flat index of a goban size goes by each symmetry, the indexes are in row major
+/// order of the goban once transformed, see [`Symmetry::size`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymmetryTable {
+    size: Size,
+    permutations: [Vec<u16>; 8],
+}
+
+impl SymmetryTable {
+    /// Precomputes the permutations of the flat indexes of a goban of the size.
+    pub fn new(size: Size) -> Self {
+        let area = size.0 as usize * size.1 as usize;
+        let permutations = Symmetry::ALL.map(|symmetry| {
+            (0..area)
+                .map(|index| {
+                    let point = symmetry.apply(size, one_to_2dim(size, index));
+                    two_to_1dim(symmetry.size(size), point) as u16
+                })
+                .collect()
+        });
+        SymmetryTable { size, permutations }
+    }
+
+    /// Size of the goban of the table.
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Number of points of the goban, the length of a plane.
+    pub fn area(&self) -> usize {
+        self.size.0 as usize * self.size.1 as usize
+    }
+
+    /// Flat index the index goes to by the symmetry. The indexes past the goban, like a pass
+    /// at the end of a policy, are unchanged.
+    pub fn transform_index(&self, symmetry: Symmetry, index: usize) -> usize {
+        self.permutations[symmetry as usize]
+            .get(index)
+            .map_or(index, |&to| to as usize)
+    }
+
+    /// Moves the planes by the symmetry, the planes are stacked one after the other and each of
+    /// them is a goban in row major order. Values after the last full plane are unchanged.
+    pub fn transform_planes<T: Copy>(&self, symmetry: Symmetry, planes: &[T]) -> Vec<T> {
+        let area = self.area();
+        let permutation = &self.permutations[symmetry as usize];
+        let mut transformed = planes.to_vec();
+        if area == 0 {
+            return transformed;
+        }
+        for (from, to) in planes.chunks_exact(area).zip(transformed.chunks_exact_mut(area)) {
+            for (&value, &index) in from.iter().zip(permutation) {
+                to[index as usize] = value;
+            }
+        }
+        transformed
+    }
+
+    /// Returns the planes and the policy index moved by each symmetry, in the order of
+    /// [`Symmetry::ALL`], the plane level version of [`augment`].
+    pub fn augment_planes<'a, T: Copy>(
+        &'a self,
+        planes: &'a [T],
+        policy_index: usize,
+    ) -> impl Iterator<Item = (Vec<T>, usize)> + 'a {
+        Symmetry::ALL.into_iter().map(move |symmetry| {
+            (
+                self.transform_planes(symmetry, planes),
+                self.transform_index(symmetry, policy_index),
+            )
+        })
+    }
+}
@@ -0,0 +1,173 @@
+//! Heuristic score estimation with the Bouzy influence map.
+//!
+//! The stones radiate influence with dilations, then erosions remove the influence which isn't
+//! surrounded, what remains is the territory of each color. Small chains low on liberties that
+//! stand in the influence of the opponent are counted as dead.
+
+use crate::pieces::goban::Goban;
+use crate::pieces::stones::{Color, MaybeColor};
+use crate::pieces::util::coord::{valid_coords, Coord, Size};
+
+/// Number of dilations of the influence.
+const DILATIONS: usize = 5;
+/// Number of erosions of the influence.
+const EROSIONS: usize = 21;
+/// Initial influence of a stone.
+const STONE_INFLUENCE: i32 = 128;
+/// A chain is weak when it has at most this number of stones...
+const WEAK_MAX_STONES: u16 = 6;
+/// ... and at most this number of liberties.
+const WEAK_MAX_LIBERTIES: usize = 2;
+
+/// Estimation of the score of a position, by area.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScoreEstimate {
+    /// Points of black.
+    pub black: f32,
+    /// Points of white, komi included.
+    pub white: f32,
+    /// Owner guessed of each point of the goban in row major order, None if neutral.
+    pub ownership: Vec<MaybeColor>,
+    /// Stones estimated dead in row major order.
+    pub dead: Vec<Coord>,
+    size: Size,
+}
+
+impl ScoreEstimate {
+    /// Points of black minus the points of white, positive if black leads.
+    #[inline]
+    pub fn margin(&self) -> f32 {
+        self.black - self.white
+    }
+
+    /// Owner guessed of the point.
+    #[inline]
+    pub fn owner(&self, (row, col): Coord) -> MaybeColor {
+        self.ownership[row as usize * self.size.1 as usize + col as usize]
+    }
+}
+
+/// Estimates the score of the goban without playouts, the result is deterministic.
+/// The points are counted by area: the live stones and the territory of each color.
+pub fn estimate_score(goban: &Goban, komi: f32) -> ScoreEstimate {
+    let size = goban.size();
+    let (height, width) = size;
+    let index = |(row, col): Coord| row as usize * width as usize + col as usize;
+    let mut stones: Vec<MaybeColor> = vec![None; height as usize * width as usize];
+    let mut weak = vec![false; stones.len()];
+    for (chain, group) in goban.get_groups() {
+        let is_weak = group.num_stones <= WEAK_MAX_STONES
+            && group.number_of_liberties() <= WEAK_MAX_LIBERTIES;
+        for stone in goban.chain_stones(chain) {
+            stones[index(stone.coord)] = Some(stone.color);
+            weak[index(stone.coord)] = is_weak;
+        }
+    }
+
+    // The influence of the strong stones decides which weak chains are dead.
+    let strong: Vec<MaybeColor> = stones
+        .iter()
+        .zip(&weak)
+        .map(|(&color, &weak)| if weak { None } else { color })
+        .collect();
+    let strong_influence = influence(size, &strong);
+    let mut dead = vec![];
+    for (chain, group) in goban.get_groups() {
+        let chain_stones: Vec<Coord> = goban.chain_stones(chain).map(|s| s.coord).collect();
+        if !weak[index(chain_stones[0])] {
+            continue;
+        }
+        let surrounded = chain_stones.iter().all(|&coord| {
+            let value = strong_influence[index(coord)];
+            match group.color {
+                Color::Black => value < 0,
+                Color::White => value > 0,
+            }
+        });
+        if surrounded {
+            dead.extend(chain_stones);
+        }
+    }
+    dead.sort_unstable();
+    for &coord in &dead {
+        stones[index(coord)] = None;
+    }
+
+    let alive_influence = influence(size, &stones);
+    let ownership: Vec<MaybeColor> = stones
+        .iter()
+        .zip(&alive_influence)
+        .map(|(&stone, &value)| {
+            stone.or(match value {
+                v if v > 0 => Some(Color::Black),
+                v if v < 0 => Some(Color::White),
+                _ => None,
+            })
+        })
+        .collect();
+    let black = ownership.iter().filter(|&&o| o == Some(Color::Black)).count() as f32;
+    let white = ownership.iter().filter(|&&o| o == Some(Color::White)).count() as f32 + komi;
+    ScoreEstimate {
+        black,
+        white,
+        ownership,
+        dead,
+        size,
+    }
+}
+
+/// Bouzy influence of the stones in row major order, positive for black, negative for white.
+fn influence(size: Size, stones: &[MaybeColor]) -> Vec<i32> {
+    let (height, width) = size;
+    let neighbors: Vec<Vec<usize>> = (0..height)
+        .flat_map(|row| (0..width).map(move |col| (row, col)))
+        .map(|coord| {
+            valid_coords(coord, size)
+                .into_iter()
+                .map(|(row, col)| row as usize * width as usize + col as usize)
+                .collect()
+        })
+        .collect();
+    let mut values: Vec<i32> = stones
+        .iter()
+        .map(|stone| match stone {
+            Some(Color::Black) => STONE_INFLUENCE,
+            Some(Color::White) => -STONE_INFLUENCE,
+            None => 0,
+        })
+        .collect();
+    let mut next = values.clone();
+
+    for _ in 0..DILATIONS {
+        for (i, value) in values.iter().enumerate() {
+            let around = &neighbors[i];
+            let positives = around.iter().filter(|&&n| values[n] > 0).count() as i32;
+            let negatives = around.iter().filter(|&&n| values[n] < 0).count() as i32;
+            next[i] = *value;
+            if *value >= 0 && negatives == 0 {
+                next[i] += positives;
+            }
+            if *value <= 0 && positives == 0 {
+                next[i] -= negatives;
+            }
+        }
+        std::mem::swap(&mut values, &mut next);
+    }
+
+    for _ in 0..EROSIONS {
+        for (i, value) in values.iter().enumerate() {
+            let around = &neighbors[i];
+            next[i] = match *value {
+                v if v > 0 => {
+                    (v - around.iter().filter(|&&n| values[n] <= 0).count() as i32).max(0)
+                }
+                v if v < 0 => {
+                    (v + around.iter().filter(|&&n| values[n] >= 0).count() as i32).min(0)
+                }
+                _ => 0,
+            };
+        }
+        std::mem::swap(&mut values, &mut next);
+    }
+    values
+}
@@ -0,0 +1,132 @@
+//! Classification of the eye spaces, the empty regions surrounded by a group, by their shape.
+//!
+//! The shape is compared to a table of the known shapes under translation and the 8 symmetries
+//! of the goban. The status is the one of the shape alone in the middle of the goban, the
+//! stones already inside the space, the edges and the liberties of the surrounding group
+//! aren't taken into account.
+
+use crate::pieces::util::coord::{neighbor_coords, Coord};
+use EyespaceStatus::{Alive, Dead, Killable};
+
+/// Status of an eye space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EyespaceStatus {
+    /// The space makes two eyes whoever plays first.
+    Alive,
+    /// A nakade: the space makes two eyes if the owner plays on the vital point, one eye if the
+    /// opponent plays there first.
+    Killable { vital: Coord },
+    /// The space makes one eye whoever plays first.
+    Dead,
+    /// The shape isn't known, the space is larger than 6 points or isn't connected.
+    Unknown,
+}
+
+/// Shapes in a normal position with their status, the vital points are in the shape
+/// coordinates.
+const SHAPES: [(&[Coord], EyespaceStatus); 13] = [
+    (&[(0, 0)], Dead),
+    (&[(0, 0), (0, 1)], Dead),
+    // Straight three.
+    (&[(0, 0), (0, 1), (0, 2)], Killable { vital: (0, 1) }),
+    // Bent three.
+    (&[(0, 0), (0, 1), (1, 0)], Killable { vital: (0, 0) }),
+    // Straight four.
+    (&[(0, 0), (0, 1), (0, 2), (0, 3)], Alive),
+    // Bent four.
+    (&[(0, 0), (1, 0), (2, 0), (2, 1)], Alive),
+    // Twisted four.
+    (&[(0, 0), (0, 1), (1, 1), (1, 2)], Alive),
+    // Pyramid four.
+    (&[(0, 0), (0, 1), (0, 2), (1, 1)], Killable { vital: (0, 1) }),
+    // Square four.
+    (&[(0, 0), (0, 1), (1, 0), (1, 1)], Dead),
+    // Bulky five.
+    (&[(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)], Killable { vital: (1, 0) }),
+    // Crossed five.
+    (&[(0, 1), (1, 0), (1, 1), (1, 2), (2, 1)], Killable { vital: (1, 1) }),
+    // Rabbity six.
+    (&[(0, 1), (1, 0), (1, 1), (1, 2), (2, 1), (2, 2)], Killable { vital: (1, 1) }),
+    // Rectangular six.
+    (&[(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)], Alive),
+];
+
+/// Classifies a connected empty region by its shape. The spaces of 5 points other than the
+/// bulky and crossed five are alive, the vital point of a killable space is returned in the
+/// coordinates of the points.
+pub fn classify_eyespace(points: &[Coord]) -> EyespaceStatus {
+    let mut points = points.to_vec();
+    points.sort_unstable();
+    points.dedup();
+    if points.is_empty() || points.len() > 6 || !is_connected(&points) {
+        return EyespaceStatus::Unknown;
+    }
+    for symmetry in 0..8 {
+        let shape = normalized(&points, symmetry);
+        let Some(&(_, status)) = SHAPES
+            .iter()
+            .find(|(known, _)| *known == shape.as_slice())
+        else {
+            continue;
+        };
+        return match status {
+            Killable { vital } => {
+                let index = transformed(&points, symmetry)
+                    .position(|point| point == vital)
+                    .expect("the vital point is in the shape");
+                Killable {
+                    vital: points[index],
+                }
+            }
+            status => status,
+        };
+    }
+    match points.len() {
+        5 => Alive,
+        _ => EyespaceStatus::Unknown,
+    }
+}
+
+/// True if the points are connected by their sides.
+fn is_connected(points: &[Coord]) -> bool {
+    let mut reached = vec![false; points.len()];
+    reached[0] = true;
+    let mut to_explore = vec![points[0]];
+    while let Some(point) = to_explore.pop() {
+        for neighbor in neighbor_coords(point) {
+            if let Ok(index) = points.binary_search(&neighbor) {
+                if !reached[index] {
+                    reached[index] = true;
+                    to_explore.push(neighbor);
+                }
+            }
+        }
+    }
+    reached.into_iter().all(|reached| reached)
+}
+
+/// The points moved by the symmetry then translated next to the origin, in the order of the
+/// points.
+fn transformed(points: &[Coord], symmetry: u8) -> impl Iterator<Item = Coord> + '_ {
+    let transform = move |(row, col): Coord| {
+        let (row, col) = (row as i16, col as i16);
+        let (row, col) = if symmetry & 4 != 0 { (col, row) } else { (row, col) };
+        (
+            if symmetry & 1 != 0 { -row } else { row },
+            if symmetry & 2 != 0 { -col } else { col },
+        )
+    };
+    let min_row = points.iter().map(|&p| transform(p).0).min().unwrap_or(0);
+    let min_col = points.iter().map(|&p| transform(p).1).min().unwrap_or(0);
+    points.iter().map(move |&point| {
+        let (row, col) = transform(point);
+        ((row - min_row) as u8, (col - min_col) as u8)
+    })
+}
+
+/// The shape of the points under the symmetry, sorted to be compared to the table.
+fn normalized(points: &[Coord], symmetry: u8) -> Vec<Coord> {
+    let mut shape: Vec<Coord> = transformed(points, symmetry).collect();
+    shape.sort_unstable();
+    shape
+}
@@ -0,0 +1,217 @@
+//! Enumeration of the ko threats, the moves away from the ko the opponent can't ignore.
+//!
+//! The threats are found by reading one move ahead: ataris, ladders which work after the move,
+//! and invasions of the territory of the score estimation. The size of a threat is rough, see
+//! [`ThreatSize`].
+
+use arrayvec::ArrayVec;
+
+use crate::analysis::estimate_score;
+use crate::pieces::goban::{Goban, GroupIdx};
+use crate::pieces::stones::{Color, Stone};
+use crate::pieces::util::coord::{valid_coords, Coord};
+use crate::rules::game::Game;
+use crate::rules::IllegalRules;
+
+/// Maximum number of ataris read in a ladder.
+const LADDER_DEPTH: u8 = 60;
+/// An invasion must take at least this number of points from the opponent to be a threat.
+const INVASION_MIN_POINTS: u32 = 6;
+
+/// Kind of a ko threat with its size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ThreatSize {
+    /// Puts chains of the opponent in atari, with their number of stones.
+    Atari(u32),
+    /// Gives two liberties to a chain of the opponent which is then captured in a ladder, with
+    /// its number of stones.
+    Ladder(u32),
+    /// Invades the territory of the opponent, with the points lost by the opponent in the score
+    /// estimation if the invasion isn't answered.
+    Invasion(u32),
+}
+
+impl ThreatSize {
+    /// Rough number of points at stake if the opponent ignores the threat, a captured stone
+    /// counts twice: the stone and the point where it was.
+    pub fn points(&self) -> u32 {
+        match *self {
+            ThreatSize::Atari(stones) | ThreatSize::Ladder(stones) => 2 * stones,
+            ThreatSize::Invasion(points) => points,
+        }
+    }
+}
+
+/// Returns the ko threats of the color, the largest first then in row major order. The moves
+/// next to the ko point, diagonals included, are part of the ko fight and aren't threats.
+/// A point gives only its largest threat.
+pub fn ko_threats(game: &Game, for_color: Color) -> Vec<(Coord, ThreatSize)> {
+    let goban = game.goban();
+    let width = goban.size().1;
+    let suicide_allowed = !game.rule().flag_illegal.contains(IllegalRules::SUICIDE);
+    let opponent = !for_color;
+    let in_ko_area = |(row, col): Coord| {
+        game.ko_point()
+            .is_some_and(|(ko_row, ko_col)| row.abs_diff(ko_row) <= 1 && col.abs_diff(ko_col) <= 1)
+    };
+    let estimate = estimate_score(goban, 0.);
+    let territory = |ownership: &[Option<Color>], goban: &Goban| {
+        goban
+            .get_empty_coords()
+            .filter(|&(row, col)| {
+                ownership[row as usize * width as usize + col as usize] == Some(opponent)
+            })
+            .count() as u32
+    };
+    let opponent_territory = territory(&estimate.ownership, goban);
+
+    let mut threats = vec![];
+    for point in goban.get_empty_coords() {
+        let stone = Stone {
+            coord: point,
+            color: for_color,
+        };
+        if in_ko_area(point)
+            || game
+                .check_stone_locally(stone, game.rule().flag_illegal)
+                .is_some()
+        {
+            continue;
+        }
+        let Some(after) = play(goban, point, for_color, suicide_allowed) else {
+            continue;
+        };
+        let mut best: Option<ThreatSize> = None;
+        let mut keep = |threat: ThreatSize| {
+            if best.is_none_or(|best| threat.points() > best.points()) {
+                best = Some(threat);
+            }
+        };
+
+        let mut in_atari = 0;
+        // A chain touching the point on several sides is counted once.
+        let mut seen = ArrayVec::<GroupIdx, 4>::new();
+        for neighbor in valid_coords(point, goban.size()) {
+            let (Some(chain_idx), Some(chain)) =
+                (after.chain_idx(neighbor), after.chain_at(neighbor))
+            else {
+                continue;
+            };
+            if chain.color != opponent || seen.contains(&chain_idx) {
+                continue;
+            }
+            seen.push(chain_idx);
+            let stones = chain.num_stones as u32;
+            let was_atari = goban
+                .chain_at(neighbor)
+                .is_some_and(|before| before.is_atari());
+            match chain.number_of_liberties() {
+                1 if !was_atari => in_atari += stones,
+                2 if ladder_captures(&after, neighbor, for_color, suicide_allowed) => {
+                    keep(ThreatSize::Ladder(stones))
+                }
+                _ => {}
+            }
+        }
+        if in_atari > 0 {
+            keep(ThreatSize::Atari(in_atari));
+        }
+
+        let (row, col) = point;
+        if estimate.ownership[row as usize * width as usize + col as usize] == Some(opponent) {
+            let lost = opponent_territory
+                .saturating_sub(territory(&estimate_score(&after, 0.).ownership, &after));
+            if lost >= INVASION_MIN_POINTS {
+                keep(ThreatSize::Invasion(lost));
+            }
+        }
+        if let Some(threat) = best {
+            threats.push((point, threat));
+        }
+    }
+    threats.sort_by_key(|&(point, threat)| (std::cmp::Reverse(threat.points()), point));
+    threats
+}
+
+/// True if the attacker, playing first, captures the chain on the point in a ladder: the
+/// attacker keeps giving atari and the defender keeps extending.
+fn ladder_captures(goban: &Goban, target: Coord, attacker: Color, suicide_allowed: bool) -> bool {
+    ladder_captures_within(goban, target, attacker, suicide_allowed, LADDER_DEPTH)
+}
+
+/// [`ladder_captures`] reading at most `depth` ataris.
+fn ladder_captures_within(
+    goban: &Goban,
+    target: Coord,
+    attacker: Color,
+    suicide_allowed: bool,
+    depth: u8,
+) -> bool {
+    let Some(chain) = goban.chain_at(target) else {
+        return true;
+    };
+    match chain.number_of_liberties() {
+        1 => true,
+        2 if depth > 0 => liberties(goban, target).into_iter().any(|atari| {
+            let Some(after_atari) = play(goban, atari, attacker, suicide_allowed) else {
+                return false;
+            };
+            if can_capture_around(&after_atari, target, attacker) {
+                return false;
+            }
+            let Some(&escape) = liberties(&after_atari, target).first() else {
+                return true;
+            };
+            match play(&after_atari, escape, !attacker, suicide_allowed) {
+                Some(after_escape) => ladder_captures_within(
+                    &after_escape,
+                    target,
+                    attacker,
+                    suicide_allowed,
+                    depth - 1,
+                ),
+                None => true,
+            }
+        }),
+        _ => false,
+    }
+}
+
+/// True if a chain of the attacker touching the chain on the point is in atari, the defender
+/// escapes by capturing it.
+fn can_capture_around(goban: &Goban, target: Coord, attacker: Color) -> bool {
+    let Some(chain_idx) = goban.chain_idx(target) else {
+        return false;
+    };
+    goban.chain_stones(chain_idx).any(|stone| {
+        goban
+            .get_connected_groups(stone.coord)
+            .iter()
+            .any(|group| group.color == attacker && group.is_atari())
+    })
+}
+
+/// Liberties of the chain on the point in row major order.
+fn liberties(goban: &Goban, point: Coord) -> Vec<Coord> {
+    goban.chain_at(point).map_or(vec![], |chain| {
+        chain
+            .liberties()
+            .into_iter()
+            .map(|index| goban.coord_of(index))
+            .collect()
+    })
+}
+
+/// The goban after the stone, None if the point is taken or the move is a forbidden suicide.
+fn play(goban: &Goban, point: Coord, color: Color, suicide_allowed: bool) -> Option<Goban> {
+    if goban.get_color(point).is_some() {
+        return None;
+    }
+    let mut after = goban.clone();
+    let (dead, added) = after.push_wth_feedback(point, color);
+    if dead.is_empty() && !suicide_allowed && after.chain_at(point)?.is_dead() {
+        return None;
+    }
+    after.remove_captured_stones_aux(suicide_allowed, &dead, added);
+    after.get_color(point).map(|_| after)
+}
@@ -0,0 +1,28 @@
+//! Analysis of positions, to help GUIs and engines evaluate a game without playing it to the end.
+
+mod augment;
+mod estimate;
+mod eyespace;
+mod ko_threats;
+mod open_area;
+mod opening;
+mod ordering;
+mod pool;
+mod transposition;
+#[cfg(feature = "random")]
+mod ownership;
+
+pub use augment::{augment, SymmetryTable};
+pub use estimate::{estimate_score, ScoreEstimate};
+pub use eyespace::{classify_eyespace, EyespaceStatus};
+pub use ko_threats::{ko_threats, ThreatSize};
+pub use open_area::largest_open_regions;
+pub use opening::{standard_opening_points, OpeningKind};
+pub use ordering::{
+    captured_stones, escapes_atari, gives_atari, local_moves, move_category, near_last_moves,
+    ordered_moves, proximity_bonus, weighted_moves, MoveCategory, OrderingWeights, LOCAL_RADIUS,
+};
+pub use pool::{GamePool, PooledGame};
+pub use transposition::ConcurrentTranspositionTable;
+#[cfg(feature = "random")]
+pub use ownership::{mc_ownership, mc_ownership_for, OwnershipMap};
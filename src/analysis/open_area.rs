@@ -0,0 +1,94 @@
+//! The largest empty areas of the goban, for the opening moves and the hints of the teaching
+//! interfaces.
+
+use std::collections::VecDeque;
+
+use crate::pieces::goban::Goban;
+use crate::pieces::util::coord::{neighbor_coords, one_to_2dim, two_to_1dim, Coord};
+
+/// Returns the `k` largest connected empty regions, the largest first and in row major order of
+/// their first point for the same size, with the points of each region in row major order.
+///
+/// The point suggested for a region is the one the farthest from the stones and from the edge.
+/// The distance to the edge only counts up to the line of the star points, the 4th line from
+/// 13x13 and the 3rd below, so on an empty goban the upper left star point is suggested. The
+/// ties are broken by the distance to the stones then by the smallest point.
+pub fn largest_open_regions(goban: &Goban, k: usize) -> Vec<(Vec<Coord>, Coord)> {
+    let size = goban.size();
+    let (height, width) = size;
+    let stones = stone_distances(goban);
+    let star_line = if height.min(width) >= 13 { 3 } else { 2 };
+    let edge = |(row, col): Coord| {
+        row.min(col)
+            .min(height - 1 - row)
+            .min(width - 1 - col)
+            .min(star_line) as u32
+    };
+
+    let mut visited = vec![false; height as usize * width as usize];
+    let mut regions: Vec<Vec<Coord>> = vec![];
+    for start in goban.get_empty_coords() {
+        if visited[two_to_1dim(size, start)] {
+            continue;
+        }
+        visited[two_to_1dim(size, start)] = true;
+        let mut region = vec![];
+        let mut to_explore = vec![start];
+        while let Some(point) = to_explore.pop() {
+            region.push(point);
+            for neighbor in neighbor_coords(point) {
+                if goban.get_index(neighbor).is_some_and(|index| !visited[index])
+                    && goban.get_color(neighbor).is_none()
+                {
+                    visited[two_to_1dim(size, neighbor)] = true;
+                    to_explore.push(neighbor);
+                }
+            }
+        }
+        region.sort_unstable();
+        regions.push(region);
+    }
+    // The sort is stable, the regions were found in row major order.
+    regions.sort_by_key(|region| std::cmp::Reverse(region.len()));
+    regions.truncate(k);
+
+    regions
+        .into_iter()
+        .map(|region| {
+            let best = region
+                .iter()
+                .copied()
+                .max_by_key(|&point| {
+                    let stone = stones[two_to_1dim(size, point)];
+                    // Reversed so the smallest point wins the ties of max_by_key.
+                    (stone.min(edge(point)), stone, std::cmp::Reverse(point))
+                })
+                .expect("A region has at least one point");
+            (region, best)
+        })
+        .collect()
+}
+
+/// Distance transform of the goban: for each point in row major order, the Manhattan distance
+/// to the nearest stone, `u32::MAX` without stones.
+fn stone_distances(goban: &Goban) -> Vec<u32> {
+    let size = goban.size();
+    let mut distances = vec![u32::MAX; size.0 as usize * size.1 as usize];
+    let mut to_explore = VecDeque::new();
+    for stone in goban.get_stones() {
+        distances[two_to_1dim(size, stone.coord)] = 0;
+        to_explore.push_back(two_to_1dim(size, stone.coord));
+    }
+    while let Some(index) = to_explore.pop_front() {
+        let distance = distances[index] + 1;
+        for neighbor in neighbor_coords(one_to_2dim(size, index)) {
+            if let Some(neighbor) = goban.get_index(neighbor) {
+                if distances[neighbor] > distance {
+                    distances[neighbor] = distance;
+                    to_explore.push_back(neighbor);
+                }
+            }
+        }
+    }
+    distances
+}
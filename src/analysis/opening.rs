@@ -0,0 +1,87 @@
+//! The conventional first moves of a square goban, for the opening of a bot or a teaching
+//! overlay.
+
+use crate::pieces::util::coord::Coord;
+use crate::pieces::Nat;
+
+/// Kind of an opening point, the corner points are named by their lines counted from 1 at the
+/// edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum OpeningKind {
+    /// The 4-4 point.
+    Hoshi,
+    /// The 3-4 point.
+    Komoku,
+    /// The 3-3 point.
+    SanSan,
+    /// The 5-3 point.
+    Mokuhazushi,
+    /// The 5-4 point.
+    Takamoku,
+    /// A star point in the middle of a side, on the 4th line.
+    SideStar,
+    /// The center.
+    Tengen,
+}
+
+/// Lines of the corner points, the first line is the row.
+const CORNER_POINTS: [(OpeningKind, Nat, Nat); 8] = [
+    (OpeningKind::Hoshi, 4, 4),
+    (OpeningKind::Komoku, 3, 4),
+    (OpeningKind::Komoku, 4, 3),
+    (OpeningKind::SanSan, 3, 3),
+    (OpeningKind::Mokuhazushi, 5, 3),
+    (OpeningKind::Mokuhazushi, 3, 5),
+    (OpeningKind::Takamoku, 5, 4),
+    (OpeningKind::Takamoku, 4, 5),
+];
+
+/// Returns the opening points of a goban of the size, sorted by kind then in row major order.
+///
+/// The points are computed from the size. A corner point is kept only if its lines are closer
+/// to the edge than to the center line, so 13x13 has all the points of 19x19, 9x9 has no 5th
+/// line point and 7x7 only the 3-3 points. The side star points are only on the odd gobans from
+/// 13x13, on smaller gobans they are too close to the corners, and the tengen is only on the
+/// odd gobans.
+///
+/// # Panics
+/// If the size doesn't fit in a `u8`.
+pub fn standard_opening_points(size: usize) -> Vec<(Coord, OpeningKind)> {
+    let size = Nat::try_from(size).expect("the size of the goban doesn't fit in a u8");
+    if size == 0 {
+        return vec![];
+    }
+    let last = size - 1;
+    let fits = |line: Nat| line - 1 < last / 2;
+    let mut points = vec![];
+    for (kind, row_line, col_line) in CORNER_POINTS {
+        if !(fits(row_line) && fits(col_line)) {
+            continue;
+        }
+        let (row, col) = (row_line - 1, col_line - 1);
+        points.extend([
+            ((row, col), kind),
+            ((row, last - col), kind),
+            ((last - row, col), kind),
+            ((last - row, last - col), kind),
+        ]);
+    }
+    if size % 2 == 1 {
+        let middle = last / 2;
+        if size >= 13 {
+            let side = 3;
+            points.extend(
+                [
+                    (side, middle),
+                    (middle, side),
+                    (middle, last - side),
+                    (last - side, middle),
+                ]
+                .map(|point| (point, OpeningKind::SideStar)),
+            );
+        }
+        points.push(((middle, middle), OpeningKind::Tengen));
+    }
+    points.sort_unstable_by_key(|&(point, kind)| (kind, point));
+    points
+}
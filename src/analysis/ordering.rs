@@ -0,0 +1,193 @@
+//! Static ordering of the legal moves, so searches try the most promising moves first.
+
+use std::cmp::Reverse;
+
+use crate::pieces::util::coord::Coord;
+use crate::rules::game::Game;
+use crate::rules::Move;
+
+/// Distance, in both directions, to one of the last two moves for a move to be close to them.
+const PROXIMITY: u8 = 2;
+
+/// Category of a move for the ordering, the first categories are tried first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MoveCategory {
+    /// Captures stones, with the number of stones captured.
+    Capture(Reverse<u32>),
+    /// Gives liberties to a chain of the player in atari.
+    EscapeAtari,
+    /// Puts a chain of the opponent in atari.
+    Atari,
+    /// Near one of the last two moves.
+    Proximity,
+    /// None of the above.
+    Other,
+}
+
+/// Returns the legal moves of the player to move, ordered by [`MoveCategory`] then by the
+/// number of stones captured, the largest first, and then in row major order.
+pub fn ordered_moves(game: &Game) -> Vec<Coord> {
+    let mut moves: Vec<(MoveCategory, Coord)> = game
+        .legals()
+        .map(|point| (move_category(game, point), point))
+        .collect();
+    moves.sort_unstable();
+    moves.into_iter().map(|(_, point)| point).collect()
+}
+
+/// Weights of the terms of [`weighted_moves`], engines tune them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderingWeights {
+    /// Weight of each stone captured.
+    pub capture: f32,
+    /// Weight of a move escaping an atari.
+    pub escape_atari: f32,
+    /// Weight of a move giving atari.
+    pub atari: f32,
+    /// Weight of the [`proximity_bonus`].
+    pub proximity: f32,
+    /// Decay of the proximity bonus with the distance.
+    pub decay: f32,
+    /// Number of the last moves for the proximity bonus.
+    pub recent: usize,
+}
+
+/// About the order of [`MoveCategory`], with the proximity as a tie-breaker.
+impl Default for OrderingWeights {
+    fn default() -> Self {
+        OrderingWeights {
+            capture: 10.,
+            escape_atari: 8.,
+            atari: 6.,
+            proximity: 1.,
+            decay: 0.5,
+            recent: 2,
+        }
+    }
+}
+
+/// Locality of a move from the recent moves: the sum of `exp(-decay * distance)` to each of
+/// them, where the distance is the number of lines in the longest direction. A move on top of
+/// a recent move scores 1 for it, the score goes to 0 far from all of them.
+pub fn proximity_bonus(mv: Coord, recent: &[Coord], decay: f32) -> f32 {
+    recent
+        .iter()
+        .map(|&(row, col)| {
+            let distance = mv.0.abs_diff(row).max(mv.1.abs_diff(col));
+            (-decay * distance as f32).exp()
+        })
+        .sum()
+}
+
+/// Returns the legal moves of the player to move by decreasing score, then in row major order.
+/// The score adds the terms of the weights: captures, escapes and ataris like
+/// [`move_category`], and the [`proximity_bonus`] to the recent moves played on the goban.
+pub fn weighted_moves(game: &Game, weights: &OrderingWeights) -> Vec<Coord> {
+    let recent: Vec<Coord> = game
+        .moves()
+        .rev()
+        .take(weights.recent)
+        .filter_map(|m| match m {
+            Move::Play(row, col) => Some((row, col)),
+            _ => None,
+        })
+        .collect();
+    let mut moves: Vec<(f32, Coord)> = game
+        .legals()
+        .map(|point| {
+            let mut score = weights.proximity * proximity_bonus(point, &recent, weights.decay);
+            let captured = captured_stones(game, point);
+            if captured > 0 {
+                score += weights.capture * captured as f32;
+            } else if escapes_atari(game, point) {
+                score += weights.escape_atari;
+            } else if gives_atari(game, point) {
+                score += weights.atari;
+            }
+            (score, point)
+        })
+        .collect();
+    moves.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+    moves.into_iter().map(|(_, point)| point).collect()
+}
+
+/// Distance of [`local_moves`] to the last move for the playout policies.
+pub const LOCAL_RADIUS: usize = 3;
+
+/// Returns the legal moves of the player to move at most `radius` lines away from the last
+/// move, diagonals included, ordered like [`ordered_moves`]. Empty if the last move was a pass
+/// or no move was played. Only the points around the last move are checked, so it's cheap
+/// enough for each move of a playout.
+pub fn local_moves(game: &Game, radius: usize) -> Vec<Coord> {
+    let Some(Move::Play(row, col)) = game.moves().next_back() else {
+        return vec![];
+    };
+    let (height, width) = game.size();
+    let radius = u8::try_from(radius).unwrap_or(u8::MAX);
+    let rows = row.saturating_sub(radius)..=row.saturating_add(radius).min(height - 1);
+    let cols = col.saturating_sub(radius)..=col.saturating_add(radius).min(width - 1);
+    let mut moves: Vec<(MoveCategory, Coord)> = rows
+        .flat_map(|row| cols.clone().map(move |col| (row, col)))
+        .filter(|&point| game.check_point(point).is_none())
+        .map(|point| (move_category(game, point), point))
+        .collect();
+    moves.sort_unstable();
+    moves.into_iter().map(|(_, point)| point).collect()
+}
+
+/// Returns the category of the move of the player to move on the point.
+pub fn move_category(game: &Game, point: Coord) -> MoveCategory {
+    let captured = captured_stones(game, point);
+    if captured > 0 {
+        MoveCategory::Capture(Reverse(captured))
+    } else if escapes_atari(game, point) {
+        MoveCategory::EscapeAtari
+    } else if gives_atari(game, point) {
+        MoveCategory::Atari
+    } else if near_last_moves(game, point) {
+        MoveCategory::Proximity
+    } else {
+        MoveCategory::Other
+    }
+}
+
+/// Number of stones captured by the move on the point.
+pub fn captured_stones(game: &Game, point: Coord) -> u32 {
+    game.goban()
+        .get_connected_groups(point)
+        .iter()
+        .filter(|group| group.color != game.turn() && group.is_atari())
+        .map(|group| group.num_stones as u32)
+        .sum()
+}
+
+/// True if the move joins a chain of the player in atari and the chain has more than one
+/// liberty after the move.
+pub fn escapes_atari(game: &Game, point: Coord) -> bool {
+    let joins_atari = game
+        .goban()
+        .get_connected_groups(point)
+        .iter()
+        .any(|group| group.color == game.turn() && group.is_atari());
+    joins_atari
+        && game
+            .play_for_verification(point)
+            .chain_at(point)
+            .is_some_and(|group| group.number_of_liberties() > 1)
+}
+
+/// True if the move puts a chain of the opponent next to the point in atari.
+pub fn gives_atari(game: &Game, point: Coord) -> bool {
+    game.play_for_verification(point)
+        .get_connected_groups(point)
+        .iter()
+        .any(|group| group.color != game.turn() && group.is_atari())
+}
+
+/// True if the point is near one of the last two moves played on the goban.
+pub fn near_last_moves(game: &Game, (row, col): Coord) -> bool {
+    game.moves().rev().take(2).any(|m| match m {
+        Move::Play(x, y) => row.abs_diff(x) <= PROXIMITY && col.abs_diff(y) <= PROXIMITY,
+        _ => false,
+    })
+}
@@ -0,0 +1,196 @@
+//! Monte-Carlo ownership: random games are played to the end from the position and each point
+//! is counted for the color owning it under Tromp-Taylor.
+//!
+//! The playouts are light: the moves are random legal moves which don't fill an eye, the super
+//! ko isn't checked and a playout stops after a number of moves proportional to the goban.
+
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use crate::pieces::goban::Goban;
+use crate::pieces::stones::Color;
+use crate::pieces::util::coord::{Coord, Size};
+use crate::rules::game::Game;
+
+/// A playout stops after this number of moves by point of the goban.
+const MAX_MOVES_BY_POINT: usize = 3;
+
+/// Probabilities of ownership of each point, computed from random playouts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OwnershipMap {
+    /// Probability that black owns the point, in row major order.
+    pub black: Vec<f32>,
+    /// Probability that white owns the point, in row major order.
+    pub white: Vec<f32>,
+    /// Mean of the score of black minus the score of white with the komi, by area.
+    pub expected_score: f32,
+    /// Number of playouts played.
+    pub playouts: usize,
+    size: Size,
+}
+
+impl OwnershipMap {
+    /// Probability that black owns the point.
+    #[inline]
+    pub fn black_probability(&self, point: Coord) -> f32 {
+        self.black[self.index(point)]
+    }
+
+    /// Probability that white owns the point.
+    #[inline]
+    pub fn white_probability(&self, point: Coord) -> f32 {
+        self.white[self.index(point)]
+    }
+
+    /// Ownership of the point between -1, owned by white, and 1, owned by black.
+    #[inline]
+    pub fn ownership(&self, point: Coord) -> f32 {
+        self.black_probability(point) - self.white_probability(point)
+    }
+
+    #[inline]
+    fn index(&self, (row, col): Coord) -> usize {
+        row as usize * self.size.1 as usize + col as usize
+    }
+}
+
+/// Estimates the ownership of each point with `playouts` random playouts from the position,
+/// at least one playout is played.
+pub fn mc_ownership(game: &Game, playouts: usize, rng: &mut impl Rng) -> OwnershipMap {
+    simulate(game, rng, |played| played == 0 || played < playouts)
+}
+
+/// Estimates the ownership of each point with as many playouts as the time budget permits,
+/// at least one playout is played.
+pub fn mc_ownership_for(game: &Game, budget: Duration, rng: &mut impl Rng) -> OwnershipMap {
+    let start = Instant::now();
+    simulate(game, rng, |played| played == 0 || start.elapsed() < budget)
+}
+
+fn simulate(
+    game: &Game,
+    rng: &mut impl Rng,
+    mut keep_going: impl FnMut(usize) -> bool,
+) -> OwnershipMap {
+    let size = game.size();
+    let length = size.0 as usize * size.1 as usize;
+    let mut black = vec![0u32; length];
+    let mut white = vec![0u32; length];
+    let mut score_sum = 0.;
+    let mut playout = Playout::new(game.goban());
+    let mut played = 0;
+    while keep_going(played) {
+        playout.run(game, rng);
+        let (stones, (black_territory, white_territory)) = (
+            playout.goban.get_stones(),
+            playout.goban.get_territories(),
+        );
+        let mut score = 0i32;
+        let mut count = |color: Color, (row, col): Coord| {
+            let i = row as usize * size.1 as usize + col as usize;
+            match color {
+                Color::Black => {
+                    black[i] += 1;
+                    score += 1;
+                }
+                Color::White => {
+                    white[i] += 1;
+                    score -= 1;
+                }
+            }
+        };
+        stones.for_each(|stone| count(stone.color, stone.coord));
+        black_territory.for_each(|point| count(Color::Black, point.coord));
+        white_territory.for_each(|point| count(Color::White, point.coord));
+        score_sum += score as f32 - game.komi();
+        played += 1;
+    }
+    let probability =
+        |count: Vec<u32>| count.into_iter().map(|c| c as f32 / played as f32).collect();
+    OwnershipMap {
+        black: probability(black),
+        white: probability(white),
+        expected_score: score_sum / played as f32,
+        playouts: played,
+        size,
+    }
+}
+
+/// Buffers of the playouts, kept between simulations.
+struct Playout {
+    goban: Goban,
+    candidates: Vec<Coord>,
+}
+
+impl Playout {
+    fn new(goban: &Goban) -> Self {
+        Playout {
+            goban: goban.clone(),
+            candidates: Vec::with_capacity(goban.size().0 as usize * goban.size().1 as usize),
+        }
+    }
+
+    /// Plays a random game from the position until two passes or the moves limit.
+    fn run(&mut self, game: &Game, rng: &mut impl Rng) {
+        self.goban.clone_from(game.goban());
+        let (height, width) = game.size();
+        let max_moves = MAX_MOVES_BY_POINT * height as usize * width as usize;
+        let mut turn = game.turn();
+        let mut ko_point = game.ko_point();
+        let mut passes = 0;
+        for _ in 0..max_moves {
+            if passes == 2 {
+                break;
+            }
+            match self.pick(turn, ko_point, rng) {
+                Some(point) => {
+                    let (dead_chains, added_chain) = self.goban.push_wth_feedback(point, turn);
+                    ko_point = self
+                        .goban
+                        .remove_captured_stones_aux(false, &dead_chains, added_chain)
+                        .1;
+                    passes = 0;
+                }
+                None => {
+                    ko_point = None;
+                    passes += 1;
+                }
+            }
+            turn = !turn;
+        }
+    }
+
+    /// Picks a random legal point which isn't an eye of the color, None to pass.
+    fn pick(&mut self, color: Color, ko_point: Option<Coord>, rng: &mut impl Rng) -> Option<Coord> {
+        self.candidates.clear();
+        self.candidates.extend(self.goban.get_empty_coords());
+        while !self.candidates.is_empty() {
+            let point = self.candidates.swap_remove(rng.random_range(0..self.candidates.len()));
+            if Some(point) != ko_point && !self.is_eye(point, color) && self.is_legal(point, color)
+            {
+                return Some(point);
+            }
+        }
+        None
+    }
+
+    /// An eye is a point surrounded by stones of the color.
+    fn is_eye(&self, point: Coord, color: Color) -> bool {
+        self.goban
+            .get_connected_points(point)
+            .all(|p| p.color == Some(color))
+    }
+
+    /// A point is legal if it isn't a suicide.
+    fn is_legal(&self, point: Coord, color: Color) -> bool {
+        self.goban.point_has_liberty(point)
+            || self.goban.get_connected_groups(point).iter().any(|group| {
+                if group.color == color {
+                    !group.is_atari()
+                } else {
+                    group.is_atari()
+                }
+            })
+    }
+}
@@ -0,0 +1,97 @@
+//! Pool of games for the searches copying a position again and again.
+//!
+//! The games given back to the pool keep their buffers, the next [`GamePool::acquire`] copies
+//! the template into them with `clone_from` instead of allocating a new game.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, PoisonError};
+
+use crate::rules::game::Game;
+
+/// Pool of copies of a template position, see the [module](self).
+#[derive(Debug)]
+pub struct GamePool {
+    template: Game,
+    idle: Mutex<Vec<Game>>,
+    /// Maximum number of games kept by the pool.
+    cap: usize,
+}
+
+impl GamePool {
+    /// Creates an empty pool of copies of the template, which keeps at most `cap` games given
+    /// back. More games can be acquired at the same time, the extra ones are dropped.
+    pub fn new(template: Game, cap: usize) -> Self {
+        GamePool {
+            template,
+            idle: Mutex::new(Vec::with_capacity(cap)),
+            cap,
+        }
+    }
+
+    pub fn template(&self) -> &Game {
+        &self.template
+    }
+
+    /// Number of games waiting in the pool.
+    pub fn idle(&self) -> usize {
+        self.idle.lock().unwrap_or_else(PoisonError::into_inner).len()
+    }
+
+    /// Returns a copy of the template, in a game of the pool if one is waiting. Everything of
+    /// the previous use is replaced: the goban, the history, the caches and the annotations.
+    pub fn acquire(&self) -> PooledGame<'_> {
+        let idle = self
+            .idle
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop();
+        let game = match idle {
+            Some(mut game) => {
+                game.clone_from(&self.template);
+                game
+            }
+            None => self.template.clone(),
+        };
+        PooledGame {
+            pool: self,
+            game: Some(game),
+        }
+    }
+}
+
+/// Game of a [`GamePool`], given back to the pool when dropped.
+#[derive(Debug)]
+pub struct PooledGame<'a> {
+    pool: &'a GamePool,
+    /// Always Some, taken when dropped.
+    game: Option<Game>,
+}
+
+impl Deref for PooledGame<'_> {
+    type Target = Game;
+
+    fn deref(&self) -> &Self::Target {
+        self.game.as_ref().expect("The game is only taken when dropped")
+    }
+}
+
+impl DerefMut for PooledGame<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.game.as_mut().expect("The game is only taken when dropped")
+    }
+}
+
+impl Drop for PooledGame<'_> {
+    fn drop(&mut self) {
+        if let Some(game) = self.game.take() {
+            let mut idle = self
+                .pool
+                .idle
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if idle.len() < self.pool.cap {
+                idle.push(game);
+            }
+        }
+    }
+}
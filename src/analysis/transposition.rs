@@ -0,0 +1,96 @@
+//! Transposition table shared by the threads of a parallel search.
+//!
+//! The entries are keyed by the zobrist hash of the position. The table has a fixed number of
+//! slots split in shards, each shard behind its own lock so the threads rarely wait for each
+//! other. A slot keeps the full hash of its entry, two positions falling in the same slot
+//! aren't mistaken for each other, the last one inserted replaces the other.
+
+use std::sync::{PoisonError, RwLock, TryLockError};
+
+/// Slots of a shard, an entry keeps the full hash of its position.
+type Shard<V> = RwLock<Box<[Option<(u64, V)>]>>;
+
+/// Transposition table for parallel searches, see the [module](self).
+#[derive(Debug)]
+pub struct ConcurrentTranspositionTable<V> {
+    shards: Box<[Shard<V>]>,
+    /// The low bits of the hash choosing the shard.
+    shard_bits: u32,
+    slots_per_shard: usize,
+}
+
+impl<V: Clone> ConcurrentTranspositionTable<V> {
+    /// Creates a table of at least `capacity` slots split in `shards` shards, rounded up to a
+    /// power of two. Some more shards than threads keep the contention low.
+    pub fn new(capacity: usize, shards: usize) -> Self {
+        let shards = shards.max(1).next_power_of_two();
+        let slots_per_shard = capacity.div_ceil(shards).max(1);
+        ConcurrentTranspositionTable {
+            shards: (0..shards)
+                .map(|_| RwLock::new(vec![None; slots_per_shard].into_boxed_slice()))
+                .collect(),
+            shard_bits: shards.trailing_zeros(),
+            slots_per_shard,
+        }
+    }
+
+    /// Number of entries the table can hold.
+    pub fn capacity(&self) -> usize {
+        self.shards.len() * self.slots_per_shard
+    }
+
+    /// Returns the value of the position, waiting if another thread is writing to its shard.
+    pub fn get(&self, hash: u64) -> Option<V> {
+        let (shard, slot) = self.locate(hash);
+        let slots = self.shards[shard]
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        Self::entry(&slots[slot], hash)
+    }
+
+    /// Returns the value of the position without waiting: None if another thread is writing to
+    /// its shard, the search then goes on as if the position wasn't known.
+    pub fn probe(&self, hash: u64) -> Option<V> {
+        let (shard, slot) = self.locate(hash);
+        let slots = match self.shards[shard].try_read() {
+            Ok(slots) => slots,
+            Err(TryLockError::Poisoned(error)) => error.into_inner(),
+            Err(TryLockError::WouldBlock) => return None,
+        };
+        Self::entry(&slots[slot], hash)
+    }
+
+    /// Stores the value of the position, replacing the entry of its slot.
+    pub fn insert(&self, hash: u64, value: V) {
+        let (shard, slot) = self.locate(hash);
+        let mut slots = self.shards[shard]
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        slots[slot] = Some((hash, value));
+    }
+
+    /// Removes all the entries.
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            shard
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .fill(None);
+        }
+    }
+
+    /// Shard and slot of the hash.
+    fn locate(&self, hash: u64) -> (usize, usize) {
+        let shard = (hash & (self.shards.len() as u64 - 1)) as usize;
+        let slot = ((hash >> self.shard_bits) % self.slots_per_shard as u64) as usize;
+        (shard, slot)
+    }
+
+    /// The value of the entry if it's the one of the hash.
+    fn entry(entry: &Option<(u64, V)>, hash: u64) -> Option<V> {
+        entry
+            .as_ref()
+            .filter(|(key, _)| *key == hash)
+            .map(|(_, value)| value.clone())
+    }
+}
@@ -0,0 +1,680 @@
+//! Engine side of the Go Text Protocol, version 2.
+//!
+//! The [`GtpEngine`] keeps the game and answers the commands of a controller, the moves are
+//! chosen by a [`GoPlayer`].
+//! # Example
+//! ```
+//! use goban::gtp::{GoPlayer, GtpEngine};
+//! use goban::pieces::stones::Color;
+//! use goban::rules::game::Game;
+//! use goban::rules::Move;
+//!
+//! struct Passer;
+//!
+//! impl GoPlayer for Passer {
+//!     fn genmove(&mut self, _game: &Game, _color: Color) -> Move {
+//!         Move::Pass
+//!     }
+//! }
+//!
+//! let mut engine = GtpEngine::new(Passer);
+//! let input = "boardsize 9\nplay black E5\n2 genmove white\nquit\n";
+//! let mut output = vec![];
+//! engine.run(input.as_bytes(), &mut output).unwrap();
+//! assert_eq!(String::from_utf8(output).unwrap(), "= \n\n= \n\n=2 pass\n\n= \n\n");
+//! ```
+
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+use crate::analysis::estimate_score;
+use crate::pieces::goban::{Goban, GroupIdx};
+use crate::pieces::stones::{Color, MaybeColor};
+use crate::pieces::util::coord::{Coord, Size};
+use crate::rules::clock::{TimeControl, TimeLeft};
+use crate::rules::game::Game;
+use crate::rules::{fixed_handicap_points, EndGame, GobanSizes, Move, CHINESE};
+
+/// Columns of the vertices, the letter I is skipped.
+const COLUMNS: &str = "ABCDEFGHJKLMNOPQRSTUVWXYZ";
+
+/// Commands known by the engine.
+const COMMANDS: &[&str] = &[
+    "boardsize",
+    "clear_board",
+    "final_score",
+    "final_status_list",
+    "fixed_handicap",
+    "genmove",
+    "kgs-time_settings",
+    "known_command",
+    "komi",
+    "list_commands",
+    "loadsgf",
+    "name",
+    "place_free_handicap",
+    "play",
+    "protocol_version",
+    "quit",
+    "reg_genmove",
+    "set_free_handicap",
+    "showboard",
+    "time_left",
+    "time_settings",
+    "undo",
+    "version",
+];
+
+/// Player choosing the moves of the engine.
+pub trait GoPlayer {
+    /// Chooses a move for the color, the move must be legal or a pass or a resign.
+    /// The time left given by the controller is in the [clock](Game::clock) of the game.
+    fn genmove(&mut self, game: &Game, color: Color) -> Move;
+
+    /// Chooses where to put `stones` free handicap stones on the empty goban. None to use the
+    /// fixed handicap points.
+    fn place_free_handicap(&mut self, _game: &Game, _stones: usize) -> Option<Vec<Coord>> {
+        None
+    }
+
+    /// Name of the engine given by the `name` command.
+    fn name(&self) -> String {
+        env!("CARGO_PKG_NAME").to_string()
+    }
+
+    /// Version of the engine given by the `version` command.
+    fn version(&self) -> String {
+        env!("CARGO_PKG_VERSION").to_string()
+    }
+}
+
+/// Engine answering the commands of a GTP controller.
+#[derive(Debug)]
+pub struct GtpEngine<P> {
+    game: Game,
+    player: P,
+    quit: bool,
+}
+
+/// Response to a command, the text of a success or the message of a failure.
+type Response = Result<String, String>;
+
+impl<P: GoPlayer> GtpEngine<P> {
+    /// Engine with a 19x19 game and the chinese rules.
+    pub fn new(player: P) -> Self {
+        GtpEngine {
+            game: Game::new(GobanSizes::Nineteen, CHINESE),
+            player,
+            quit: false,
+        }
+    }
+
+    #[inline]
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    #[inline]
+    pub fn player(&self) -> &P {
+        &self.player
+    }
+
+    /// Reads the commands until `quit` or the end of the input, and writes the responses.
+    ///
+    /// # Errors
+    /// If reading the input or writing the output fails.
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if let Some(response) = self.execute(&line) {
+                output.write_all(response.as_bytes())?;
+                output.flush()?;
+            }
+            if self.quit {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Executes a line of the controller and returns the full response, with the id of the
+    /// command and the empty line ending it. Returns None for empty and comment lines.
+    pub fn execute(&mut self, line: &str) -> Option<String> {
+        let line = preprocess(line);
+        let mut words = line.split_whitespace();
+        let first = words.next()?;
+        let (id, command) = if first.chars().all(|c| c.is_ascii_digit()) {
+            (first, words.next().unwrap_or_default())
+        } else {
+            ("", first)
+        };
+        let args: Vec<&str> = words.collect();
+        let (status, text) = match self.command(command, &args) {
+            Ok(text) => ('=', text),
+            Err(text) => ('?', text),
+        };
+        Some(if text.is_empty() {
+            format!("{status}{id} \n\n")
+        } else {
+            format!("{status}{id} {text}\n\n")
+        })
+    }
+
+    fn command(&mut self, command: &str, args: &[&str]) -> Response {
+        match command {
+            "protocol_version" => Ok("2".to_string()),
+            "name" => Ok(self.player.name()),
+            "version" => Ok(self.player.version()),
+            "known_command" => {
+                let name = args.first().ok_or("syntax error")?;
+                Ok(COMMANDS.contains(name).to_string())
+            }
+            "list_commands" => Ok(COMMANDS.join("\n")),
+            "quit" => {
+                self.quit = true;
+                Ok(String::new())
+            }
+            "boardsize" => self.boardsize(args),
+            "clear_board" => {
+                self.clear_board(self.game.size());
+                Ok(String::new())
+            }
+            "komi" => {
+                let komi = args.first().and_then(|komi| komi.parse().ok());
+                self.game.set_komi(komi.ok_or("syntax error")?);
+                Ok(String::new())
+            }
+            "play" => self.play(args),
+            "genmove" => self.genmove(args),
+            "reg_genmove" => self.reg_genmove(args),
+            "loadsgf" => self.loadsgf(args),
+            "undo" => self
+                .game
+                .undo()
+                .map(|_| String::new())
+                .ok_or_else(|| "cannot undo".to_string()),
+            "showboard" => Ok(showboard(&self.game)),
+            "fixed_handicap" => self.fixed_handicap(args),
+            "place_free_handicap" => self.place_free_handicap(args),
+            "set_free_handicap" => self.set_free_handicap(args),
+            "time_settings" => self.time_settings(args),
+            "kgs-time_settings" => self.kgs_time_settings(args),
+            "time_left" => self.time_left(args),
+            "final_score" => Ok(self.final_score()),
+            "final_status_list" => self.final_status_list(args),
+            _ => Err("unknown command".to_string()),
+        }
+    }
+
+    fn boardsize(&mut self, args: &[&str]) -> Response {
+        let size: u8 = args
+            .first()
+            .and_then(|size| size.parse().ok())
+            .ok_or("syntax error")?;
+        if !(2..=19).contains(&size) {
+            return Err("unacceptable size".to_string());
+        }
+        self.clear_board((size, size));
+        Ok(String::new())
+    }
+
+    /// New game of the size with the rule, komi and time settings of the current game.
+    fn clear_board(&mut self, (height, width): Size) {
+        let size = GobanSizes::Custom(height as usize, width as usize);
+        let control = self.game.clock().control();
+        self.game = Game::new(size, self.game.rule());
+        self.game.clock_mut().set_control(control);
+    }
+
+    fn play(&mut self, args: &[&str]) -> Response {
+        let [color, vertex] = args else {
+            return Err("syntax error".to_string());
+        };
+        let color = parse_color(color).ok_or("syntax error")?;
+        let play = parse_vertex(vertex, self.game.size()).ok_or("syntax error")?;
+        self.game
+            .try_play_as(color, play)
+            .map_err(|_| "illegal move".to_string())?;
+        Ok(String::new())
+    }
+
+    fn genmove(&mut self, args: &[&str]) -> Response {
+        let color = args
+            .first()
+            .and_then(|color| parse_color(color))
+            .ok_or("syntax error")?;
+        let play = self.player.genmove(&self.game, color);
+        self.game
+            .try_play_as(color, play)
+            .map_err(|e| format!("the player chose an illegal move: {e}"))?;
+        Ok(move_to_vertex(play, self.game.size()))
+    }
+
+    /// The move the player would choose, it isn't played.
+    fn reg_genmove(&mut self, args: &[&str]) -> Response {
+        let color = args
+            .first()
+            .and_then(|color| parse_color(color))
+            .ok_or("syntax error")?;
+        let play = self.player.genmove(&self.game, color);
+        Ok(move_to_vertex(play, self.game.size()))
+    }
+
+    /// Replaces the game by the main line of the SGF file, up to the position before the move
+    /// number if given. The file is decoded with the charset of its `CA` property, see
+    /// [`Game::from_sgf_bytes`]. The time settings are kept.
+    fn loadsgf(&mut self, args: &[&str]) -> Response {
+        let (path, move_number) = match args {
+            [path] => (path, None),
+            [path, number] => match number.parse::<usize>() {
+                Ok(number) if number >= 1 => (path, Some(number)),
+                _ => return Err("syntax error".to_string()),
+            },
+            _ => return Err("syntax error".to_string()),
+        };
+        let (mut game, _) = fs::read(path)
+            .ok()
+            .and_then(|sgf| Game::from_sgf_bytes(&sgf).ok())
+            .ok_or("cannot load file")?;
+        if let Some(number) = move_number {
+            while game.moves().count() >= number {
+                game.undo();
+            }
+        }
+        game.clock_mut().set_control(self.game.clock().control());
+        self.game = game;
+        Ok(String::new())
+    }
+
+    /// Canadian overtime, a period of 0 is the absolute time and 0 stones with a period no
+    /// time limit. `0 0`, with or without stones, is also no time limit.
+    fn time_settings(&mut self, args: &[&str]) -> Response {
+        let numbers = parse_numbers(args).ok_or("syntax error")?;
+        let control = match numbers[..] {
+            [0, 0] | [0, 0, _] => TimeControl::None,
+            [main, 0, _] => TimeControl::Absolute { main: secs(main) },
+            [_, _, 0] => TimeControl::None,
+            [main, period, stones] => TimeControl::Canadian {
+                main: secs(main),
+                period: secs(period),
+                stones,
+            },
+            _ => return Err("syntax error".to_string()),
+        };
+        self.game.clock_mut().set_control(control);
+        Ok(String::new())
+    }
+
+    /// The time settings of the KGS extension, `none`, `absolute main`, `byoyomi main period
+    /// periods` or `canadian main period stones`. The canadian overtime is read like
+    /// `time_settings`, a byo-yomi without periods is the absolute time.
+    fn kgs_time_settings(&mut self, args: &[&str]) -> Response {
+        let (kind, args) = args.split_first().ok_or("syntax error")?;
+        let numbers = parse_numbers(args).ok_or("syntax error")?;
+        let control = match (kind.to_ascii_lowercase().as_str(), &numbers[..]) {
+            ("none", []) => TimeControl::None,
+            ("absolute", &[main]) | ("byoyomi", &[main, _, 0]) => {
+                TimeControl::Absolute { main: secs(main) }
+            }
+            ("byoyomi", &[main, period, periods]) => TimeControl::ByoYomi {
+                main: secs(main),
+                period: secs(period),
+                periods,
+            },
+            ("canadian", [_, _, _]) => return self.time_settings(args),
+            _ => return Err("syntax error".to_string()),
+        };
+        self.game.clock_mut().set_control(control);
+        Ok(String::new())
+    }
+
+    /// The time left of a player, the stones are 0 in the main time.
+    fn time_left(&mut self, args: &[&str]) -> Response {
+        let [color, time, stones] = args else {
+            return Err("syntax error".to_string());
+        };
+        let color = parse_color(color).ok_or("syntax error")?;
+        let numbers = parse_numbers(&[time, stones]).ok_or("syntax error")?;
+        let time_left = TimeLeft::overtime(secs(numbers[0]), numbers[1]);
+        self.game.clock_mut().set_time_left(color, time_left);
+        Ok(String::new())
+    }
+
+    /// The result of the game if it ended by resign, time or forfeit, else the score by the
+    /// rule of the game with the stones of [`dead_chains`] removed as prisoners, like the
+    /// scoring phase.
+    fn final_score(&self) -> String {
+        if let Some(
+            outcome @ (EndGame::WinnerByResign(_)
+            | EndGame::WinnerByTime(_)
+            | EndGame::WinnerByForfeit(_)),
+        ) = self.game.outcome()
+        {
+            return result(outcome);
+        }
+        let rule = self.game.rule().flag_score;
+        let score = self
+            .game
+            .calculate_score_with_dead(rule, &dead_chains(&self.game));
+        result(EndGame::from_score_by(score, rule))
+    }
+
+    /// The chains with the status, one chain by line. The estimation doesn't recognize the
+    /// seki, so the seki list is always empty.
+    fn final_status_list(&self, args: &[&str]) -> Response {
+        let dead = dead_chains(&self.game);
+        let goban = self.game.goban();
+        let chains: Vec<GroupIdx> = match args.first().copied() {
+            Some("dead") => dead,
+            Some("alive") => goban
+                .get_groups()
+                .map(|(chain, _)| chain)
+                .filter(|chain| !dead.contains(chain))
+                .collect(),
+            Some("seki") => vec![],
+            _ => return Err("syntax error".to_string()),
+        };
+        let lines: Vec<String> = chains
+            .into_iter()
+            .map(|chain| {
+                let mut stones: Vec<Coord> = goban.chain_stones(chain).map(|s| s.coord).collect();
+                stones.sort_unstable();
+                vertices(&stones, self.game.size())
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+
+    fn fixed_handicap(&mut self, args: &[&str]) -> Response {
+        let stones = self.handicap_stones(args)?;
+        let points =
+            fixed_handicap_points(self.game.size(), stones).ok_or("invalid number of stones")?;
+        self.game.put_handicap(&points);
+        Ok(vertices(&points, self.game.size()))
+    }
+
+    fn place_free_handicap(&mut self, args: &[&str]) -> Response {
+        let stones = self.handicap_stones(args)?;
+        let size = self.game.size();
+        let chosen = self
+            .player
+            .place_free_handicap(&self.game, stones)
+            .filter(|points| valid_free_handicap(points, size, stones));
+        // The engine can put less stones than asked when they don't fit on the fixed points.
+        let points = chosen
+            .or_else(|| {
+                (2..=stones)
+                    .rev()
+                    .find_map(|n| fixed_handicap_points(size, n))
+            })
+            .ok_or("invalid number of stones")?;
+        self.game.put_handicap(&points);
+        Ok(vertices(&points, size))
+    }
+
+    fn set_free_handicap(&mut self, args: &[&str]) -> Response {
+        self.check_empty()?;
+        let size = self.game.size();
+        let points = args
+            .iter()
+            .map(|vertex| match parse_vertex(vertex, size) {
+                Some(Move::Play(row, column)) => Some((row, column)),
+                _ => None,
+            })
+            .collect::<Option<Vec<Coord>>>()
+            .filter(|points| valid_free_handicap(points, size, points.len()))
+            .ok_or("bad vertex list")?;
+        self.game.put_handicap(&points);
+        Ok(String::new())
+    }
+
+    /// Number of handicap stones of the arguments, the goban must be empty.
+    fn handicap_stones(&self, args: &[&str]) -> Result<usize, String> {
+        let stones: usize = args
+            .first()
+            .and_then(|stones| stones.parse().ok())
+            .ok_or("syntax error")?;
+        self.check_empty()?;
+        let (height, width) = self.game.size();
+        if stones < 2 || stones >= height as usize * width as usize {
+            return Err("invalid number of stones".to_string());
+        }
+        Ok(stones)
+    }
+
+    fn check_empty(&self) -> Result<(), String> {
+        if self.game.goban().get_stones().next().is_some() {
+            Err("board not empty".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Free handicap stones are at least two different points, with at least one point left.
+fn valid_free_handicap(points: &[Coord], (height, width): Size, stones: usize) -> bool {
+    let mut sorted = points.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    points.len() == stones
+        && sorted.len() == stones
+        && stones >= 2
+        && stones < height as usize * width as usize
+        && points
+            .iter()
+            .all(|&(row, column)| row < height && column < width)
+}
+
+/// Removes the comments and the control characters, tabs are spaces.
+fn preprocess(line: &str) -> String {
+    let line = line.split('#').next().unwrap_or_default();
+    line.chars()
+        .filter_map(|c| match c {
+            '\t' => Some(' '),
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .collect()
+}
+
+/// Chains estimated dead by [`estimate_score`], by the first stone in row major order.
+fn dead_chains(game: &Game) -> Vec<GroupIdx> {
+    let goban = game.goban();
+    let mut chains: Vec<GroupIdx> = vec![];
+    for point in estimate_score(goban, game.komi()).dead {
+        let chain = goban
+            .chain_idx(point)
+            .expect("A dead stone is on the goban");
+        if !chains.contains(&chain) {
+            chains.push(chain);
+        }
+    }
+    chains
+}
+
+/// The result like `B+3.5`, `W+Resign` or `0` for a draw.
+fn result(outcome: EndGame) -> String {
+    let winner = |color| match color {
+        Color::Black => "B",
+        Color::White => "W",
+    };
+    match outcome {
+        EndGame::WinnerByScore(color, margin) => format!("{}+{margin}", winner(color)),
+        EndGame::WinnerByResign(color) => format!("{}+Resign", winner(color)),
+        EndGame::WinnerByTime(color) => format!("{}+Time", winner(color)),
+        EndGame::WinnerByForfeit(color) => format!("{}+Forfeit", winner(color)),
+        EndGame::Draw => "0".to_string(),
+    }
+}
+
+fn parse_numbers(args: &[&str]) -> Option<Vec<u32>> {
+    args.iter().map(|arg| arg.parse().ok()).collect()
+}
+
+fn secs(seconds: u32) -> Duration {
+    Duration::from_secs(seconds as u64)
+}
+
+pub(crate) fn parse_color(color: &str) -> Option<Color> {
+    match color.to_ascii_lowercase().as_str() {
+        "b" | "black" => Some(Color::Black),
+        "w" | "white" => Some(Color::White),
+        _ => None,
+    }
+}
+
+/// Reads a vertex like `D4` or `pass`, the rows are numbered from the bottom.
+pub fn parse_vertex(vertex: &str, (height, width): Size) -> Option<Move> {
+    let vertex = vertex.to_ascii_uppercase();
+    if vertex == "PASS" {
+        return Some(Move::Pass);
+    }
+    let mut chars = vertex.chars();
+    let column = COLUMNS.find(chars.next()?)? as u8;
+    let row: u8 = chars.as_str().parse().ok()?;
+    (column < width && (1..=height).contains(&row)).then_some(Move::Play(height - row, column))
+}
+
+/// Writes the point as a vertex, the reverse of [`parse_vertex`].
+pub fn vertex((row, column): Coord, (height, _): Size) -> String {
+    let letter = COLUMNS.as_bytes()[column as usize] as char;
+    format!("{letter}{}", height - row)
+}
+
+pub(crate) fn move_to_vertex(play: Move, size: Size) -> String {
+    match play {
+        Move::Play(row, column) => vertex((row, column), size),
+        Move::Pass => "pass".to_string(),
+        Move::Resign(_) => "resign".to_string(),
+    }
+}
+
+fn vertices(points: &[Coord], size: Size) -> String {
+    points
+        .iter()
+        .map(|&point| vertex(point, size))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The goban of [`Goban::to_gtp_board_string`] after a line break, as GTP answers it.
+fn showboard(game: &Game) -> String {
+    let board = game.goban().to_gtp_board_string();
+    format!("\n{}", board.trim_end())
+}
+
+/// Errors when reading a goban with [`Goban::from_gtp_board_string`].
+#[derive(Clone, Eq, PartialEq, Debug, Copy)]
+pub enum BoardStringError {
+    /// No line starts with a row number followed by points.
+    NoRows,
+    /// The rows aren't numbered from the size of the goban down to 1.
+    RowNumber(u8),
+    /// The row hasn't the same number of points as the first one.
+    RowWidth(u8),
+    /// The character isn't a point.
+    Point(char),
+    /// The goban is larger than 19x19.
+    TooLarge,
+}
+
+impl Display for BoardStringError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardStringError::NoRows => write!(f, "no row found"),
+            BoardStringError::RowNumber(row) => write!(f, "the row {row} is out of order"),
+            BoardStringError::RowWidth(row) => write!(f, "the row {row} has a different width"),
+            BoardStringError::Point(c) => write!(f, "{c:?} isn't a point"),
+            BoardStringError::TooLarge => write!(f, "the goban is larger than 19x19"),
+        }
+    }
+}
+
+impl std::error::Error for BoardStringError {}
+
+impl Goban {
+    /// The goban like the `showboard` of GTP engines: a `X` for black, a `O` for white and a
+    /// `.` for an empty point, with the column letters above and below and the row numbers,
+    /// from the bottom, on both sides.
+    pub fn to_gtp_board_string(&self) -> String {
+        let (height, width) = self.size();
+        let columns: String = COLUMNS[..width as usize]
+            .chars()
+            .flat_map(|c| [' ', c])
+            .collect();
+        let mut board = format!("  {columns}\n");
+        for row in 0..height {
+            board += &format!("{:>2}", height - row);
+            for column in 0..width {
+                board.push(' ');
+                board.push(match self.get_color((row, column)) {
+                    Some(Color::Black) => 'X',
+                    Some(Color::White) => 'O',
+                    None => '.',
+                });
+            }
+            board += &format!(" {}\n", height - row);
+        }
+        board + "  " + &columns + "\n"
+    }
+
+    /// Reads a goban written like [`Goban::to_gtp_board_string`] or the `showboard` of GNU Go.
+    /// The lines which don't start with a row number are skipped, like the letters and the
+    /// response marker, and what follows the points on a row is ignored, like the row number
+    /// again or the prisoners. A `+` is an empty star point, and the parentheses around the
+    /// last move are ignored. The size of the goban is given by the rows found.
+    ///
+    /// # Errors
+    /// If there is no row, if the rows aren't numbered down to 1, if they don't have the same
+    /// width or if a point isn't `X`, `O`, `.` or `+`.
+    pub fn from_gtp_board_string(board: &str) -> Result<Goban, BoardStringError> {
+        let mut rows: Vec<(u8, Vec<MaybeColor>)> = vec![];
+        for line in board.lines() {
+            let line = line.trim_start();
+            let digits = line
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(line.len());
+            let Ok(number) = line[..digits].parse::<u8>() else {
+                continue;
+            };
+            let mut points = vec![];
+            for c in line[digits..].chars() {
+                match c {
+                    'X' | 'x' => points.push(Some(Color::Black)),
+                    'O' | 'o' => points.push(Some(Color::White)),
+                    '.' | '+' => points.push(None),
+                    ' ' | '\t' | '(' | ')' => (),
+                    c if c.is_ascii_digit() => break,
+                    c => return Err(BoardStringError::Point(c)),
+                }
+            }
+            rows.push((number, points));
+        }
+
+        let height = rows.len();
+        let width = rows.first().map_or(0, |(_, points)| points.len());
+        if width == 0 {
+            return Err(BoardStringError::NoRows);
+        }
+        if height > 19 || width > 19 {
+            return Err(BoardStringError::TooLarge);
+        }
+        let mut goban = Goban::new((height as u8, width as u8));
+        for (row, (number, points)) in rows.into_iter().enumerate() {
+            if number as usize != height - row {
+                return Err(BoardStringError::RowNumber(number));
+            }
+            if points.len() != width {
+                return Err(BoardStringError::RowWidth(number));
+            }
+            for (column, point) in points.into_iter().enumerate() {
+                if let Some(color) = point {
+                    goban.push((row as u8, column as u8), color);
+                }
+            }
+        }
+        Ok(goban)
+    }
+}
@@ -1,5 +1,13 @@
 #[doc = include_str ! ("../README.md")]
 #[macro_use]
 extern crate bitflags;
+pub mod analysis;
+pub mod gtp;
 pub mod pieces;
+#[cfg(feature = "random")]
+mod random;
 pub mod rules;
+pub mod sgf;
+#[cfg(feature = "proptest")]
+pub mod testing;
+pub mod training;
@@ -1,30 +1,278 @@
 //! Module with the goban and his implementations.
 
-use crate::one2dim;
 use crate::pieces::group::CircularGroupIter;
-use crate::pieces::group::{merge, set, Group, Groups, Liberties, EMPTY_LIBERTIES};
+use crate::pieces::group::{
+    count_ones, get, iter_ones, merge, set, Group, Groups, Liberties, EMPTY_LIBERTIES,
+};
 use crate::pieces::stones::*;
+use crate::pieces::symmetry::Symmetry;
 use crate::pieces::util::coord::{
-    one_to_2dim, two_to_1dim, valid_coords, Coord, IntoCoord, IntoIdx, Size,
+    is_coord_valid, one_to_2dim, sgf_to_coord, two_to_1dim, valid_coords, Coord, IntoCoord,
+    IntoIdx, SgfCoordError, Size,
 };
 use crate::pieces::zobrist::*;
 use crate::pieces::{Connections, Nat};
 use arrayvec::ArrayVec;
 use nonmax::NonMaxU16;
+use std::fmt::Debug;
 use std::fmt::Display;
 use std::fmt::Error;
 use std::fmt::Formatter;
 use std::hash::{Hash, Hasher};
+use std::num::TryFromIntError;
+use std::ops::Index;
 
 pub type GroupIdx = usize;
-pub type BoardIdx = usize;
 
 const BOARD_MAX_SIZE: (Nat, Nat) = (19, 19);
 const BOARD_MAX_LENGTH: usize = BOARD_MAX_SIZE.0 as usize * BOARD_MAX_SIZE.1 as usize;
+
+/// Flat index of a point in row major order, `row * width + column`. Any point of a goban of
+/// at most 255x255 fits in 16 bits, it's the index of the chains, the bitboards and the move
+/// encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct BoardIdx(u16);
+
+impl BoardIdx {
+    /// Index of the point on a goban of the size.
+    ///
+    /// # Panics
+    /// In debug builds, if the point is outside the goban.
+    #[inline(always)]
+    pub const fn from_coord((height, width): Size, (row, column): Coord) -> Self {
+        debug_assert!(row < height && column < width, "point outside the goban");
+        BoardIdx(row as u16 * width as u16 + column as u16)
+    }
+
+    /// Point of the index on a goban of the size.
+    #[inline(always)]
+    pub const fn to_coord(self, (_, width): Size) -> Coord {
+        ((self.0 / width as u16) as Nat, (self.0 % width as u16) as Nat)
+    }
+
+    /// The index to access the slices.
+    #[inline(always)]
+    pub const fn get(self) -> usize {
+        self.0 as usize
+    }
+
+    /// Indexes of all the points of a goban of the size, in row major order.
+    #[inline]
+    pub fn all((height, width): Size) -> impl Iterator<Item = BoardIdx> {
+        (0..height as u16 * width as u16).map(BoardIdx)
+    }
+}
+
+impl From<u16> for BoardIdx {
+    #[inline(always)]
+    fn from(index: u16) -> Self {
+        BoardIdx(index)
+    }
+}
+
+impl From<BoardIdx> for u16 {
+    #[inline(always)]
+    fn from(index: BoardIdx) -> Self {
+        index.0
+    }
+}
+
+impl From<BoardIdx> for usize {
+    #[inline(always)]
+    fn from(index: BoardIdx) -> Self {
+        index.get()
+    }
+}
+
+/// Fails if the index doesn't fit in 16 bits.
+impl TryFrom<usize> for BoardIdx {
+    type Error = TryFromIntError;
+
+    #[inline]
+    fn try_from(index: usize) -> Result<Self, Self::Error> {
+        u16::try_from(index).map(BoardIdx)
+    }
+}
+
+impl Display for BoardIdx {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Indexes of a goban with their points in row major order. The point is moved along the
+/// index instead of being divided out of it, for the iterators over the whole goban.
+#[derive(Clone, Debug)]
+struct IndexedPoints {
+    index: u16,
+    end: u16,
+    width: Nat,
+    coord: Coord,
+}
+
+impl IndexedPoints {
+    #[inline]
+    fn new((height, width): Size) -> Self {
+        IndexedPoints {
+            index: 0,
+            end: height as u16 * width as u16,
+            width,
+            coord: (0, 0),
+        }
+    }
+}
+
+impl Iterator for IndexedPoints {
+    type Item = (BoardIdx, Coord);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index == self.end {
+            return None;
+        }
+        let item = (BoardIdx(self.index), self.coord);
+        self.index += 1;
+        self.coord.1 += 1;
+        if self.coord.1 == self.width {
+            self.coord = (self.coord.0 + 1, 0);
+        }
+        Some(item)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.end - self.index) as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for IndexedPoints {}
+
+/// Value stored on the board for the chain. The chains are only reused by a rollback, so a goban
+/// can't hold more than `u16::MAX` chains in its life.
+///
+/// # Panics
+/// If the index doesn't fit.
+#[inline(always)]
+fn chain_id(chain_idx: GroupIdx) -> NonMaxU16 {
+    u16::try_from(chain_idx)
+        .ok()
+        .and_then(NonMaxU16::new)
+        .expect("too many chains created on the goban")
+}
+
 const MAX_CHAINS: usize = 4 * BOARD_MAX_LENGTH / 5;
 
+/// Entry of the transaction journal, the point and the color it had before the mutation.
+type JournalEntry = (Coord, MaybeColor);
+
+/// The color, the size and the liberties of a chain, see [`Goban::chain_info`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChainInfo {
+    pub color: Color,
+    pub stone_count: usize,
+    pub liberty_count: usize,
+    /// The smallest stone of the chain in row major order.
+    pub first_stone: Coord,
+}
+
+/// Errors returned by the goban when an operation can't be done.
+#[derive(Clone, Eq, PartialEq, Debug, Copy)]
+pub enum GobanError {
+    /// A transaction is already running, nested transactions are not supported.
+    TransactionAlreadyActive,
+    /// Commit or rollback was called without any running transaction.
+    NoActiveTransaction,
+    /// The point of the stone isn't on the goban.
+    OutsideBoard(Coord),
+    /// A stone is already on the point.
+    Occupied(Coord),
+    /// The buffer of [`Goban::snapshot_into`] doesn't have one byte per point.
+    BufferLength { expected: usize, actual: usize },
+}
+
+/// Invariant of the goban or of the game found broken by `check_invariants`.
+#[derive(Clone, Eq, PartialEq, Debug, Copy)]
+pub enum InvariantViolation {
+    /// A stone is stored after the last intersection of the goban.
+    StoneOutsideBoard { index: BoardIdx },
+    /// The chain contains a stone whose intersection points to another chain.
+    BoardChainMismatch { coord: Coord, chain: GroupIdx },
+    /// The stone is in the stones of two chains.
+    StoneInTwoChains { coord: Coord },
+    /// The intersection points to a chain that was removed.
+    DanglingChain { coord: Coord, chain: GroupIdx },
+    /// The intersection points to a chain that doesn't contain the stone.
+    StoneNotInChain { coord: Coord, chain: GroupIdx },
+    /// The origin of the chain isn't its smallest stone.
+    WrongOrigin { chain: GroupIdx },
+    /// The last stone stored in the chain isn't the last one of its stones.
+    WrongLast { chain: GroupIdx },
+    /// The number of stones stored in the chain differs from the stones really in the chain.
+    WrongStoneCount {
+        chain: GroupIdx,
+        stored: usize,
+        actual: usize,
+    },
+    /// The liberties of the chain disagree with the board at the coordinate.
+    WrongLiberty { chain: GroupIdx, coord: Coord },
+    /// The stored zobrist hash differs from the one computed from the stones.
+    WrongHash { stored: u64, computed: u64 },
+    /// A chain without liberties is on the board of a game.
+    ChainWithoutLiberties { coord: Coord, chain: GroupIdx },
+    /// The number of hashes in the super ko history differs from the number of moves.
+    HistoryMismatch { moves: usize, hashes: usize },
+}
+
+impl Display for InvariantViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            InvariantViolation::StoneOutsideBoard { index } => {
+                write!(f, "stone stored outside the goban at index {index}")
+            }
+            InvariantViolation::BoardChainMismatch { coord, chain } => {
+                write!(f, "the chain {chain} has the stone {coord:?} but the board points elsewhere")
+            }
+            InvariantViolation::StoneInTwoChains { coord } => {
+                write!(f, "the stone {coord:?} is in two chains")
+            }
+            InvariantViolation::DanglingChain { coord, chain } => {
+                write!(f, "the point {coord:?} points to the removed chain {chain}")
+            }
+            InvariantViolation::StoneNotInChain { coord, chain } => {
+                write!(f, "the point {coord:?} points to the chain {chain} that doesn't contain it")
+            }
+            InvariantViolation::WrongOrigin { chain } => {
+                write!(f, "the origin of the chain {chain} isn't its smallest stone")
+            }
+            InvariantViolation::WrongLast { chain } => {
+                write!(f, "the last stone of the chain {chain} is wrong")
+            }
+            InvariantViolation::WrongStoneCount {
+                chain,
+                stored,
+                actual,
+            } => write!(f, "the chain {chain} stores {stored} stones but has {actual}"),
+            InvariantViolation::WrongLiberty { chain, coord } => {
+                write!(f, "the liberties of the chain {chain} are wrong at {coord:?}")
+            }
+            InvariantViolation::WrongHash { stored, computed } => {
+                write!(f, "the hash stored {stored:#x} differs from the computed {computed:#x}")
+            }
+            InvariantViolation::ChainWithoutLiberties { coord, chain } => {
+                write!(f, "the chain {chain} at {coord:?} has no liberties")
+            }
+            InvariantViolation::HistoryMismatch { moves, hashes } => {
+                write!(f, "{moves} moves were played but the history has {hashes} hashes")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvariantViolation {}
+
 /// Represents a goban. the stones are stored in ROW MAJOR (row, column)
-#[derive(Debug, Clone, Eq)]
+#[derive(Eq)]
 pub struct Goban {
     chains: Groups,
     /// The board contains indexes of the chains
@@ -32,11 +280,41 @@ pub struct Goban {
     next_stone: Vec<u16>,
     size: Size,
     zobrist_hash: u64,
+    /// Mutations recorded since the start of the transaction, None if no transaction is active.
+    journal: Option<Vec<JournalEntry>>,
+    /// Number of chain slots at the start of the transaction, a rollback gives back the others.
+    journal_chains: usize,
+}
+
+impl Clone for Goban {
+    fn clone(&self) -> Self {
+        Goban {
+            chains: self.chains.clone(),
+            board: self.board.clone(),
+            next_stone: self.next_stone.clone(),
+            size: self.size,
+            zobrist_hash: self.zobrist_hash,
+            journal: self.journal.clone(),
+            journal_chains: self.journal_chains,
+        }
+    }
+
+    /// Reuses the buffers of the goban, for the loops copying a position again and again.
+    fn clone_from(&mut self, source: &Self) {
+        self.chains.0.clone_from(&source.chains.0);
+        self.board.clone_from(&source.board);
+        self.next_stone.clone_from(&source.next_stone);
+        self.size = source.size;
+        self.zobrist_hash = source.zobrist_hash;
+        self.journal.clone_from(&source.journal);
+        self.journal_chains = source.journal_chains;
+    }
 }
 
 impl From<&[MaybeColor]> for Goban {
     fn from(stones: &[MaybeColor]) -> Self {
-        let size = (stones.len() as f32).sqrt() as u8;
+        // The size is checked by the goban, only a slice of a square goban is expected.
+        let size = u8::try_from(stones.len().isqrt()).unwrap_or(u8::MAX);
         let mut game = Goban::new((size, size));
         stones
             .iter()
@@ -50,6 +328,30 @@ impl From<&[MaybeColor]> for Goban {
     }
 }
 
+/// Collects the stones in a 19x19 goban, use [`Goban::from_stones`] for other sizes.
+impl FromIterator<Stone> for Goban {
+    fn from_iter<T: IntoIterator<Item = Stone>>(iter: T) -> Self {
+        Goban::from_stones(BOARD_MAX_SIZE, iter)
+    }
+}
+
+/// Pushes the stones with [`Goban::push`], so a stone overwrites the one already on its point.
+/// Stones out of the goban are skipped, in debug builds they panic.
+impl Extend<Stone> for Goban {
+    fn extend<T: IntoIterator<Item = Stone>>(&mut self, iter: T) {
+        for stone in iter {
+            debug_assert!(
+                is_coord_valid(self.size, stone.coord),
+                "Stone {:?} out of the goban",
+                stone.coord
+            );
+            if is_coord_valid(self.size, stone.coord) {
+                self.push_stone(stone);
+            }
+        }
+    }
+}
+
 impl Goban {
     /// Creates a Goban
     /// # Arguments
@@ -66,10 +368,19 @@ impl Goban {
             board: vec![None; BOARD_MAX_LENGTH],
             next_stone: vec![0; BOARD_MAX_LENGTH],
             chains: Groups::with_capacity(MAX_CHAINS),
+            journal: None,
+            journal_chains: 0,
             //free_slots: Default::default(),
         }
     }
 
+    /// Creates a goban of the given size with the stones, see [`Extend`] for the policy.
+    pub fn from_stones(size: Size, stones: impl IntoIterator<Item = Stone>) -> Self {
+        let mut goban = Goban::new(size);
+        goban.extend(stones);
+        goban
+    }
+
     pub fn size(&self) -> Size {
         self.size
     }
@@ -78,24 +389,132 @@ impl Goban {
         self.zobrist_hash
     }
 
-    // Returns all the groups in the goban even dead groups
+    /// Returns all the groups in the goban even dead groups.
+    /// The groups are ordered by their smallest stone in row major order.
     pub fn chains(&self) -> impl Iterator<Item = &Group> {
-        self.chains.iter()
+        self.get_groups().map(|(_, group)| group)
+    }
+
+    /// Returns all the groups with their index, ordered by their smallest stone in row major
+    /// order. It's a single pass over the board, a group is yielded on its origin.
+    pub fn get_groups(&self) -> impl Iterator<Item = (GroupIdx, &Group)> + '_ {
+        self.groups_where(|_| true)
+    }
+
+    /// Returns the chains of the color, ordered by their smallest stone in row major order.
+    pub fn chains_of_color(&self, color: Color) -> impl Iterator<Item = &Group> {
+        self.groups_where(move |group| group.color == color)
+            .map(|(_, group)| group)
+    }
+
+    /// The chains of the opponent of `by` in atari, with the point where `by` captures them,
+    /// ordered by their smallest stone in row major order. Two chains with the same last
+    /// liberty give two entries with the same point.
+    ///
+    /// A capture always frees a liberty so the move is never a suicide, but it can retake a
+    /// ko, that's checked by [`Game::check_point`](crate::rules::game::Game::check_point).
+    pub fn capturable_chains(&self, by: Color) -> impl Iterator<Item = (&Group, Coord)> + '_ {
+        self.groups_where(move |group| group.color != by && group.is_atari())
+            .filter_map(move |(_, group)| {
+                let liberty = iter_ones(&group.liberties).next()?;
+                Some((group, liberty.to_coord(self.size)))
+            })
+    }
+
+    /// The points where `color` captures, with the number of stones captured, in row major
+    /// order. Built on [`Goban::capturable_chains`], the ko isn't checked.
+    pub fn capture_moves(&self, color: Color) -> Vec<(Coord, u32)> {
+        let mut captures: Vec<(Coord, u32)> = vec![];
+        for (group, point) in self.capturable_chains(color) {
+            match captures.iter_mut().find(|(coord, _)| *coord == point) {
+                Some((_, stones)) => *stones += group.num_stones as u32,
+                None => captures.push((point, group.num_stones as u32)),
+            }
+        }
+        captures.sort_unstable();
+        captures
+    }
+
+    /// The points where `color` puts a chain of the opponent with two liberties in atari, with
+    /// the index of the chain, in row major order. A point giving atari to two chains gives two
+    /// entries. The suicides are skipped, the self-ataris too unless `self_atari` is true, a
+    /// move which captures is never a self-atari.
+    pub fn atari_moves(&self, color: Color, self_atari: bool) -> Vec<(Coord, GroupIdx)> {
+        let mut ataris = vec![];
+        for (chain_idx, group) in
+            self.groups_where(move |group| group.color != color && group.number_of_liberties() == 2)
+        {
+            for liberty in iter_ones(&group.liberties) {
+                let liberties = self.liberties_after(liberty, color);
+                if liberties == 0 || (liberties == 1 && !self_atari) {
+                    continue;
+                }
+                ataris.push((liberty.to_coord(self.size), chain_idx));
+            }
+        }
+        ataris.sort_by_key(|&(point, _)| point);
+        ataris
+    }
+
+    /// Number of liberties of the chain of a stone of the color pushed on the empty point,
+    /// [`usize::MAX`] if it captures.
+    fn liberties_after(&self, point: BoardIdx, color: Color) -> usize {
+        let mut liberties = EMPTY_LIBERTIES;
+        for neighbor in self.neighbors_idx(point) {
+            match self.board[neighbor.get()] {
+                None => set::<true>(neighbor, &mut liberties),
+                Some(chain_idx) => {
+                    let chain = &self.chains[chain_idx];
+                    if chain.color == color {
+                        merge(&mut liberties, &chain.liberties);
+                    } else if chain.is_atari() {
+                        return usize::MAX;
+                    }
+                }
+            }
+        }
+        set::<false>(point, &mut liberties);
+        count_ones(&liberties)
+    }
+
+    /// The single pass of [`Goban::get_groups`], keeping the groups accepted by the filter.
+    fn groups_where<'a>(
+        &'a self,
+        filter: impl Fn(&Group) -> bool + 'a,
+    ) -> impl Iterator<Item = (GroupIdx, &'a Group)> + 'a {
+        BoardIdx::all(self.size).filter_map(move |idx| {
+            let chain_idx = self.board[idx.get()]?.get() as usize;
+            let group = &self.chains[chain_idx];
+            (group.origin == u16::from(idx) && filter(group)).then_some((chain_idx, group))
+        })
     }
 
-    pub fn chain_stones(&self, idx: impl IntoIdx) -> impl Iterator<Item = Stone> + '_ {
-        let idx = idx.into_idx(self.size);
-        let chain = self.chains[idx];
+    /// Stones of the chain, from its origin.
+    pub fn chain_stones(&self, chain_idx: GroupIdx) -> impl Iterator<Item = Stone> + '_ {
+        let chain = self.chains[chain_idx];
         let color = chain.color;
         chain.iter(&self.next_stone).map(move |e| Stone {
-            coord: one_to_2dim(self.size, e),
+            coord: e.to_coord(self.size),
             color,
         })
     }
 
-    /// Returns the underlying goban in a vector with a RowMajor Policy, calculated on the fly.
+    /// The chain of the stone on the point, None if the point is empty. Read from the chain
+    /// without going through its stones.
+    pub fn chain_info(&self, coord: Coord) -> Option<ChainInfo> {
+        let chain = &self.chains[self.chain_idx(coord)?];
+        Some(ChainInfo {
+            color: chain.color,
+            stone_count: chain.num_stones as usize,
+            liberty_count: chain.number_of_liberties(),
+            first_stone: BoardIdx::from(chain.origin).to_coord(self.size),
+        })
+    }
+
+    /// Returns the colors of the points in row major order, the only order of the goban, so the
+    /// point (row, column) is at [`Goban::index_of`]. Calculated on the fly.
     pub fn to_vec(&self) -> Vec<MaybeColor> {
-        self.board
+        self.board[..self.size.0 as usize * self.size.1 as usize]
             .iter()
             .map(|point| {
                 point.map_or(EMPTY, |go_str_ptr| {
@@ -105,10 +524,43 @@ impl Goban {
             .collect()
     }
 
-    /// Like vec but in a matrix shape.
+    /// Writes one byte per point in row major order, the point (row, column) at
+    /// [`Goban::index_of`]: 0 for an empty point, 1 for black and 2 for white. This layout
+    /// doesn't depend on how the goban stores its stones and won't change.
+    ///
+    /// # Errors
+    /// `BufferLength` if the buffer doesn't have exactly `height * width` bytes.
+    pub fn snapshot_into(&self, out: &mut [u8]) -> Result<(), GobanError> {
+        let length = self.size.0 as usize * self.size.1 as usize;
+        if out.len() != length {
+            return Err(GobanError::BufferLength {
+                expected: length,
+                actual: out.len(),
+            });
+        }
+        for (byte, point) in out.iter_mut().zip(&self.board) {
+            *byte = match point.map(|chain_id| self.chains[chain_id.get() as usize].color) {
+                None => 0,
+                Some(Color::Black) => 1,
+                Some(Color::White) => 2,
+            };
+        }
+        Ok(())
+    }
+
+    /// The bytes of [`Goban::snapshot_into`] in a new buffer.
+    pub fn snapshot(&self) -> Vec<u8> {
+        let mut out = vec![0; self.size.0 as usize * self.size.1 as usize];
+        self.snapshot_into(&mut out)
+            .expect("The buffer has one byte per point");
+        out
+    }
+
+    /// Like vec but in a matrix shape, one vector by row.
     pub fn matrix(&self) -> Vec<Vec<MaybeColor>> {
         let mut mat = vec![];
-        for line in self.board.chunks_exact(self.size.1 as usize) {
+        let points = &self.board[..self.size.0 as usize * self.size.1 as usize];
+        for line in points.chunks_exact(self.size.1 as usize) {
             let v = line
                 .iter()
                 .map(|o| o.map_or(EMPTY, |idx| self.chains[idx].color.into()))
@@ -143,9 +595,127 @@ impl Goban {
         &self.board
     }
 
+    /// Removes the stone at the point, the rest of its chain stays on the goban.
     pub(crate) fn put_empty(&mut self, point: Coord) {
-        let pushed_stone_idx = two_to_1dim(self.size, point);
-        self.board[pushed_stone_idx] = None;
+        let idx = BoardIdx::from_coord(self.size, point);
+        let Some(chain_idx) = self.board[idx.get()] else {
+            return;
+        };
+        let chain_idx = chain_idx.get() as usize;
+        let color = self.chains[chain_idx].color;
+        let remaining: Vec<BoardIdx> = self
+            .iter_stones(chain_idx)
+            .filter(|&stone| stone != idx)
+            .collect();
+
+        // The chain is removed then rebuilt, the journal only keeps the removed stone.
+        let journal = self.journal.take();
+        self.remove_chain(chain_idx);
+        for stone in remaining {
+            self.push_wth_feedback(stone.to_coord(self.size), color);
+        }
+        self.journal = journal;
+        if let Some(journal) = &mut self.journal {
+            journal.push((point, Some(color)));
+        }
+    }
+
+    /// Returns a copy of the goban where black stones become white and white stones black.
+    pub fn swap_colors(&self) -> Goban {
+        let mut goban = self.clone();
+        goban.journal = None;
+        goban.swap_colors_mut();
+        goban
+    }
+
+    /// Returns the goban moved by the symmetry, a goban of `symmetry.size(self.size())`.
+    pub fn transform(&self, symmetry: Symmetry) -> Goban {
+        Goban::from_stones(
+            symmetry.size(self.size),
+            self.get_stones().map(|stone| Stone {
+                coord: symmetry.apply(self.size, stone.coord),
+                color: stone.color,
+            }),
+        )
+    }
+
+    /// Black stones become white and white stones black.
+    /// The chains keep their stones and liberties, the hash is recomputed because each color
+    /// has its own zobrist keys.
+    pub fn swap_colors_mut(&mut self) {
+        if let Some(journal) = &mut self.journal {
+            journal.extend(
+                IndexedPoints::new(self.size).filter_map(|(idx, coord)| {
+                    self.board[idx.get()].map(|chain| (coord, Some(self.chains[chain].color)))
+                }),
+            );
+        }
+        for group in self.chains.0.iter_mut().flatten() {
+            group.color = !group.color;
+        }
+        self.zobrist_hash = self.compute_zobrist_hash();
+        debug_assert_eq!(self.check_invariants(), Ok(()));
+    }
+
+    /// Starts recording every mutation of the goban, so they can be undone by [`Goban::rollback`].
+    ///
+    /// # Errors
+    /// Returns `TransactionAlreadyActive` if a transaction is already running.
+    pub fn begin_transaction(&mut self) -> Result<(), GobanError> {
+        if self.journal.is_some() {
+            return Err(GobanError::TransactionAlreadyActive);
+        }
+        self.journal = Some(Vec::new());
+        self.journal_chains = self.chains.0.len();
+        Ok(())
+    }
+
+    /// Keeps all the mutations done since the start of the transaction and drops the journal.
+    ///
+    /// # Errors
+    /// Returns `NoActiveTransaction` if no transaction is running.
+    pub fn commit(&mut self) -> Result<(), GobanError> {
+        self.journal
+            .take()
+            .map(|_| ())
+            .ok_or(GobanError::NoActiveTransaction)
+    }
+
+    /// Restores the goban (stones, chains and hash) as it was at the start of the transaction.
+    ///
+    /// # Errors
+    /// Returns `NoActiveTransaction` if no transaction is running.
+    pub fn rollback(&mut self) -> Result<(), GobanError> {
+        let journal = self.journal.take().ok_or(GobanError::NoActiveTransaction)?;
+        for (point, previous) in journal.into_iter().rev() {
+            match previous {
+                Some(color) => {
+                    self.push(point, color);
+                }
+                EMPTY => self.put_empty(point),
+            }
+        }
+        // The rebuilt chains move to the slots freed by the transaction, so speculative
+        // placements can be rolled back forever without running out of chains.
+        let mark = self.journal_chains;
+        let mut free_slots: Vec<GroupIdx> =
+            (0..mark).filter(|&idx| self.chains.0[idx].is_none()).collect();
+        for chain_idx in mark..self.chains.0.len() {
+            if let Some(chain) = self.chains.0[chain_idx].take() {
+                let slot = free_slots.pop().expect("The transaction freed the slot of the chain");
+                self.chains.0[slot] = Some(chain);
+                self.update_chain_indexes_in_board(slot);
+            }
+        }
+        self.chains.0.truncate(mark);
+        debug_assert_eq!(self.check_invariants(), Ok(()));
+        Ok(())
+    }
+
+    /// Returns true if a transaction is running.
+    #[inline]
+    pub fn in_transaction(&self) -> bool {
+        self.journal.is_some()
     }
 
     /// pushes the stone
@@ -158,16 +728,19 @@ impl Goban {
         &mut self,
         point: Coord,
         color: Color,
-    ) -> (ArrayVec<usize, 4>, GroupIdx) {
-        let pushed_stone_idx = two_to_1dim(self.size, point);
+    ) -> (ArrayVec<GroupIdx, 4>, GroupIdx) {
+        let pushed_stone_idx = BoardIdx::from_coord(self.size, point);
+        if let Some(journal) = &mut self.journal {
+            journal.push((point, EMPTY));
+        }
 
-        let mut adjacent_same_color_str_set = ArrayVec::<BoardIdx, 4>::new();
-        let mut adjacent_opposite_color_str_set = ArrayVec::<BoardIdx, 4>::new();
+        let mut adjacent_same_color_str_set = ArrayVec::<GroupIdx, 4>::new();
+        let mut adjacent_opposite_color_str_set = ArrayVec::<GroupIdx, 4>::new();
         let mut liberties = ArrayVec::<BoardIdx, 4>::new();
 
         // For each neighbor we fill the right vector
         for neighbor_idx in self.neighbors_idx(pushed_stone_idx) {
-            match self.board[neighbor_idx] {
+            match self.board[neighbor_idx.get()] {
                 Some(adj_ren_index) => {
                     let adj_ren_index = adj_ren_index.get() as usize;
                     if self.chains[adj_ren_index].color == color {
@@ -184,7 +757,7 @@ impl Goban {
             }
         }
 
-        let mut dead_ren = ArrayVec::<BoardIdx, 4>::new();
+        let mut dead_ren = ArrayVec::<GroupIdx, 4>::new();
         // for every string of opposite color remove a liberty and update the string.
         for ren_idx in adjacent_opposite_color_str_set {
             let group = &mut self.chains[ren_idx];
@@ -204,7 +777,7 @@ impl Goban {
                     .remove_liberty(pushed_stone_idx)
                     .union_liberties_slice(&liberties);
                 self.add_stone_to_chain(only_ren_idx, pushed_stone_idx);
-                self.board[pushed_stone_idx] = Some(NonMaxU16::new(only_ren_idx as u16).unwrap());
+                self.board[pushed_stone_idx.get()] = Some(chain_id(only_ren_idx));
                 only_ren_idx
             }
             _ => {
@@ -223,9 +796,8 @@ impl Goban {
                 to_merge
             }
         };
-        self.zobrist_hash ^= index_zobrist(pushed_stone_idx, color);
-        #[cfg(debug_assertions)]
-        self.check_integrity_all();
+        self.zobrist_hash ^= index_zobrist(pushed_stone_idx.get(), color);
+        debug_assert_eq!(self.check_invariants(), Ok(()));
         (dead_ren, updated_ren_index)
     }
 
@@ -242,7 +814,7 @@ impl Goban {
             let dead_chain = &self.chains[dead_ren_idx];
             // If only one stone and one group is removed then it becomes a ko point
             if dead_chain.num_stones == 1 && only_one_ren_removed {
-                ko_point = Some(one_to_2dim(self.size(), dead_chain.origin as usize));
+                ko_point = Some(BoardIdx::from(dead_chain.origin).to_coord(self.size));
             }
             match dead_chain.color {
                 Color::White => {
@@ -271,9 +843,30 @@ impl Goban {
         (stones_removed, ko_point)
     }
 
+    /// Returns a copy of the goban with the stone played on the empty point, the chains without
+    /// liberties are captured, its own chain last so a suicide removes it. The goban is left
+    /// untouched, the copy doesn't record the transaction of the goban.
+    ///
+    /// # Errors
+    /// If the point is outside the goban or taken.
+    pub fn try_play(&self, point: Coord, color: Color) -> Result<Goban, GobanError> {
+        if !is_coord_valid(self.size, point) {
+            return Err(GobanError::OutsideBoard(point));
+        }
+        if self.get_color(point).is_some() {
+            return Err(GobanError::Occupied(point));
+        }
+        let mut goban = self.clone();
+        goban.journal = None;
+        let (dead, added) = goban.push_wth_feedback(point, color);
+        goban.remove_captured_stones_aux(true, &dead, added);
+        Ok(goban)
+    }
+
     /// Put a stones in the goban.
     /// default (line, column)
     /// the (0,0) point is in the top left.
+    /// If the point is already occupied the old stone is replaced.
     ///
     /// # Panics
     /// if the point is out of bounds
@@ -288,6 +881,11 @@ impl Goban {
             "Coordinate point.1 {} out of bounds",
             point.1
         );
+        match self.get_color(point) {
+            Some(c) if c == color => return self,
+            Some(_) => self.put_empty(point),
+            EMPTY => {}
+        }
         self.push_wth_feedback(point, color);
         self
     }
@@ -337,7 +935,7 @@ impl Goban {
         let index = index.into_idx(self.size);
         let mut array_vec: ArrayVec<GroupIdx, 4> = ArrayVec::new_const();
         for idx in self.neighbors_idx(index) {
-            if let Some(idx) = self.board[idx] {
+            if let Some(idx) = self.board[idx.get()] {
                 if !array_vec.contains(&(idx.get() as usize)) {
                     array_vec.push(idx.get() as usize);
                 }
@@ -346,81 +944,253 @@ impl Goban {
         array_vec
     }
 
+    /// Indexes of the chains of the other color touching the chain on the point, ordered by
+    /// their smallest stone in row major order. Empty if the point is empty.
+    #[inline]
+    pub fn adjacent_enemy_chains(&self, coord: Coord) -> Vec<GroupIdx> {
+        self.adjacent_chains(coord, false)
+    }
+
+    /// Indexes of the other chains of the same color touching the chain on the point, by
+    /// their smallest stone in row major order. They touch through a shared liberty.
+    /// Empty if the point is empty.
+    #[inline]
+    pub fn adjacent_friendly_chains(&self, coord: Coord) -> Vec<GroupIdx> {
+        self.adjacent_chains(coord, true)
+    }
+
+    /// Chains touching the chain on the point, a chain of the same color is only
+    /// touched by a liberty. The stones of the chain are scanned once.
+    fn adjacent_chains(&self, coord: Coord, same_color: bool) -> Vec<GroupIdx> {
+        let Some(chain_idx) = self.chain_idx(coord) else {
+            return vec![];
+        };
+        let color = self.chains[chain_idx].color;
+        let mut chains: Vec<GroupIdx> = vec![];
+        let mut add = |neighbor: BoardIdx| {
+            if let Some(idx) = self.chain_idx(neighbor) {
+                if idx != chain_idx
+                    && (self.chains[idx].color == color) == same_color
+                    && !chains.contains(&idx)
+                {
+                    chains.push(idx);
+                }
+            }
+        };
+        for stone in self.iter_stones(chain_idx) {
+            for neighbor in self.neighbors_idx(stone) {
+                if same_color && self.board[neighbor.get()].is_none() {
+                    self.neighbors_idx(neighbor).for_each(&mut add);
+                } else {
+                    add(neighbor);
+                }
+            }
+        }
+        chains.sort_unstable_by_key(|&idx| self.chains[idx].origin);
+        chains
+    }
+
+    /// Chain of the stone on the point, None if the point is empty.
+    #[inline]
+    pub(crate) fn chain_at(&self, coord: impl IntoIdx) -> Option<&Group> {
+        self.chain_idx(coord).map(|chain_idx| &self.chains[chain_idx])
+    }
+
+    /// Liberties of the chain as a bitset of board indexes.
+    #[inline]
+    pub(crate) fn chain_liberties(&self, chain_idx: GroupIdx) -> Liberties {
+        self.chains[chain_idx].liberties
+    }
+
+    /// Index of the chain of the stone on the point, None if the point is empty.
+    #[inline]
+    pub(crate) fn chain_idx(&self, coord: impl IntoIdx) -> Option<GroupIdx> {
+        self.board[coord.into_idx(self.size).get()].map(|chain_id| chain_id.get() as usize)
+    }
+
     #[inline]
     pub fn get_color(&self, coord: impl IntoIdx) -> MaybeColor {
         let idx = coord.into_idx(self.size);
-        self.board[idx].map(|chain_id| self.chains[chain_id.get() as usize].color)
+        self.board[idx.get()].map(|chain_id| self.chains[chain_id.get() as usize].color)
+    }
+
+    /// Flat index of the point, the points are indexed in row major order from 0.
+    ///
+    /// # Panics
+    /// If the point is out of the goban.
+    #[inline]
+    pub fn index_of(&self, coord: Coord) -> usize {
+        self.get_index(coord)
+            .unwrap_or_else(|| panic!("the point {coord:?} is out of the goban {:?}", self.size))
+    }
+
+    /// Flat index of the point, None if the point is out of the goban.
+    #[inline]
+    pub fn get_index(&self, coord: Coord) -> Option<usize> {
+        is_coord_valid(self.size, coord).then(|| two_to_1dim(self.size, coord))
+    }
+
+    /// Point at the flat index, the reverse of [`Goban::index_of`]. With a policy over the
+    /// points of the goban:
+    /// ```
+    /// use goban::rules::game::Game;
+    /// use goban::rules::{GobanSizes, JAPANESE};
+    ///
+    /// let mut game = Game::new(GobanSizes::Nineteen, JAPANESE);
+    /// let policy = vec![0f32; 361];
+    /// let best = (0..policy.len()).max_by(|&a, &b| policy[a].total_cmp(&policy[b])).unwrap();
+    /// game.play(game.goban().coord_of(best).into());
+    /// ```
+    ///
+    /// # Panics
+    /// If the index is out of the goban.
+    #[inline]
+    pub fn coord_of(&self, index: usize) -> Coord {
+        self.get_coord(index)
+            .unwrap_or_else(|| panic!("the index {index} is out of the goban {:?}", self.size))
+    }
+
+    /// Point at the flat index, None if the index is out of the goban.
+    #[inline]
+    pub fn get_coord(&self, index: usize) -> Option<Coord> {
+        (index < self.size.0 as usize * self.size.1 as usize)
+            .then(|| one_to_2dim(self.size, index))
+    }
+
+    /// Color on the SGF point, like `"dd"`, see [`sgf_to_coord`].
+    ///
+    /// # Errors
+    /// If the point can't be read or is out of the goban.
+    #[inline]
+    pub fn at_sgf(&self, point: &str) -> Result<MaybeColor, SgfCoordError> {
+        Ok(self.get_color(sgf_to_coord(point, self.size)?))
+    }
+
+    /// Puts a stone on the SGF point, like `"dd"`, see [`sgf_to_coord`].
+    ///
+    /// # Errors
+    /// If the point can't be read or is out of the goban.
+    #[inline]
+    pub fn put_sgf(&mut self, point: &str, color: Color) -> Result<&mut Self, SgfCoordError> {
+        Ok(self.push(sgf_to_coord(point, self.size)?, color))
     }
 
-    /// Get all the stones except "EMPTY stones"
+    /// Get all the stones except "EMPTY stones", in row major order.
     #[inline]
     pub fn get_stones(&self) -> impl Iterator<Item = Stone> + '_ {
-        self.board.iter().enumerate().filter_map(move |(index, o)| {
-            o.map(move |chain_idx| Stone {
-                coord: one_to_2dim(self.size, index),
+        self.points().filter_map(move |(point, coord)| {
+            point.map(move |chain_idx| Stone {
+                coord,
                 color: self.chains[chain_idx.get() as usize].color,
             })
         })
     }
 
-    /// Get stones by their color.
+    /// Get stones by their color, in row major order.
     #[inline]
     pub fn get_stones_by_color(&self, color: MaybeColor) -> impl Iterator<Item = Point> + '_ {
         self.get_coords_by_color(color)
             .map(move |c| Point { color, coord: c })
     }
 
+    /// The points of the board with their coordinates, in row major order.
+    #[inline]
+    fn points(&self) -> impl Iterator<Item = (Option<NonMaxU16>, Coord)> + '_ {
+        IndexedPoints::new(self.size).map(|(index, coord)| (self.board[index.get()], coord))
+    }
+
+    /// Get the indexes of the empty intersections, in row major order.
     pub fn get_empty_idx(&self) -> impl Iterator<Item = BoardIdx> + '_ {
-        self.board
-            .iter()
-            .enumerate()
-            .filter_map(|(idx, group)| group.map(|_| idx))
+        BoardIdx::all(self.size).filter(|idx| self.board[idx.get()].is_none())
     }
 
+    /// Get the empty intersections, in row major order.
     pub fn get_empty_coords(&self) -> impl Iterator<Item = Coord> + '_ {
-        let board_length = self.size.0 as usize * self.size.1 as usize;
-        self.board[..board_length]
-            .iter()
-            .enumerate()
-            .filter_map(|x| {
-                if x.1.is_none() {
-                    Some(one2dim!(self.size, x.0))
+        self.points()
+            .filter(|(point, _)| point.is_none())
+            .map(|(_, coord)| coord)
+    }
+
+    /// The empty point closest to the point, searched in rings of growing Chebyshev distance.
+    /// In a ring the smallest point in row major order wins. None only if the goban is full.
+    #[inline]
+    pub fn nearest_empty(&self, from: Coord) -> Option<Coord> {
+        self.nearest_empty_by(from, |_| true)
+    }
+
+    /// Like [`Goban::nearest_empty`] but the point must also satisfy the predicate, like being a
+    /// legal move. Nothing is allocated, the rings are walked in place.
+    pub fn nearest_empty_by(&self, from: Coord, pred: impl Fn(Coord) -> bool) -> Option<Coord> {
+        let (height, width) = (self.size.0 as i32, self.size.1 as i32);
+        let (row, col) = (from.0 as i32, from.1 as i32);
+        let max_radius = (row.max(height - 1 - row)).max(col.max(width - 1 - col));
+        let accept = |r: i32, c: i32| {
+            let point = (r as Nat, c as Nat);
+            (self.get_color(point).is_none() && pred(point)).then_some(point)
+        };
+        for radius in 0..=max_radius {
+            for r in (row - radius).max(0)..=(row + radius).min(height - 1) {
+                let found = if r == row - radius || r == row + radius {
+                    // The top and bottom rows of the ring are full.
+                    ((col - radius).max(0)..=(col + radius).min(width - 1))
+                        .find_map(|c| accept(r, c))
                 } else {
-                    None
+                    [col - radius, col + radius]
+                        .into_iter()
+                        .filter(|&c| (0..width).contains(&c))
+                        .find_map(|c| accept(r, c))
+                };
+                if found.is_some() {
+                    return found;
                 }
-            })
+            }
+        }
+        None
     }
 
-    /// Get points by their color.
+    /// Get points by their color, in row major order.
     #[inline]
     pub fn get_coords_by_color(&self, color: MaybeColor) -> impl Iterator<Item = Coord> + '_ {
-        let mut res = ArrayVec::<Coord, BOARD_MAX_LENGTH>::new();
-        for board_idx in 0..(self.size.0 * self.size.1) as usize {
-            match color {
-                EMPTY => res.push(one_to_2dim(self.size, board_idx)),
-                Some(c) => self.board[board_idx]
-                    .filter(|&chain_idx| self.chains[chain_idx.get() as usize].color == c)
-                    .map(|_| res.push(one_to_2dim(self.size, board_idx)))
-                    .unwrap_or(()),
-            }
-        }
-        res.into_iter()
+        self.points()
+            .filter(move |(point, _)| match color {
+                EMPTY => true,
+                Some(c) => point
+                    .is_some_and(|chain_idx| self.chains[chain_idx.get() as usize].color == c),
+            })
+            .map(|(_, coord)| coord)
     }
 
-    /// Returns the "empty" stones connected to the stone
+    /// Returns the empty neighbors of the point, whatever is on the point.
+    ///
+    /// These are the liberties of the point alone, not of its chain: a stone surrounded by
+    /// its own chain and the opponent has none while its chain may have many, so they don't
+    /// tell if a move is legal or a chain in atari. The liberties of a chain are counted by
+    /// [`Group::number_of_liberties`].
     #[inline]
-    pub fn get_liberties(&self, coord: Coord) -> impl Iterator<Item = Coord> + '_ {
-        self.neighbors_coords(coord)
+    pub fn liberties_of_point(&self, point: Coord) -> impl Iterator<Item = Coord> + '_ {
+        self.neighbors_coords(point)
             .filter(|&x| self.get_color(x).is_none())
     }
 
-    /// Returns true if the stone has liberties.
+    /// Returns true if the point has an empty neighbor, see [`Goban::liberties_of_point`].
+    #[inline]
+    pub fn point_has_liberty(&self, point: Coord) -> bool {
+        self.liberties_of_point(point).next().is_some()
+    }
+
+    #[deprecated(note = "use `Goban::liberties_of_point`")]
+    #[inline]
+    pub fn get_liberties(&self, coord: Coord) -> impl Iterator<Item = Coord> + '_ {
+        self.liberties_of_point(coord)
+    }
+
+    #[deprecated(note = "use `Goban::point_has_liberty`")]
     #[inline]
     pub fn has_liberties(&self, coord: Coord) -> bool {
-        self.get_liberties(coord).next().is_some()
+        self.point_has_liberty(coord)
     }
 
-    /// Get a string for printing the goban in normal shape (0,0) left bottom
+    /// Get a string for printing the goban, one line by row with the row 0 at the top.
     pub fn pretty_string(&self) -> String {
         let mut buff = String::with_capacity(361);
         for i in 0..self.size.0 as Nat {
@@ -460,6 +1230,13 @@ impl Goban {
     pub fn remove_chain(&mut self, ren_to_remove_idx: GroupIdx) {
         let chain = self.chains[ren_to_remove_idx];
         let color_of_the_string = chain.color;
+        if let Some(journal) = &mut self.journal {
+            journal.extend(
+                chain
+                    .iter(&self.next_stone)
+                    .map(|idx| (idx.to_coord(self.size), Some(color_of_the_string))),
+            );
+        }
         for point_idx in chain.iter(&self.next_stone) {
             let mut neighbors_chains = self.get_connected_groups_idx(point_idx);
             // We remove our group from the neighbors
@@ -468,10 +1245,11 @@ impl Goban {
             for &n in &neighbors_chains {
                 self.chains[n].add_liberty(point_idx);
             }
-            self.zobrist_hash ^= index_zobrist(point_idx, color_of_the_string);
-            self.board[point_idx] = None;
+            self.zobrist_hash ^= index_zobrist(point_idx.get(), color_of_the_string);
+            self.board[point_idx.get()] = None;
         }
         self.chains.remove(ren_to_remove_idx);
+        debug_assert_eq!(self.check_invariants(), Ok(()));
     }
 
     /// Updates the group idx of the board when merging groups
@@ -480,11 +1258,12 @@ impl Goban {
             self.chains[chain_idx]
                 .iter(&self.next_stone)
                 .last()
-                .unwrap() as u16,
-            self.chains[chain_idx].last
+                .map(u16::from),
+            Some(self.chains[chain_idx].last)
         );
+        let id = chain_id(chain_idx);
         for point in self.chains[chain_idx].iter(&self.next_stone) {
-            self.board[point] = Some(NonMaxU16::new(chain_idx as u16).unwrap());
+            self.board[point.get()] = Some(id);
         }
     }
 
@@ -497,8 +1276,8 @@ impl Goban {
     #[inline]
     fn neighbors_idx(&self, board_idx: BoardIdx) -> impl Iterator<Item = BoardIdx> {
         let size = self.size;
-        self.neighbors_coords(one_to_2dim(size, board_idx))
-            .map(move |coord| two_to_1dim(size, coord))
+        self.neighbors_coords(board_idx.to_coord(size))
+            .map(move |coord| BoardIdx::from_coord(size, coord))
     }
 
     #[inline]
@@ -508,7 +1287,7 @@ impl Goban {
             set::<true>(board_idx, &mut lib_bitset);
         }
         let chain_to_place = Group::new_with_liberties(color, origin, lib_bitset);
-        self.next_stone[origin] = origin as u16;
+        self.next_stone[origin.get()] = origin.into();
         let chain_idx = self.chains.put_free_spot(chain_to_place);
         self.update_chain_indexes_in_board(chain_idx);
         chain_idx
@@ -516,20 +1295,21 @@ impl Goban {
 
     fn add_stone_to_chain(&mut self, chain_idx: GroupIdx, stone: BoardIdx) {
         let group = &mut self.chains[chain_idx];
-        if stone < group.origin as usize {
+        let stone = u16::from(stone);
+        if stone < group.origin {
             // replace origin
-            self.next_stone[stone] = group.origin;
-            self.next_stone[group.last as usize] = stone as u16;
-            group.origin = stone as u16;
+            self.next_stone[stone as usize] = group.origin;
+            self.next_stone[group.last as usize] = stone;
+            group.origin = stone;
         } else {
-            self.next_stone[group.last as usize] = stone as u16;
-            self.next_stone[stone] = group.origin;
-            group.last = stone as u16;
+            self.next_stone[group.last as usize] = stone;
+            self.next_stone[stone as usize] = group.origin;
+            group.last = stone;
         }
         group.num_stones += 1;
         debug_assert_eq!(
-            self.chains[chain_idx].iter(&self.next_stone).last().unwrap() as u16,
-            self.chains[chain_idx].last
+            self.chains[chain_idx].iter(&self.next_stone).last().map(u16::from),
+            Some(self.chains[chain_idx].last)
         );
     }
 
@@ -582,36 +1362,157 @@ impl Goban {
         self.chains.remove(chain2_idx);
     }
 
-    #[allow(dead_code)]
-    #[cfg(debug_assertions)]
-    fn check_integrity_ren(&self, ren_idx: GroupIdx) {
-        assert_eq!(
-            self.iter_stones(ren_idx).next().unwrap() as u16,
-            self.chains[ren_idx].origin,
-            "The origin doesn't match"
-        );
-        assert_eq!(
-            self.iter_stones(ren_idx).last().unwrap() as u16,
-            self.chains[ren_idx].last,
-            "The last doesn't match"
-        );
-        
-        if self.iter_stones(ren_idx).count() as u16 != self.chains[ren_idx].num_stones {
-            panic!("The number of stones don't match")
+    #[inline(always)]
+    fn iter_stones(&self, chain_idx: GroupIdx) -> CircularGroupIter<'_> {
+        self.chains[chain_idx].iter(&self.next_stone)
+    }
+
+    /// Computes the zobrist hash of the goban with the keys of the table, it's the hash of
+    /// [`Goban::zobrist_hash`] for the default table.
+    pub fn zobrist_hash_with(&self, table: &ZobristTable) -> u64 {
+        self.get_stones().fold(0, |hash, stone| {
+            hash ^ table.key(BoardIdx::from_coord(self.size, stone.coord).get(), stone.color)
+        })
+    }
+
+    /// Zobrist hash of the goban after the stone is played on the empty point: the chains it
+    /// captures are removed, and its own chain if it's a suicide. Nothing is copied, it's the
+    /// hash of the goban after [`Goban::push`] and the removal of the dead chains.
+    pub fn hash_after(&self, point: Coord, color: Color) -> u64 {
+        let index = BoardIdx::from_coord(self.size, point);
+        let neighbors = self.get_connected_groups_idx(index);
+        let remove = |hash: u64, chain_idx: GroupIdx| {
+            let color = self.chains[chain_idx].color;
+            self.iter_stones(chain_idx)
+                .fold(hash, |hash, stone| hash ^ index_zobrist(stone.get(), color))
+        };
+        let mut hash = self.zobrist_hash ^ index_zobrist(index.get(), color);
+        let mut captures = false;
+        for &chain_idx in &neighbors {
+            let chain = &self.chains[chain_idx];
+            if chain.color != color && chain.is_atari() {
+                captures = true;
+                hash = remove(hash, chain_idx);
+            }
         }
+        let suicide = !captures
+            && !self.point_has_liberty(point)
+            && neighbors.iter().all(|&chain_idx| {
+                let chain = &self.chains[chain_idx];
+                chain.color != color || chain.is_atari()
+            });
+        if suicide {
+            hash ^= index_zobrist(index.get(), color);
+            for &chain_idx in &neighbors {
+                if self.chains[chain_idx].color == color {
+                    hash = remove(hash, chain_idx);
+                }
+            }
+        }
+        hash
     }
 
-    #[inline(always)]
-    fn iter_stones(&self, chain_idx: usize) -> CircularGroupIter<'_> {
-        self.chains[chain_idx].iter(&self.next_stone)
+    /// Computes the zobrist hash of the goban from scratch.
+    pub(crate) fn compute_zobrist_hash(&self) -> u64 {
+        self.get_stones().fold(0, |hash, stone| {
+            hash ^ index_zobrist(BoardIdx::from_coord(self.size, stone.coord).get(), stone.color)
+        })
     }
 
-    #[allow(dead_code)]
-    #[cfg(debug_assertions)]
-    fn check_integrity_all(&self) {
-        for (ren_idx, _) in self.chains.iter_with_index() {
-            self.check_integrity_ren(ren_idx);
+    /// Verifies the internal structures of the goban agree with each other: the board and the
+    /// stones of each chain, the stone counters, the liberties and the hash.
+    /// Chains without liberties are allowed, the goban doesn't capture by itself.
+    ///
+    /// # Errors
+    /// Returns the first invariant violated, with where it happened.
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        let board_length = self.size.0 as usize * self.size.1 as usize;
+        if let Some(index) = self.board[board_length..].iter().position(Option::is_some) {
+            return Err(InvariantViolation::StoneOutsideBoard {
+                index: BoardIdx::try_from(board_length + index).unwrap_or_default(),
+            });
+        }
+
+        let mut in_a_chain = EMPTY_LIBERTIES;
+        for (chain_idx, chain) in self.chains.iter_with_index() {
+            let mut liberties = EMPTY_LIBERTIES;
+            let mut num_stones = 0;
+            let mut last = BoardIdx::from(chain.origin);
+            // The take protects against a corrupted circular list.
+            for stone in chain.iter(&self.next_stone).take(board_length + 1) {
+                if stone.get() >= board_length {
+                    return Err(InvariantViolation::StoneOutsideBoard { index: stone });
+                }
+                let coord = stone.to_coord(self.size);
+                if self.board[stone.get()].map(|idx| idx.get() as usize) != Some(chain_idx) {
+                    return Err(InvariantViolation::BoardChainMismatch {
+                        coord,
+                        chain: chain_idx,
+                    });
+                }
+                if get(stone, &in_a_chain) {
+                    return Err(InvariantViolation::StoneInTwoChains { coord });
+                }
+                if u16::from(stone) < chain.origin {
+                    return Err(InvariantViolation::WrongOrigin { chain: chain_idx });
+                }
+                set::<true>(stone, &mut in_a_chain);
+                for neighbor in self.neighbors_idx(stone) {
+                    if self.board[neighbor.get()].is_none() {
+                        set::<true>(neighbor, &mut liberties);
+                    }
+                }
+                num_stones += 1;
+                last = stone;
+            }
+            if num_stones != chain.num_stones as usize {
+                return Err(InvariantViolation::WrongStoneCount {
+                    chain: chain_idx,
+                    stored: chain.num_stones as usize,
+                    actual: num_stones,
+                });
+            }
+            if u16::from(last) != chain.last {
+                return Err(InvariantViolation::WrongLast { chain: chain_idx });
+            }
+            if liberties != chain.liberties {
+                let index = (0..BOARD_MAX_LENGTH as u16)
+                    .map(BoardIdx::from)
+                    .find(|&index| get(index, &liberties) != chain.contains_liberty(index))
+                    .unwrap_or_default();
+                return Err(InvariantViolation::WrongLiberty {
+                    chain: chain_idx,
+                    coord: index.to_coord(self.size),
+                });
+            }
+        }
+
+        for (index, coord) in IndexedPoints::new(self.size) {
+            if let Some(chain_idx) = self.board[index.get()] {
+                let chain_idx = chain_idx.get() as usize;
+                if self.chains.0.get(chain_idx).is_none_or(Option::is_none) {
+                    return Err(InvariantViolation::DanglingChain {
+                        coord,
+                        chain: chain_idx,
+                    });
+                }
+                if !get(index, &in_a_chain) {
+                    return Err(InvariantViolation::StoneNotInChain {
+                        coord,
+                        chain: chain_idx,
+                    });
+                }
+            }
         }
+
+        let computed = self.compute_zobrist_hash();
+        if computed != self.zobrist_hash {
+            return Err(InvariantViolation::WrongHash {
+                stored: self.zobrist_hash,
+                computed,
+            });
+        }
+        Ok(())
     }
 }
 
@@ -621,6 +1522,66 @@ impl Display for Goban {
     }
 }
 
+/// The size, the hash, the number of stones and the [board](Goban::pretty_string) instead of
+/// the buffers of the goban. `{:#?}` writes them with their labels.
+impl Debug for Goban {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let (height, width) = self.size;
+        let (black, white) = self.number_of_stones();
+        let board = self.pretty_string();
+        let board = board.trim_end();
+        if f.alternate() {
+            f.debug_struct("Goban")
+                .field("size", &format_args!("{height}x{width}"))
+                .field("hash", &format_args!("{:#018x}", self.zobrist_hash))
+                .field("black", &black)
+                .field("white", &white)
+                .field("board", &format_args!("\n{board}"))
+                .finish()
+        } else {
+            write!(
+                f,
+                "Goban {height}x{width} {:#018x} {black} black {white} white\n{board}",
+                self.zobrist_hash
+            )
+        }
+    }
+}
+
+/// Color at the flat index, see [`Goban::index_of`].
+///
+/// # Panics
+/// If the index is out of the goban.
+impl Index<usize> for Goban {
+    type Output = MaybeColor;
+
+    fn index(&self, index: usize) -> &MaybeColor {
+        static_color(self.get_color(self.coord_of(index)))
+    }
+}
+
+/// Color on the SGF point, see [`Goban::at_sgf`].
+///
+/// # Panics
+/// If the point can't be read or is out of the goban.
+impl Index<&str> for Goban {
+    type Output = MaybeColor;
+
+    fn index(&self, point: &str) -> &MaybeColor {
+        let coord = sgf_to_coord(point, self.size).unwrap_or_else(|e| panic!("{point:?}: {e}"));
+        static_color(self.get_color(coord))
+    }
+}
+
+/// The indexes return references, the goban only stores the chains of the points.
+fn static_color(color: MaybeColor) -> &'static MaybeColor {
+    match color {
+        Some(Color::Black) => &Some(Color::Black),
+        Some(Color::White) => &Some(Color::White),
+        EMPTY => &EMPTY,
+    }
+}
+
 impl Default for Goban {
     fn default() -> Self {
         Goban::new((19, 19))
@@ -633,6 +1594,40 @@ impl Hash for Goban {
     }
 }
 
+/// Compares the goban point by point in row major order, (0, 0), (0, 1) ... (1, 0) ...
+/// The slice must have exactly height * width colors, else the comparison is false.
+impl PartialEq<[MaybeColor]> for Goban {
+    fn eq(&self, other: &[MaybeColor]) -> bool {
+        let (height, width) = self.size;
+        other.len() == height as usize * width as usize
+            && other
+                .iter()
+                .enumerate()
+                .all(|(index, &color)| {
+                    let index = BoardIdx::try_from(index).expect("the length is checked");
+                    self.get_color(index) == color
+                })
+    }
+}
+
+impl PartialEq<&[MaybeColor]> for Goban {
+    fn eq(&self, other: &&[MaybeColor]) -> bool {
+        self == *other
+    }
+}
+
+impl PartialEq<Goban> for [MaybeColor] {
+    fn eq(&self, other: &Goban) -> bool {
+        other == self
+    }
+}
+
+impl PartialEq<Goban> for &[MaybeColor] {
+    fn eq(&self, other: &Goban) -> bool {
+        other == *self
+    }
+}
+
 impl PartialEq for Goban {
     fn eq(&self, other: &Self) -> bool {
         if self.size != other.size || self.zobrist_hash != other.zobrist_hash {
@@ -650,3 +1645,70 @@ impl PartialEq for Goban {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexed_points_follow_indexes() {
+        for size in [(19, 19), (9, 13), (13, 9), (1, 1), (1, 7)] {
+            let points: Vec<_> = IndexedPoints::new(size).collect();
+            let expected: Vec<_> = BoardIdx::all(size)
+                .map(|index| (index, index.to_coord(size)))
+                .collect();
+            assert_eq!(points, expected);
+            assert_eq!(IndexedPoints::new(size).len(), expected.len());
+        }
+    }
+
+    #[test]
+    fn check_invariants_detects_corruption() {
+        let mut goban = Goban::new((9, 9));
+        goban.push_many(&[(2, 2), (2, 3), (3, 3)], Color::Black);
+        goban.push((5, 5), Color::White);
+        assert_eq!(goban.check_invariants(), Ok(()));
+
+        let mut corrupted = goban.clone();
+        corrupted.zobrist_hash ^= 1;
+        assert_eq!(
+            corrupted.check_invariants(),
+            Err(InvariantViolation::WrongHash {
+                stored: goban.zobrist_hash ^ 1,
+                computed: goban.zobrist_hash,
+            })
+        );
+
+        let chain_idx = goban.board[goban.index_of((2, 2))].unwrap().get() as usize;
+        let mut corrupted = goban.clone();
+        corrupted.chains[chain_idx].num_stones = 2;
+        assert_eq!(
+            corrupted.check_invariants(),
+            Err(InvariantViolation::WrongStoneCount {
+                chain: chain_idx,
+                stored: 2,
+                actual: 3,
+            })
+        );
+
+        let mut corrupted = goban.clone();
+        corrupted.chains[chain_idx].add_liberty(BoardIdx::from_coord(goban.size, (8, 8)));
+        assert_eq!(
+            corrupted.check_invariants(),
+            Err(InvariantViolation::WrongLiberty {
+                chain: chain_idx,
+                coord: (8, 8),
+            })
+        );
+
+        let mut corrupted = goban.clone();
+        corrupted.board[goban.index_of((7, 7))] = Some(chain_id(chain_idx));
+        assert_eq!(
+            corrupted.check_invariants(),
+            Err(InvariantViolation::StoneNotInChain {
+                coord: (7, 7),
+                chain: chain_idx,
+            })
+        );
+    }
+}
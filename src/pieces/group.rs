@@ -1,8 +1,7 @@
 use std::ops::{BitOrAssign, Index, IndexMut};
 
-use crate::pieces::goban::GroupIdx;
+use crate::pieces::goban::{BoardIdx, GroupIdx};
 use crate::pieces::stones::Color;
-use crate::pieces::BoardIdx;
 use arrayvec::ArrayVec;
 use nonmax::NonMaxU16;
 use std::iter::FusedIterator;
@@ -18,9 +17,9 @@ pub type Liberties = [Bucket; SIZE];
 pub const EMPTY_LIBERTIES: Liberties = [0; SIZE];
 
 #[inline(always)]
-pub fn set<const VAL: bool>(index: usize, lib: &mut Liberties) {
-    let chunk = index / BITS;
-    let bit_index = index % BITS;
+pub fn set<const VAL: bool>(index: BoardIdx, lib: &mut Liberties) {
+    let chunk = index.get() / BITS;
+    let bit_index = index.get() % BITS;
     let mask = 1 << bit_index;
     if VAL {
         lib[chunk] |= mask;
@@ -40,28 +39,29 @@ fn any(lib: &Liberties) -> bool {
 }
 
 #[inline(always)]
-fn count_ones(lib: &Liberties) -> usize {
+pub(crate) fn count_ones(lib: &Liberties) -> usize {
     lib.iter().map(|x| x.count_ones() as usize).sum()
 }
 
-fn iter_ones(lib: &Liberties) -> impl Iterator<Item = usize> + '_ {
+pub(crate) fn iter_ones(lib: &Liberties) -> impl Iterator<Item = BoardIdx> + '_ {
     lib.iter().enumerate().flat_map(|(ix, chunk)| {
         let mut chunk = *chunk;
-        let mut ixs = ArrayVec::<usize, BITS>::new();
+        let mut ixs = ArrayVec::<BoardIdx, BITS>::new();
         let mut index = 0;
         while chunk != 0 {
             let zeros = chunk.trailing_zeros();
-            index += zeros as usize + 1;
-            ixs.push(index - 1 + BITS * ix);
+            index += zeros as u16 + 1;
+            ixs.push(BoardIdx::from(index - 1 + (BITS * ix) as u16));
             chunk = chunk.checked_shr(zeros + 1).unwrap_or(0);
         }
         ixs.into_iter()
     })
 }
 
-fn get(index: usize, lib: &Liberties) -> bool {
-    let chunk = index / BITS;
-    let bit_index = index % BITS;
+#[inline(always)]
+pub(crate) fn get(index: BoardIdx, lib: &Liberties) -> bool {
+    let chunk = index.get() / BITS;
+    let bit_index = index.get() % BITS;
     (lib[chunk] & (1 << bit_index)) != 0
 }
 
@@ -83,8 +83,8 @@ impl Group {
     pub fn new_with_liberties(color: Color, stone: BoardIdx, liberties: Liberties) -> Self {
         Group {
             color,
-            origin: stone as u16,
-            last: stone as u16,
+            origin: stone.into(),
+            last: stone.into(),
             liberties,
             num_stones: 1,
         }
@@ -163,15 +163,16 @@ impl Group {
         self
     }
 
+    /// Flat indexes of the liberties, see [`Goban::index_of`](crate::pieces::goban::Goban::index_of).
     pub fn liberties(&self) -> Vec<usize> {
-        iter_ones(&self.liberties).collect()
+        iter_ones(&self.liberties).map(BoardIdx::get).collect()
     }
 
     pub fn iter<'a>(&self, next_stone: &'a [u16]) -> CircularGroupIter<'a> {
         CircularGroupIter {
             next_stone,
-            origin: self.origin as usize,
-            next: Some(self.origin as usize),
+            origin: self.origin,
+            next: Some(self.origin),
             num_stones: self.num_stones,
         }
     }
@@ -196,8 +197,8 @@ impl Index<usize> for Groups {
     }
 }
 
-impl IndexMut<BoardIdx> for Groups {
-    fn index_mut(&mut self, index: BoardIdx) -> &mut Self::Output {
+impl IndexMut<usize> for Groups {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         self.0[index].as_mut().unwrap()
     }
 }
@@ -237,20 +238,20 @@ impl Groups {
 #[derive(Copy, Clone)]
 pub struct CircularGroupIter<'a> {
     next_stone: &'a [u16],
-    origin: usize,
-    next: Option<usize>,
+    origin: u16,
+    next: Option<u16>,
     num_stones: u16,
 }
 
 impl Iterator for CircularGroupIter<'_> {
-    type Item = usize;
+    type Item = BoardIdx;
 
     fn next(&mut self) -> Option<Self::Item> {
         let origin = self.origin;
         let ret = self.next;
         self.next = self
             .next
-            .map(|stone_idx| self.next_stone[stone_idx] as usize)
+            .map(|stone_idx| self.next_stone[stone_idx as usize])
             .filter(move |&o| o != origin);
 
         #[cfg(debug_assertions)]
@@ -258,7 +259,7 @@ impl Iterator for CircularGroupIter<'_> {
             panic!("infinite loop detected")
         }
 
-        ret
+        ret.map(BoardIdx::from)
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -283,15 +284,15 @@ mod tests {
             next: Some(2),
             num_stones: 3,
         };
-        let iter2 = iter.clone();
+        let iter2 = iter;
 
-        assert_eq!(2, iter.next().unwrap());
-        assert_eq!(4, iter.next().unwrap());
-        assert_eq!(6, iter.next().unwrap());
+        assert_eq!(2, iter.next().unwrap().get());
+        assert_eq!(4, iter.next().unwrap().get());
+        assert_eq!(6, iter.next().unwrap().get());
         assert_eq!(None, iter.next());
         assert_eq!(None, iter.next());
 
-        assert_eq!(6, iter2.last().unwrap());
+        assert_eq!(6, iter2.last().unwrap().get());
 
         let mut iter = CircularGroupIter {
             next_stone: &a,
@@ -299,7 +300,7 @@ mod tests {
             next: Some(8),
             num_stones: 1,
         };
-        assert_eq!(8, iter.next().unwrap());
+        assert_eq!(8, iter.next().unwrap().get());
         assert_eq!(None, iter.next());
         assert_eq!(None, iter.next());
     }
@@ -4,14 +4,16 @@
 //!
 use arrayvec::ArrayVec;
 
+use goban::BoardIdx;
+
 pub(super) type Nat = u8;
-pub(super) type BoardIdx = usize;
 
-pub(super) type Connections<T=BoardIdx> = ArrayVec<T, 4>;
+pub(super) type Connections<T = BoardIdx> = ArrayVec<T, 4>;
 
 pub mod group;
 pub mod goban;
 pub mod stones;
+pub mod symmetry;
 pub mod territory;
 pub mod util;
 pub mod zobrist;
@@ -5,7 +5,9 @@ use std::fmt::Display;
 use std::fmt::Error;
 use std::fmt::Formatter;
 
-#[derive(Debug, Clone, PartialEq, Eq, Copy, Hash)]
+/// Colors are ordered by their value, black first.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum Color {
     White = 2,
@@ -46,7 +48,8 @@ impl Display for Color {
 }
 
 /// Stone on a goban.
-#[derive(PartialEq, Eq, Hash, Clone, Debug, Copy)]
+/// Points are ordered by their coordinates in row major order, then by their color.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Debug, Copy)]
 pub struct Point<T = MaybeColor> {
     pub coord: Coord,
     pub color: T,
@@ -0,0 +1,77 @@
+//! The 8 symmetries of the goban: the rotations and the reflections.
+//!
+//! The symmetries swapping the rows and the columns also swap the height and the width, so
+//! they work on the non square gobans too.
+
+use crate::pieces::util::coord::{Coord, Size};
+
+/// A symmetry of the goban, the rotations are clockwise with the row 0 at the top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Symmetry {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Upside down, the rows are reversed.
+    FlipRows,
+    /// Mirror, the columns are reversed.
+    FlipColumns,
+    /// Reflection on the diagonal from the top left corner.
+    Transpose,
+    /// Reflection on the diagonal from the top right corner.
+    AntiTranspose,
+}
+
+impl Symmetry {
+    /// All the symmetries, the identity first.
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipRows,
+        Symmetry::FlipColumns,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+    ];
+
+    /// True if the symmetry swaps the rows and the columns.
+    pub const fn transposes(self) -> bool {
+        matches!(
+            self,
+            Symmetry::Rotate90 | Symmetry::Rotate270 | Symmetry::Transpose | Symmetry::AntiTranspose
+        )
+    }
+
+    /// Size of the goban of the size once transformed.
+    pub const fn size(self, (height, width): Size) -> Size {
+        if self.transposes() {
+            (width, height)
+        } else {
+            (height, width)
+        }
+    }
+
+    /// The point of a goban of the size moved by the symmetry.
+    pub const fn apply(self, (height, width): Size, (row, col): Coord) -> Coord {
+        match self {
+            Symmetry::Identity => (row, col),
+            Symmetry::Rotate90 => (col, height - 1 - row),
+            Symmetry::Rotate180 => (height - 1 - row, width - 1 - col),
+            Symmetry::Rotate270 => (width - 1 - col, row),
+            Symmetry::FlipRows => (height - 1 - row, col),
+            Symmetry::FlipColumns => (row, width - 1 - col),
+            Symmetry::Transpose => (col, row),
+            Symmetry::AntiTranspose => (width - 1 - col, height - 1 - row),
+        }
+    }
+
+    /// The symmetry undoing this one.
+    pub const fn inverse(self) -> Symmetry {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            symmetry => symmetry,
+        }
+    }
+}
@@ -1,37 +1,52 @@
 //! Module with tools for getting the connected stones and liberties.
 
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 
-use crate::pieces::goban::Goban;
+use crate::pieces::goban::{BoardIdx, Goban};
+use crate::pieces::group::{get, set, Liberties, EMPTY_LIBERTIES};
 use crate::pieces::stones::Point;
 use crate::pieces::stones::{Color, EMPTY};
 
+/// Number of empty points of the goban by owner, see [`Goban::count_territory`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct TerritoryCount {
+    pub black: usize,
+    pub white: usize,
+    /// Empty points touching both colors, or no stone at all.
+    pub dame: usize,
+}
+
 impl Goban {
     ///
     /// Get the group of stones connected to a stone. with a Breadth First Search,
     /// works for EMPTY stones too.
     ///
-    /// Ex: Passing a stone 'a' it will return and HashSet [a,b,t,z] with the string where the
-    /// stone is.
+    /// Ex: Passing a stone 'a' it will return and a set [a,b,t,z] with the string where the
+    /// stone is, ordered in row major order.
     /// It will return the stone alone if it's lonely
     ///
-    pub fn get_group_from_point(&self, stone: Point) -> HashSet<Point> {
-        let mut explored = HashSet::<Point>::new();
-        explored.insert(stone);
+    pub fn get_group_from_point(&self, stone: Point) -> BTreeSet<Point> {
+        let mut visited = EMPTY_LIBERTIES;
+        self.get_group_from_point_visited(stone, &mut visited)
+    }
 
-        let mut to_explore: Vec<Point> = self
-            .get_connected_points(stone.coord)
-            .filter(|p| p.color == stone.color)
-            .collect(); // Acquiring all the neighbors
+    /// Flood fill from the stone, the points explored are marked in the visited bitset.
+    fn get_group_from_point_visited(&self, stone: Point, visited: &mut Liberties) -> BTreeSet<Point> {
+        let size = self.size();
+        let mut explored = BTreeSet::new();
+        set::<true>(BoardIdx::from_coord(size, stone.coord), visited);
+        let mut to_explore = vec![stone];
 
         while let Some(stone_to_explore) = to_explore.pop() {
             // exploring the graph
             explored.insert(stone_to_explore);
-
-            to_explore.extend(
-                self.get_connected_points(stone_to_explore.coord)
-                    .filter(|p| p.color == stone.color && !explored.contains(p)),
-            )
+            for point in self.get_connected_points(stone_to_explore.coord) {
+                let idx = BoardIdx::from_coord(size, point.coord);
+                if point.color == stone.color && !get(idx, visited) {
+                    set::<true>(idx, visited);
+                    to_explore.push(point);
+                }
+            }
         }
         explored
     }
@@ -41,23 +56,24 @@ impl Goban {
     /// stones.
     /// Use a breadth first search to deduce the groups of connected stones.
     /// Get stones connected. [[x,y,z],[a,e,r]] example of return.
+    /// The groups are in the order of their first stone in the iterator.
     ///
     pub fn get_chains_from_stones(
         &self,
         stones: impl Iterator<Item = Point>,
-    ) -> Vec<HashSet<Point>> {
-        let mut groups_of_stones: Vec<HashSet<Point>> = Default::default();
+    ) -> Vec<BTreeSet<Point>> {
+        let mut groups_of_stones: Vec<BTreeSet<Point>> = Default::default();
+        let mut visited = EMPTY_LIBERTIES;
         for s in stones {
-            let is_handled = groups_of_stones.iter().any(|set| set.contains(&s));
-
-            if !is_handled {
-                groups_of_stones.push(self.get_group_from_point(s))
+            if !get(BoardIdx::from_coord(self.size(), s.coord), &visited) {
+                groups_of_stones.push(self.get_group_from_point_visited(s, &mut visited))
             }
         }
         groups_of_stones
     }
 
-    /// Get two iterators of empty points. The first one is the territory of black the second is white territory
+    /// Get two iterators of empty points. The first one is the territory of black the second is white territory.
+    /// The points are yielded in row major order.
     pub fn get_territories(&self) -> (impl Iterator<Item = Point>, impl Iterator<Item = Point>) {
         let empty_chains =
             self.get_chains_from_stones(self.get_empty_coords().map(|coord| Point {
@@ -83,11 +99,13 @@ impl Goban {
                 }
             }
             if neutral.0 && !neutral.1 {
-                black_territory.extend(empty_group.into_iter())
+                black_territory.extend(empty_group)
             } else if !neutral.0 && neutral.1 {
-                white_territory.extend(empty_group.into_iter())
+                white_territory.extend(empty_group)
             }
         }
+        black_territory.sort_unstable_by_key(|point| point.coord);
+        white_territory.sort_unstable_by_key(|point| point.coord);
         (black_territory.into_iter(), white_territory.into_iter())
     }
 
@@ -102,4 +120,25 @@ impl Goban {
         let (black_territory, white_territory) = self.get_territories();
         (black_territory.count(), white_territory.count())
     }
+
+    /// Counts the territories of [`Goban::get_territories`] with the other empty points.
+    /// On an empty goban everything is dame.
+    pub fn count_territory(&self) -> TerritoryCount {
+        let (black, white) = self.calculate_territories();
+        TerritoryCount {
+            black,
+            white,
+            dame: self.get_empty_coords().count() - black - white,
+        }
+    }
+
+    /// Counts the territory of the color, see [`Goban::count_territory`].
+    #[inline]
+    pub fn count_territory_for(&self, color: Color) -> usize {
+        let count = self.count_territory();
+        match color {
+            Color::Black => count.black,
+            Color::White => count.white,
+        }
+    }
 }
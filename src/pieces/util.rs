@@ -4,6 +4,7 @@ pub mod coord {
     use arrayvec::ArrayVec;
 
     /// Defining the policy for the columns.
+    /// Coordinates are (row, column) so the tuple ordering is the row major order.
     pub type Coord = (Nat, Nat);
     pub type Size = (u8, u8);
 
@@ -13,15 +14,17 @@ pub mod coord {
         (coord.0) < height && (coord.1) < width
     }
 
+    /// Flat index of the point in row major order, see [`BoardIdx`].
     #[inline(always)]
     pub const fn two_to_1dim(size: Size, point: Coord) -> usize {
-        (point.0 as u32 * size.0 as u32 + point.1 as u32) as usize
+        point.0 as usize * size.1 as usize + point.1 as usize
     }
 
+    /// Point of the flat index in row major order, see [`BoardIdx`].
     #[inline(always)]
     pub const fn one_to_2dim(size: Size, index: usize) -> Coord {
         (
-            (index / size.0 as usize) as u8,
+            (index / size.1 as usize) as u8,
             (index % size.1 as usize) as u8,
         )
     }
@@ -30,7 +33,7 @@ pub mod coord {
     macro_rules! one2dim {
         ($size: expr, $index: expr) => {
             (
-                ($index / $size.0 as usize) as u8,
+                ($index / $size.1 as usize) as u8,
                 ($index % $size.1 as usize) as u8,
             )
         };
@@ -63,6 +66,77 @@ pub mod coord {
         ]
     }
 
+    /// Errors when reading a point written in SGF.
+    #[derive(Clone, Eq, PartialEq, Debug, Copy)]
+    pub enum SgfCoordError {
+        /// The point isn't two letters.
+        Malformed,
+        /// Uppercase letters, used by gobans bigger than 26 lines, aren't supported.
+        Uppercase,
+        /// The point is out of the goban.
+        OutOfBounds(Coord),
+    }
+
+    impl std::fmt::Display for SgfCoordError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                SgfCoordError::Malformed => write!(f, "a SGF point is two letters"),
+                SgfCoordError::Uppercase => {
+                    write!(f, "uppercase letters in SGF points aren't supported")
+                }
+                SgfCoordError::OutOfBounds(coord) => {
+                    write!(f, "the point {coord:?} is out of the goban")
+                }
+            }
+        }
+    }
+
+    impl std::error::Error for SgfCoordError {}
+
+    /// Point from the coordinates of `sgf_parser`, the column then the row starting from 1.
+    #[inline(always)]
+    pub(crate) const fn sgf_pair_to_coord((column, row): (u8, u8)) -> Coord {
+        (row - 1, column - 1)
+    }
+
+    /// Coordinates of `sgf_parser` of the point, the column then the row starting from 1.
+    #[inline(always)]
+    pub(crate) const fn coord_to_sgf_pair((row, column): Coord) -> (u8, u8) {
+        (column + 1, row + 1)
+    }
+
+    /// Reads the letters of a SGF point into the coordinates of `sgf_parser`.
+    pub(crate) fn parse_sgf_pair(point: &str) -> Result<(u8, u8), SgfCoordError> {
+        let letter = |c: u8| match c {
+            b'a'..=b'z' => Ok(c - b'a' + 1),
+            b'A'..=b'Z' => Err(SgfCoordError::Uppercase),
+            _ => Err(SgfCoordError::Malformed),
+        };
+        match point.as_bytes() {
+            &[column, row] => Ok((letter(column)?, letter(row)?)),
+            _ => Err(SgfCoordError::Malformed),
+        }
+    }
+
+    /// Reads a SGF point like `"cb"`, the column then the row from `'a'`, so `"cb"` is (1, 2).
+    ///
+    /// # Errors
+    /// If the point isn't two lowercase letters or is out of a goban of the size.
+    pub fn sgf_to_coord(point: &str, size: Size) -> Result<Coord, SgfCoordError> {
+        let coord = sgf_pair_to_coord(parse_sgf_pair(point)?);
+        if is_coord_valid(size, coord) {
+            Ok(coord)
+        } else {
+            Err(SgfCoordError::OutOfBounds(coord))
+        }
+    }
+
+    /// Writes the point in SGF, the reverse of [`sgf_to_coord`].
+    pub fn coord_to_sgf(coord: Coord) -> String {
+        let (column, row) = coord_to_sgf_pair(coord);
+        [column, row].iter().map(|&c| (b'a' + c - 1) as char).collect()
+    }
+
     pub trait IntoCoord {
         fn into_coord(self, size: Size) -> Coord;
     }
@@ -75,7 +149,7 @@ pub mod coord {
 
     impl IntoCoord for BoardIdx {
         fn into_coord(self, size: Size) -> Coord {
-            one_to_2dim(size, self)
+            self.to_coord(size)
         }
     }
 
@@ -85,7 +159,7 @@ pub mod coord {
 
     impl IntoIdx for Coord {
         fn into_idx(self, size: Size) -> BoardIdx {
-            two_to_1dim(size, self)
+            BoardIdx::from_coord(size, self)
         }
     }
 
@@ -2,6 +2,9 @@ use crate::pieces::stones::Color;
 
 const BOARD_SIZE: usize = 19 * 19;
 
+/// Keys of the zobrist hashes, the black keys of the flat indexes then the white ones.
+/// The keys are written in the source, they never change between versions or processes so the
+/// hashes stored on disk stay valid, see [`ZobristTable`] to store them with the hashes.
 pub const ZOBRIST: [u64; BOARD_SIZE * 2] = [
     0xffed071d11b312ad,
     0xecf90189c0cadd3d,
@@ -732,3 +735,109 @@ pub const fn index_zobrist(index: usize, color: Color) -> u64 {
     let page_idx = color as usize - 1;
     ZOBRIST[index + (BOARD_SIZE * page_idx)]
 }
+
+/// Version of the layout of [`ZobristTable::to_bytes`].
+pub const ZOBRIST_TABLE_VERSION: u16 = 1;
+
+const TABLE_MAGIC: &[u8; 4] = b"GOZT";
+const TABLE_HEADER_LEN: usize = 8;
+
+/// Errors when reading a zobrist table from bytes.
+#[derive(Clone, Eq, PartialEq, Debug, Copy)]
+pub enum ZobristTableError {
+    /// The bytes don't start with the magic of a table.
+    BadMagic,
+    /// The table was written by another version of the layout.
+    UnsupportedVersion(u16),
+    /// The number of keys isn't the one of a 19x19 goban, or the bytes don't hold them all.
+    Length { expected: usize, found: usize },
+}
+
+impl std::fmt::Display for ZobristTableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ZobristTableError::BadMagic => write!(f, "not a zobrist table"),
+            ZobristTableError::UnsupportedVersion(version) => {
+                write!(f, "unsupported version {version} of the zobrist table")
+            }
+            ZobristTableError::Length { expected, found } => {
+                write!(f, "expected {expected} zobrist keys, found {found}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ZobristTableError {}
+
+/// The keys of the zobrist hashes, to be stored next to the hashes of an opening book or of a
+/// transposition table. The default table holds the keys of [`ZOBRIST`], the ones of the
+/// gobans, and a book can ship its own table, see [`Goban::zobrist_hash_with`].
+///
+/// The bytes are the magic `GOZT`, the version and the number of keys on 2 bytes each, then
+/// the keys on 8 bytes each, all little endian.
+///
+/// [`Goban::zobrist_hash_with`]: crate::pieces::goban::Goban::zobrist_hash_with
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ZobristTable {
+    keys: Box<[u64; BOARD_SIZE * 2]>,
+}
+
+impl Default for ZobristTable {
+    fn default() -> Self {
+        ZobristTable {
+            keys: Box::new(ZOBRIST),
+        }
+    }
+}
+
+impl ZobristTable {
+    /// Key of the stone on the flat index, like [`index_zobrist`].
+    pub fn key(&self, index: usize, color: Color) -> u64 {
+        self.keys[index + BOARD_SIZE * (color as usize - 1)]
+    }
+
+    /// Writes the table with its version.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(TABLE_HEADER_LEN + 8 * self.keys.len());
+        bytes.extend_from_slice(TABLE_MAGIC);
+        bytes.extend_from_slice(&ZOBRIST_TABLE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.keys.len() as u16).to_le_bytes());
+        for key in self.keys.iter() {
+            bytes.extend_from_slice(&key.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Reads a table written by [`ZobristTable::to_bytes`].
+    ///
+    /// # Errors
+    /// If the bytes aren't a table of this version with the keys of a 19x19 goban.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ZobristTableError> {
+        if bytes.len() < TABLE_HEADER_LEN || &bytes[0..4] != TABLE_MAGIC {
+            return Err(ZobristTableError::BadMagic);
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != ZOBRIST_TABLE_VERSION {
+            return Err(ZobristTableError::UnsupportedVersion(version));
+        }
+        let count = u16::from_le_bytes([bytes[6], bytes[7]]) as usize;
+        let keys = &bytes[TABLE_HEADER_LEN..];
+        if count != BOARD_SIZE * 2 {
+            return Err(ZobristTableError::Length {
+                expected: BOARD_SIZE * 2,
+                found: count,
+            });
+        }
+        if keys.len() != 8 * count {
+            return Err(ZobristTableError::Length {
+                expected: count,
+                found: keys.len() / 8,
+            });
+        }
+        let mut table = ZobristTable::default();
+        for (key, bytes) in table.keys.iter_mut().zip(keys.chunks_exact(8)) {
+            *key = u64::from_le_bytes(bytes.try_into().expect("Chunks of 8 bytes"));
+        }
+        Ok(table)
+    }
+}
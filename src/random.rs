@@ -0,0 +1,63 @@
+//! Random positions and random games, for benchmarks and tests.
+//! Everything only depends on the random generator passed, so a seeded generator always
+//! gives the same goban or game.
+
+use rand::seq::IndexedRandom;
+use rand::Rng;
+
+use crate::pieces::goban::Goban;
+use crate::pieces::stones::Color;
+use crate::pieces::util::coord::{Coord, Size};
+use crate::rules::game::Game;
+use crate::rules::{GobanSizes, Move, Rule};
+
+impl Goban {
+    /// Creates a goban with `stones` stones placed at random empty points, alternating colors
+    /// starting with black. A placement that would leave a chain without liberties is never
+    /// chosen, so if no point is left the goban can have less stones than requested.
+    pub fn random(size: Size, stones: usize, rng: &mut impl Rng) -> Goban {
+        let mut goban = Goban::new(size);
+        let mut color = Color::Black;
+        for _ in 0..stones {
+            let candidates: Vec<Coord> = goban
+                .get_empty_coords()
+                .filter(|&point| goban.keeps_liberties(point, color))
+                .collect();
+            let Some(&point) = candidates.choose(rng) else {
+                break;
+            };
+            goban.push(point, color);
+            color = !color;
+        }
+        goban
+    }
+
+    /// Returns true if a stone of the color on the point leaves every chain with liberties.
+    fn keeps_liberties(&self, point: Coord, color: Color) -> bool {
+        let groups = self.get_connected_groups(point);
+        let captures = groups
+            .iter()
+            .any(|group| group.color != color && group.is_atari());
+        let connected_with_liberties = groups
+            .iter()
+            .any(|group| group.color == color && !group.is_atari());
+        !captures && (self.point_has_liberty(point) || connected_with_liberties)
+    }
+}
+
+impl Game {
+    /// Creates a game where at most `moves` random legal moves were played.
+    /// When no legal move is left the player passes, the game stops if it's over.
+    pub fn random_game(size: GobanSizes, rule: Rule, moves: usize, rng: &mut impl Rng) -> Game {
+        let mut game = Game::new(size, rule);
+        for _ in 0..moves {
+            if game.is_over() {
+                break;
+            }
+            let legals: Vec<Coord> = game.legals().collect();
+            let m = legals.choose(rng).map_or(Move::Pass, |&point| point.into());
+            game.play(m);
+        }
+        game
+    }
+}
@@ -0,0 +1,133 @@
+//! # Annotations
+//! Comments, markup and move quality attached to the moves of a game, exported in SGF by
+//! [`Game::to_sgf`] and read back by [`Game::from_sgf`].
+//! # Example
+//! ```
+//! use goban::rules::annotation::Annotation;
+//! use goban::rules::game::Game;
+//! use goban::rules::Move;
+//!
+//! let mut game = Game::default();
+//! game.play(Move::Play(3, 3));
+//! let mut annotation = Annotation::comment("Star point [D16]");
+//! annotation.markup.triangles.insert((3, 3));
+//! game.annotate(1, annotation.clone());
+//! assert!(game.to_sgf().contains(r"C[Star point [D16\]]"));
+//!
+//! let game = Game::from_sgf(&game.to_sgf()).unwrap();
+//! assert_eq!(game.annotation(1), Some(&annotation));
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::pieces::util::coord::Coord;
+use crate::rules::game::Game;
+
+/// Shapes and labels drawn on points of the goban.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Markup {
+    pub triangles: BTreeSet<Coord>,
+    pub squares: BTreeSet<Coord>,
+    pub circles: BTreeSet<Coord>,
+    pub labels: BTreeMap<Coord, String>,
+}
+
+impl Markup {
+    /// Returns true if nothing is drawn.
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+            && self.squares.is_empty()
+            && self.circles.is_empty()
+            && self.labels.is_empty()
+    }
+}
+
+/// Judgement of a move, the `TE`, `BM`, `DO` and `IT` properties of SGF.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MoveQuality {
+    /// Tesuji, a good move.
+    Good {
+        emphasized: bool,
+    },
+    Bad {
+        emphasized: bool,
+    },
+    Doubtful,
+    Interesting,
+}
+
+impl MoveQuality {
+    /// The conventional symbol shown next to the move, like `!` or `?!`.
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            MoveQuality::Good { emphasized: false } => "!",
+            MoveQuality::Good { emphasized: true } => "!!",
+            MoveQuality::Bad { emphasized: false } => "?",
+            MoveQuality::Bad { emphasized: true } => "??",
+            MoveQuality::Doubtful => "?!",
+            MoveQuality::Interesting => "!?",
+        }
+    }
+}
+
+/// Comment, markup and quality of the move of a node of the record.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Annotation {
+    pub comment: Option<String>,
+    pub markup: Markup,
+    pub quality: Option<MoveQuality>,
+}
+
+impl Annotation {
+    /// Annotation with only a comment.
+    pub fn comment(comment: impl Into<String>) -> Self {
+        Annotation {
+            comment: Some(comment.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Annotation with only the quality of the move.
+    pub fn quality(quality: MoveQuality) -> Self {
+        Annotation {
+            quality: Some(quality),
+            ..Default::default()
+        }
+    }
+
+    /// Returns true if there is no comment, no markup and no quality.
+    pub fn is_empty(&self) -> bool {
+        self.comment.is_none() && self.markup.is_empty() && self.quality.is_none()
+    }
+}
+
+impl Game {
+    /// Attaches the annotation to the move number, counted from 1 like the SGF nodes. The move
+    /// number 0 is the root, for a comment on the whole game. An empty annotation removes the
+    /// previous one.
+    pub fn annotate(&mut self, move_number: usize, annotation: Annotation) {
+        if annotation.is_empty() {
+            self.annotations.remove(&move_number);
+        } else {
+            self.annotations.insert(move_number, annotation);
+        }
+    }
+
+    /// The annotation of the move number, 0 for the root.
+    #[inline]
+    pub fn annotation(&self, move_number: usize) -> Option<&Annotation> {
+        self.annotations.get(&move_number)
+    }
+
+    /// The quality of the move number, from its annotation.
+    #[inline]
+    pub fn quality_of(&self, move_number: usize) -> Option<MoveQuality> {
+        self.annotation(move_number)?.quality
+    }
+
+    /// Every annotation by move number.
+    #[inline]
+    pub fn annotations(&self) -> &BTreeMap<usize, Annotation> {
+        &self.annotations
+    }
+}
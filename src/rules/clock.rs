@@ -0,0 +1,174 @@
+//! # Game clock
+//! Time settings of a game and time left of each player, as told by a controller or read
+//! from a record. The clock doesn't run by itself, the time left is set from outside.
+//! # Example
+//! ```
+//! use std::time::Duration;
+//! use goban::pieces::stones::Color;
+//! use goban::rules::clock::{TimeControl, TimeLeft};
+//! use goban::rules::game::Game;
+//!
+//! let mut game = Game::default();
+//! game.clock_mut().set_control(TimeControl::Canadian {
+//!     main: Duration::from_secs(600),
+//!     period: Duration::from_secs(300),
+//!     stones: 25,
+//! });
+//! game.clock_mut().set_time_left(Color::Black, TimeLeft::overtime(Duration::from_secs(140), 12));
+//! assert!(game.clock().time_left(Color::Black).in_overtime());
+//! assert_eq!(game.clock().time_left(Color::White).time, Duration::from_secs(600));
+//! ```
+
+use std::time::Duration;
+
+use crate::pieces::stones::Color;
+use crate::rules::game::Game;
+
+/// How the time of the players is limited.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimeControl {
+    /// No time limit.
+    #[default]
+    None,
+    /// The game is lost when the main time is over.
+    Absolute { main: Duration },
+    /// After the main time, `stones` moves must be played in each period.
+    Canadian {
+        main: Duration,
+        period: Duration,
+        stones: u32,
+    },
+    /// After the main time, each move must be played in a period, going over a period
+    /// consumes it.
+    ByoYomi {
+        main: Duration,
+        period: Duration,
+        periods: u32,
+    },
+}
+
+impl TimeControl {
+    /// Time left of a player at the start of the game.
+    pub fn initial_time_left(&self) -> TimeLeft {
+        match *self {
+            TimeControl::None => TimeLeft::main(Duration::ZERO),
+            TimeControl::Absolute { main } => TimeLeft::main(main),
+            TimeControl::Canadian {
+                main,
+                period,
+                stones,
+            } if main.is_zero() => TimeLeft::overtime(period, stones),
+            TimeControl::ByoYomi {
+                main,
+                period,
+                periods,
+            } if main.is_zero() => TimeLeft::overtime(period, periods),
+            TimeControl::Canadian { main, .. } | TimeControl::ByoYomi { main, .. } => {
+                TimeLeft::main(main)
+            }
+        }
+    }
+}
+
+/// Time left of a player. In the main time `stones` is 0, in the overtime it's the stones
+/// to play in the period for the canadian overtime, or the periods left for the byo-yomi.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeLeft {
+    pub time: Duration,
+    pub stones: u32,
+}
+
+impl TimeLeft {
+    pub const fn main(time: Duration) -> Self {
+        TimeLeft { time, stones: 0 }
+    }
+
+    pub const fn overtime(time: Duration, stones: u32) -> Self {
+        TimeLeft { time, stones }
+    }
+
+    #[inline]
+    pub const fn in_overtime(&self) -> bool {
+        self.stones != 0
+    }
+}
+
+/// Time control of the game with the time left of each player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Clock {
+    control: TimeControl,
+    black: TimeLeft,
+    white: TimeLeft,
+}
+
+impl Clock {
+    /// Clock with the time left of the players at the start of the game.
+    pub fn new(control: TimeControl) -> Self {
+        Clock {
+            control,
+            black: control.initial_time_left(),
+            white: control.initial_time_left(),
+        }
+    }
+
+    #[inline]
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    /// Changes the time control, the time left of the players is reset.
+    pub fn set_control(&mut self, control: TimeControl) {
+        *self = Clock::new(control);
+    }
+
+    #[inline]
+    pub fn time_left(&self, color: Color) -> TimeLeft {
+        match color {
+            Color::Black => self.black,
+            Color::White => self.white,
+        }
+    }
+
+    pub fn set_time_left(&mut self, color: Color, time_left: TimeLeft) {
+        match color {
+            Color::Black => self.black = time_left,
+            Color::White => self.white = time_left,
+        }
+    }
+
+    pub(crate) fn swap_colors_mut(&mut self) {
+        std::mem::swap(&mut self.black, &mut self.white);
+    }
+}
+
+impl Game {
+    /// Clock after the move number, 0 for the start of the game. It's the last time left
+    /// recorded at or before the move, by [`Game::record_time_left`] or by the `BL`, `WL`, `OB`
+    /// and `OW` of a SGF, the initial time left of the time control before any record.
+    pub fn clock_at(&self, move_number: usize) -> Clock {
+        self.clocks
+            .range(..=move_number)
+            .next_back()
+            .map_or_else(|| Clock::new(self.clock.control()), |(_, &clock)| clock)
+    }
+
+    /// Sets the time left of the player and records the clock at the last move, like the `BL`
+    /// and `WL` of a SGF node. The clocks recorded after the last move, before an undo, are
+    /// removed.
+    pub fn record_time_left(&mut self, color: Color, time_left: TimeLeft) {
+        let move_number = self.history.len();
+        self.clock.set_time_left(color, time_left);
+        self.clocks.split_off(&(move_number + 1));
+        self.clocks.insert(move_number, self.clock);
+    }
+
+    /// The `OT` of the loaded SGF when it isn't a byo-yomi like `5x30 byo-yomi` or a canadian
+    /// overtime like `25/600 Canadian`, the time control of the game is then only the main time.
+    #[inline]
+    pub fn sgf_overtime(&self) -> Option<&str> {
+        self.overtime.as_deref()
+    }
+}
@@ -151,7 +151,7 @@ impl Game {
                     .or_insert(0);
             }
         }
-        let all_chains = self.goban.chains().enumerate();
+        let all_chains = self.goban.get_groups();
         let string_with_2eyes = chains_wth_eye
             .into_iter()
             .filter(|(_, v)| *v >= 2)
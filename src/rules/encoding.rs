@@ -0,0 +1,81 @@
+//! Compact encoding of the moves on 16 bits, for storing a lot of games.
+//! A play is encoded as its flat index in row major order, pass and resigns use reserved values
+//! at the top of the range. The encoding has room for boards up to 25x25, but the crate is
+//! limited to 19x19.
+
+use crate::pieces::stones::Color;
+use crate::pieces::util::coord::Size;
+use crate::pieces::Nat;
+use crate::rules::game::Game;
+use crate::rules::{EncodingError, GobanSizes, Move, Rule};
+
+/// Value of a pass.
+const ENCODED_PASS: u16 = u16::MAX;
+/// Value of a resign of black.
+const ENCODED_RESIGN_BLACK: u16 = u16::MAX - 1;
+/// Value of a resign of white.
+const ENCODED_RESIGN_WHITE: u16 = u16::MAX - 2;
+
+impl Move {
+    /// Encodes the move on 16 bits for a goban of the given size.
+    /// A play is `row * width + column`, a pass is `u16::MAX`, a resign is `u16::MAX - 1` when
+    /// black resigns and `u16::MAX - 2` when white resigns.
+    ///
+    /// # Panics
+    /// In debug builds, if the play is outside the goban.
+    pub fn encode(&self, (height, width): Size) -> u16 {
+        match *self {
+            Move::Pass => ENCODED_PASS,
+            Move::Resign(Color::Black) => ENCODED_RESIGN_BLACK,
+            Move::Resign(Color::White) => ENCODED_RESIGN_WHITE,
+            Move::Play(x, y) => {
+                debug_assert!(x < height && y < width, "Move {self:?} outside the goban");
+                x as u16 * width as u16 + y as u16
+            }
+        }
+    }
+
+    /// Decodes a move encoded by [`Move::encode`] for a goban of the given size.
+    ///
+    /// # Errors
+    /// Returns `InvalidValue` if the value isn't a point of the goban nor a reserved value.
+    pub fn decode(value: u16, (height, width): Size) -> Result<Move, EncodingError> {
+        match value {
+            ENCODED_PASS => Ok(Move::Pass),
+            ENCODED_RESIGN_BLACK => Ok(Move::Resign(Color::Black)),
+            ENCODED_RESIGN_WHITE => Ok(Move::Resign(Color::White)),
+            v if v < height as u16 * width as u16 => Ok(Move::Play(
+                (v / width as u16) as Nat,
+                (v % width as u16) as Nat,
+            )),
+            v => Err(EncodingError::InvalidValue(v)),
+        }
+    }
+}
+
+impl Game {
+    /// Returns the moves played encoded with [`Move::encode`].
+    pub fn encode_history(&self) -> Vec<u16> {
+        let size = self.size();
+        self.moves().map(|m| m.encode(size)).collect()
+    }
+
+    /// Creates a game by replaying the encoded moves from an empty goban, each move is checked.
+    ///
+    /// # Errors
+    /// Returns the index of the first move which can't be decoded or played, with the reason.
+    pub fn from_encoded_history(
+        size: GobanSizes,
+        rule: Rule,
+        moves: &[u16],
+    ) -> Result<Game, (usize, EncodingError)> {
+        let mut game = Game::new(size, rule);
+        let goban_size = game.size();
+        for (index, &value) in moves.iter().enumerate() {
+            let m = Move::decode(value, goban_size).map_err(|e| (index, e))?;
+            game.try_play(m)
+                .map_err(|e| (index, EncodingError::IllegalMove(e)))?;
+        }
+        Ok(game)
+    }
+}
@@ -0,0 +1,91 @@
+//! Queue of the events of a game, for the frontends reading the changes after the fact.
+//!
+//! The events are opt-in: nothing is recorded until [`Game::enable_events`], so a game nobody
+//! drains doesn't grow. Once enabled they accumulate in the order of the changes until
+//! [`Game::take_events`]. The undo and the jumps in the history aren't events.
+//! # Example
+//! ```
+//! use goban::pieces::stones::Color;
+//! use goban::rules::events::GameEvent;
+//! use goban::rules::game::Game;
+//! use goban::rules::{EndGame, GobanSizes, Move, CHINESE};
+//!
+//! let mut game = Game::new(GobanSizes::Nine, CHINESE);
+//! game.enable_events();
+//! game.play(Move::Play(4, 4)).play(Move::Resign(Color::White));
+//! assert_eq!(
+//!     game.take_events(),
+//!     [
+//!         GameEvent::MovePlayed { color: Color::Black, mv: Move::Play(4, 4), captures: 0 },
+//!         GameEvent::Resigned(Color::White),
+//!         GameEvent::GameEnded(EndGame::WinnerByResign(Color::Black)),
+//!     ]
+//! );
+//! assert!(game.take_events().is_empty());
+//! ```
+
+use crate::pieces::goban::GroupIdx;
+use crate::pieces::stones::Color;
+use crate::rules::game::Game;
+use crate::rules::{EndGame, Move};
+
+/// A change of the game.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    /// A stone was played, `mv` is always a [`Move::Play`]. `captures` is the number of stones
+    /// removed, the own chain of a suicide included.
+    MovePlayed { color: Color, mv: Move, captures: u32 },
+    /// The player of this color passed.
+    Passed(Color),
+    /// The player of this color resigned, followed by [`GameEvent::GameEnded`].
+    Resigned(Color),
+    /// The game got its outcome, by resign or at the end of the scoring phase. The passes alone
+    /// don't end the game, the players may still score it.
+    GameEnded(EndGame),
+    ScoringStarted,
+    /// The chain, on the goban of the scoring session, was marked dead or alive.
+    DeadMarkToggled(GroupIdx),
+}
+
+impl Game {
+    /// Starts recording the events, see the [module](self).
+    pub fn enable_events(&mut self) {
+        if self.events.is_none() {
+            self.events = Some(vec![]);
+            if let Some(session) = &mut self.scoring {
+                session.record_toggles(true);
+            }
+        }
+    }
+
+    /// Stops recording the events, the events not taken are dropped.
+    pub fn disable_events(&mut self) {
+        self.events = None;
+        if let Some(session) = &mut self.scoring {
+            session.record_toggles(false);
+        }
+    }
+
+    /// Returns the events since the last call, in the order they happened. Empty if the events
+    /// aren't enabled.
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        self.flush_toggles();
+        self.events.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// Records the event if the events are enabled.
+    pub(super) fn push_event(&mut self, event: GameEvent) {
+        self.flush_toggles();
+        if let Some(events) = &mut self.events {
+            events.push(event);
+        }
+    }
+
+    /// Moves the marks of the scoring session to the events, they are kept by the session
+    /// which doesn't have access to the game.
+    pub(super) fn flush_toggles(&mut self) {
+        if let (Some(events), Some(session)) = (&mut self.events, &mut self.scoring) {
+            events.extend(session.take_toggles().map(GameEvent::DeadMarkToggled));
+        }
+    }
+}
@@ -1,20 +1,25 @@
 use crate::pieces::goban::*;
-use crate::pieces::stones::Color::{Black, White};
-use crate::pieces::stones::{Color, Stone, EMPTY};
-use crate::pieces::util::coord::{corner_points, is_coord_valid, two_to_1dim, Coord, Size};
+use crate::pieces::stones::{Color, Stone};
+use crate::pieces::symmetry::Symmetry;
+use crate::pieces::util::coord::{
+    corner_points, is_coord_valid, two_to_1dim, Coord, Size,
+};
 use crate::pieces::{Connections, Nat};
-use crate::rules::EndGame::{Draw, WinnerByScore};
+use crate::rules::annotation::{Annotation, Markup};
+use crate::rules::clock::Clock;
+use crate::rules::history::{History, Record};
+use crate::rules::events::GameEvent;
+use crate::rules::legal_cache::LegalMoveCache;
+use crate::rules::scoring::ScoringSession;
 use crate::rules::Rule;
-use crate::rules::{EndGame, GobanSizes, IllegalRules, Move, ScoreRules};
+use crate::rules::{EndGame, GobanSizes, IllegalReason, IllegalRules, Move, ScoreRules};
 use crate::rules::{PlayError, CHINESE};
-use hash_hasher::{HashBuildHasher, HashHasher};
-use indexmap::IndexSet;
-use std::hash::BuildHasherDefault;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
 use std::ops::Deref;
 
 /// Most important struct of the library, it's the entry point.
 /// It represents a Game of Go.
-#[derive(Clone, Debug)]
 pub struct Game {
     pub(super) goban: Goban,
     pub(super) passes: u32,
@@ -24,8 +29,95 @@ pub struct Game {
     pub(super) turn: Color,
     pub(super) rule: Rule,
     pub(super) handicap: u32,
-    pub(super) history: IndexSet<Goban, BuildHasherDefault<HashHasher>>,
+    pub(super) history: History,
     pub(super) ko_point: Option<Coord>,
+    /// The SGF the game was loaded from, kept to export it without loss.
+    pub(super) sgf_source: Option<sgf_parser::GameTree>,
+    /// Some when the players are marking the dead stones.
+    pub(super) scoring: Option<ScoringSession>,
+    /// Some when the cache of the legal moves is enabled.
+    pub(super) legal_cache: Option<LegalMoveCache>,
+    pub(super) clock: Clock,
+    /// Clock after the moves with a recorded time left, by move number, 0 for the root.
+    pub(super) clocks: BTreeMap<usize, Clock>,
+    /// The `OT` of the loaded SGF when it isn't a known overtime.
+    pub(super) overtime: Option<String>,
+    /// Comments and markup by move number, 0 for the root.
+    pub(super) annotations: BTreeMap<usize, Annotation>,
+    /// Some when the events are recorded, see [`Game::enable_events`].
+    pub(super) events: Option<Vec<GameEvent>>,
+}
+
+impl Clone for Game {
+    fn clone(&self) -> Self {
+        Game {
+            goban: self.goban.clone(),
+            passes: self.passes,
+            prisoners: self.prisoners,
+            outcome: self.outcome,
+            turn: self.turn,
+            rule: self.rule,
+            handicap: self.handicap,
+            history: self.history.clone(),
+            ko_point: self.ko_point,
+            sgf_source: self.sgf_source.clone(),
+            scoring: self.scoring.clone(),
+            legal_cache: self.legal_cache.clone(),
+            clock: self.clock,
+            clocks: self.clocks.clone(),
+            overtime: self.overtime.clone(),
+            annotations: self.annotations.clone(),
+            events: self.events.clone(),
+        }
+    }
+
+    /// Reuses the buffers of the gobans, for the searches copying a root position again and
+    /// again.
+    fn clone_from(&mut self, source: &Self) {
+        self.goban.clone_from(&source.goban);
+        self.passes = source.passes;
+        self.prisoners = source.prisoners;
+        self.outcome = source.outcome;
+        self.turn = source.turn;
+        self.rule = source.rule;
+        self.handicap = source.handicap;
+        self.history.clone_from(&source.history);
+        self.ko_point = source.ko_point;
+        self.sgf_source.clone_from(&source.sgf_source);
+        self.scoring.clone_from(&source.scoring);
+        self.legal_cache.clone_from(&source.legal_cache);
+        self.clock = source.clock;
+        self.clocks.clone_from(&source.clocks);
+        self.overtime.clone_from(&source.overtime);
+        self.annotations.clone_from(&source.annotations);
+        self.events.clone_from(&source.events);
+    }
+}
+
+/// The turn, the prisoners, the last move, the outcome and the goban instead of the whole
+/// history. `{:#?}` writes them with their labels.
+impl std::fmt::Debug for Game {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let last_move = self.moves().next_back();
+        if f.alternate() {
+            f.debug_struct("Game")
+                .field("turn", &self.turn)
+                .field("prisoners", &self.prisoners)
+                .field("last_move", &last_move)
+                .field("outcome", &self.outcome())
+                .field("goban", &self.goban)
+                .finish()
+        } else {
+            write!(
+                f,
+                "Game {:?} to play, prisoners {:?}, last move {last_move:?}, outcome {:?}\n{:?}",
+                self.turn,
+                self.prisoners,
+                self.outcome(),
+                self.goban
+            )
+        }
+    }
 }
 
 impl Deref for Game {
@@ -44,7 +136,7 @@ impl Game {
         let length = h as usize * w as usize;
         let prisoners = (0, 0);
         let handicap = 0;
-        let history = IndexSet::with_capacity_and_hasher(length, HashBuildHasher::default());
+        let history = History::with_capacity(length);
         Self {
             goban,
             turn: Color::Black,
@@ -55,6 +147,14 @@ impl Game {
             handicap,
             history,
             ko_point: None,
+            sgf_source: None,
+            scoring: None,
+            legal_cache: None,
+            clock: Clock::default(),
+            clocks: BTreeMap::new(),
+            overtime: None,
+            annotations: BTreeMap::new(),
+            events: None,
         }
     }
 }
@@ -69,6 +169,7 @@ impl Game {
     #[inline]
     pub fn set_rule(&mut self, rule: Rule) {
         self.rule = rule;
+        self.reset_legal_cache();
     }
 
     #[inline]
@@ -86,6 +187,23 @@ impl Game {
         self.rule.komi
     }
 
+    /// Number of handicap stones of black.
+    #[inline]
+    pub fn handicap(&self) -> u32 {
+        self.handicap
+    }
+
+    /// Time control and time left of the players.
+    #[inline]
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    #[inline]
+    pub fn clock_mut(&mut self) -> &mut Clock {
+        &mut self.clock
+    }
+
     #[inline]
     pub fn size(&self) -> Size {
         self.goban.size()
@@ -103,18 +221,54 @@ impl Game {
         self.turn
     }
 
+    /// The point where the ko forbids to play, None if there is no ko.
+    #[inline]
+    pub fn ko_point(&self) -> Option<Coord> {
+        self.ko_point
+    }
+
+    /// Returns the goban before each move played.
     #[cfg(feature = "history")]
-    pub fn history(&self) -> impl Iterator<Item = &Goban> {
-        self.history.iter()
+    pub fn history(&self) -> impl DoubleEndedIterator<Item = &Goban> {
+        self.history.records().iter().map(|record| &record.goban)
+    }
+
+    /// Returns the moves played since the creation of the game.
+    pub fn moves(&self) -> impl DoubleEndedIterator<Item = Move> + '_ {
+        self.history.records().iter().map(|record| record.played)
     }
 
-    /// True when the game is over (two passes, or no more legals moves, Resign)
+    /// Returns the moves after the position when the game jumped back with [`Game::jump_to`],
+    /// the next move first. Playing or undoing a move drops them.
+    pub fn moves_ahead(&self) -> impl DoubleEndedIterator<Item = Move> + '_ {
+        self.history.ahead().iter().rev().map(|record| record.played)
+    }
+
+    /// Returns the zobrist hash of the position before each move, indexed by move number.
+    /// The current position isn't in the slice, it's the hash of the goban.
+    #[inline]
+    pub fn hash_history(&self) -> &[u64] {
+        self.history.hashes()
+    }
+
+    /// Returns the move number of the first occurrence of the position, the current position
+    /// included. None if the position never occurred in this game.
+    pub fn position_seen(&self, hash: u64) -> Option<usize> {
+        self.history
+            .hashes()
+            .iter()
+            .position(|&h| h == hash)
+            .or_else(|| (self.goban.zobrist_hash() == hash).then_some(self.history.len()))
+    }
+
+    /// True when the game is over: the passes of [`Rule::passes_to_end`] in a row, or an
+    /// outcome like a resign.
     #[inline]
     pub fn is_over(&self) -> bool {
         if self.outcome.is_some() {
             true
         } else {
-            self.passes >= 2
+            self.passes >= self.rule.passes_to_end
         }
     }
 
@@ -127,19 +281,12 @@ impl Game {
         } else if self.outcome.is_some() {
             self.outcome
         } else {
-            // two passes
-            let scores = self.calculate_score();
-            if (scores.0 - scores.1).abs() < f32::EPSILON {
-                Some(Draw)
-            } else if scores.0 > scores.1 {
-                Some(WinnerByScore(Black, scores.0 - scores.1))
-            } else {
-                Some(WinnerByScore(White, scores.1 - scores.0))
-            }
+            // the passes ended the game
+            Some(EndGame::from_score_by(self.calculate_score(), self.rule.flag_score))
         }
     }
 
-    /// Generate all moves on all empty intersections. Lazy.
+    /// Generate all moves on all empty intersections in row major order. Lazy.
     #[inline]
     pub fn pseudo_legals(&self) -> impl Iterator<Item = Coord> + '_ {
         self.goban.get_empty_coords()
@@ -161,6 +308,7 @@ impl Game {
     }
 
     /// Returns a list with legals moves. from the rule specified in at the creation.
+    /// The moves are in row major order.
     #[inline]
     pub fn legals(&self) -> impl Iterator<Item = Coord> + '_ {
         self.legals_by(self.rule.flag_illegal)
@@ -176,44 +324,342 @@ impl Game {
 
     /// Method to play on the goban or pass.
     /// (0,0) is in the top left corner of the goban.
+    /// A resign ends the game, the opponent wins. Like all moves it's in the history, so
+    /// [`Game::undo`] resumes the game.
     ///
     /// # Panics
     ///
     /// If the coordinates of the move are outside the board.
+    /// In debug builds, if the move breaks an invariant of the game, like a suicide leaving a
+    /// chain without liberties when suicides are illegal.
     pub fn play(&mut self, play: Move) -> &mut Self {
+        if let Move::Pass = play {
+            assert!(
+                self.passes < self.rule.passes_to_end,
+                "This game is already paused"
+            );
+        }
+        self.history.push(Record {
+            played: play,
+            goban: self.goban.clone(),
+            passes: self.passes,
+            prisoners: self.prisoners,
+            outcome: self.outcome,
+            turn: self.turn,
+            ko_point: self.ko_point,
+            forced: false,
+        });
         match play {
             Move::Pass => {
-                assert!(self.passes < 2, "This game is already paused");
+                let old_ko_point = self.ko_point;
+                self.push_event(GameEvent::Passed(self.turn));
                 self.turn = !self.turn;
                 self.ko_point = None;
                 self.passes += 1;
+                self.update_legal_cache(None, old_ko_point);
                 self
             }
             Move::Play(x, y) => {
-                //let hash = self.goban.zobrist_hash();
-                self.history.insert(self.goban.clone());
+                let old_ko_point = self.ko_point;
+                let old_prisoners = self.prisoners;
                 let (dead_rens, added_ren) = self.goban.push_wth_feedback((x, y), self.turn);
                 self.ko_point = None;
                 self.remove_captured_stones(&dead_rens, added_ren);
                 //self.prisoners = self.remove_captured_stones();
+                let captures = self.prisoners.0 + self.prisoners.1
+                    - old_prisoners.0
+                    - old_prisoners.1;
+                self.push_event(GameEvent::MovePlayed {
+                    color: self.turn,
+                    mv: play,
+                    captures,
+                });
                 self.turn = !self.turn;
                 self.passes = 0;
+                self.update_legal_cache(None, old_ko_point);
+                debug_assert_eq!(self.check_invariants(), Ok(()));
                 self
             }
             Move::Resign(player) => {
-                self.outcome = Some(EndGame::WinnerByResign(player));
+                let outcome = EndGame::WinnerByResign(!player);
+                self.outcome = Some(outcome);
+                self.push_event(GameEvent::Resigned(player));
+                self.push_event(GameEvent::GameEnded(outcome));
                 self
             }
         }
     }
 
+    /// Puts a stone of the player to move on the point whatever the rules, to transcribe a
+    /// record with an illegal move. A stone already on the point is replaced, the captures are
+    /// made, the own chain is removed if it has no liberties, and the ko isn't kept. The move is
+    /// marked as forced in the history, see [`Game::is_forced`].
+    ///
+    /// # Panics
+    /// If the point is outside the goban.
+    pub fn force_play(&mut self, point: Coord) -> &mut Self {
+        self.history.push(Record {
+            played: point.into(),
+            goban: self.goban.clone(),
+            passes: self.passes,
+            prisoners: self.prisoners,
+            outcome: self.outcome,
+            turn: self.turn,
+            ko_point: self.ko_point,
+            forced: true,
+        });
+        self.goban.put_empty(point);
+        let old_prisoners = self.prisoners;
+        let (dead_chains, added_chain) = self.goban.push_wth_feedback(point, self.turn);
+        let ((black_prisoners, white_prisoners), _) =
+            self.goban.remove_captured_stones_aux(true, &dead_chains, added_chain);
+        self.prisoners.0 += black_prisoners;
+        self.prisoners.1 += white_prisoners;
+        self.push_event(GameEvent::MovePlayed {
+            color: self.turn,
+            mv: point.into(),
+            captures: self.prisoners.0 + self.prisoners.1 - old_prisoners.0 - old_prisoners.1,
+        });
+        self.ko_point = None;
+        self.turn = !self.turn;
+        self.passes = 0;
+        self.reset_legal_cache();
+        self
+    }
+
+    /// True if the move number, counted from 1, was put on the goban against the rules by
+    /// [`Game::force_play`].
+    pub fn is_forced(&self, move_number: usize) -> bool {
+        move_number
+            .checked_sub(1)
+            .and_then(|index| self.history.records().get(index))
+            .is_some_and(|record| record.forced)
+    }
+
+    /// Returns a copy of the game with the colors swapped: the stones, the prisoners, the player
+    /// to move, the outcome and the whole history, so the super ko history stays consistent.
+    pub fn swap_colors(&self) -> Game {
+        let mut game = self.clone();
+        game.swap_colors_mut();
+        game
+    }
+
+    /// Swaps the colors of the game in place, see [`Game::swap_colors`].
+    pub fn swap_colors_mut(&mut self) {
+        fn swap_outcome(outcome: EndGame) -> EndGame {
+            match outcome {
+                EndGame::WinnerByScore(c, score) => EndGame::WinnerByScore(!c, score),
+                EndGame::WinnerByResign(c) => EndGame::WinnerByResign(!c),
+                EndGame::WinnerByTime(c) => EndGame::WinnerByTime(!c),
+                EndGame::WinnerByForfeit(c) => EndGame::WinnerByForfeit(!c),
+                EndGame::Draw => EndGame::Draw,
+            }
+        }
+        let mut history = History::with_capacity(self.history.len());
+        for record in self.history.records() {
+            history.push(Record {
+                played: match record.played {
+                    Move::Resign(c) => Move::Resign(!c),
+                    m => m,
+                },
+                goban: record.goban.swap_colors(),
+                passes: record.passes,
+                prisoners: (record.prisoners.1, record.prisoners.0),
+                outcome: record.outcome.map(swap_outcome),
+                turn: !record.turn,
+                ko_point: record.ko_point,
+                forced: record.forced,
+            });
+        }
+        self.history = history;
+        self.goban.swap_colors_mut();
+        self.prisoners = (self.prisoners.1, self.prisoners.0);
+        self.outcome = self.outcome.map(swap_outcome);
+        self.turn = !self.turn;
+        if let Some(session) = &mut self.scoring {
+            session.swap_colors_mut();
+        }
+        self.clock.swap_colors_mut();
+        self.clocks.values_mut().for_each(Clock::swap_colors_mut);
+        // The colors of the loaded record don't match anymore.
+        self.sgf_source = None;
+        self.reset_legal_cache();
+    }
+
+    /// Returns the game moved by the symmetry: the starting position, the handicap stones and
+    /// the moves are mapped then the moves are played again, so the goban, the prisoners, the
+    /// ko and the super ko history are consistent. The passes, the resignations, the outcome and
+    /// the clock are kept, the markup of the annotations is mapped too. The moves ahead after a
+    /// [`Game::jump_to`] and the scoring phase aren't kept.
+    pub fn transform(&self, symmetry: Symmetry) -> Game {
+        let size = self.size();
+        let (start, turn, prisoners, initial_outcome) = match self.history.records().first() {
+            Some(record) => (&record.goban, record.turn, record.prisoners, record.outcome),
+            None => (&self.goban, self.turn, self.prisoners, self.outcome),
+        };
+        let (height, width) = symmetry.size(size);
+        let mut game = Game::new(
+            GobanSizes::Custom(height as usize, width as usize),
+            self.rule,
+        );
+        game.goban = start.transform(symmetry);
+        game.turn = turn;
+        game.prisoners = prisoners;
+        game.handicap = self.handicap;
+        game.outcome = initial_outcome;
+        game.clock = self.clock;
+        game.clocks = self.clocks.clone();
+        game.overtime = self.overtime.clone();
+        for (index, played) in self.moves().enumerate() {
+            match played {
+                Move::Play(x, y) if self.is_forced(index + 1) => {
+                    game.force_play(symmetry.apply(size, (x, y)));
+                }
+                Move::Play(x, y) => {
+                    let (x, y) = symmetry.apply(size, (x, y));
+                    game.replay(Move::Play(x, y))
+                        .expect("The symmetric move of a legal move is legal");
+                }
+                m => game.replay(m).expect("A pass or a resign is legal"),
+            }
+        }
+        game.outcome = self.outcome;
+        let map = |points: &BTreeSet<Coord>| -> BTreeSet<Coord> {
+            points.iter().map(|&p| symmetry.apply(size, p)).collect()
+        };
+        game.annotations = self
+            .annotations
+            .iter()
+            .map(|(&number, annotation)| {
+                let markup = &annotation.markup;
+                let markup = Markup {
+                    triangles: map(&markup.triangles),
+                    squares: map(&markup.squares),
+                    circles: map(&markup.circles),
+                    labels: markup
+                        .labels
+                        .iter()
+                        .map(|(&p, label)| (symmetry.apply(size, p), label.clone()))
+                        .collect(),
+                };
+                (number, Annotation { markup, ..annotation.clone() })
+            })
+            .collect();
+        game
+    }
+
+    /// Plays the move if it's legal, whatever the outcome of the game, which is only known at
+    /// the end. A pass after the passes ending the game means the players resumed the game.
+    pub(crate) fn replay(&mut self, played: Move) -> Result<(), PlayError> {
+        match played {
+            Move::Play(x, y) if !is_coord_valid(self.size(), (x, y)) => {
+                return Err(PlayError::OutsideBoard((x, y)));
+            }
+            Move::Play(x, y) => {
+                if let Some(error) = self.check_point((x, y)) {
+                    return Err(error);
+                }
+            }
+            Move::Pass if self.passes >= self.rule.passes_to_end => self.resume(),
+            Move::Pass | Move::Resign(_) => {}
+        }
+        self.play(played);
+        Ok(())
+    }
+
+    /// Verifies the invariants of the goban, that no chain is left without liberties and that
+    /// the super ko history has one hash by move.
+    ///
+    /// # Errors
+    /// Returns the first invariant violated, with where it happened.
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        self.goban.check_invariants()?;
+        if let Some((chain, group)) = self.goban.get_groups().find(|(_, group)| group.is_dead()) {
+            return Err(InvariantViolation::ChainWithoutLiberties {
+                coord: BoardIdx::from(group.origin).to_coord(self.goban.size()),
+                chain,
+            });
+        }
+        let moves = self.moves().count();
+        let hashes = self.history.hashes().len();
+        if moves != hashes {
+            return Err(InvariantViolation::HistoryMismatch { moves, hashes });
+        }
+        Ok(())
+    }
+
+    /// Takes back the last move, the game is restored as it was before the move.
+    /// Returns the move undone, None if no move was played.
+    pub fn undo(&mut self) -> Option<Move> {
+        let record = self.history.pop()?;
+        let old_ko_point = self.ko_point;
+        let played = record.played;
+        let before = self.restore(record);
+        self.update_legal_cache(Some(&before), old_ko_point);
+        Some(played)
+    }
+
+    /// Moves the game to the position after `move_number` moves, for a viewer going through
+    /// the game. The moves after the position are kept, see [`Game::moves_ahead`], so the game
+    /// can jump forward again until a move is played or undone.
+    /// The history keeps the state before each move, so a jump replays at most one move.
+    /// Like an undo, a jump doesn't push any event.
+    /// Returns false, and the game is unchanged, if there are fewer moves.
+    pub fn jump_to(&mut self, move_number: usize) -> bool {
+        let current = self.history.len();
+        if move_number > current + self.history.ahead().len() {
+            return false;
+        }
+        if move_number < current {
+            for _ in move_number..current {
+                self.history.rewind();
+            }
+            let record = self.history.ahead().last().expect("A move was rewound").clone();
+            self.restore(record);
+            self.reset_legal_cache();
+        } else if move_number > current {
+            for _ in current + 1..move_number {
+                self.history.advance();
+            }
+            let mut ahead = self.history.take_ahead();
+            let record = ahead.pop().expect("The move is ahead");
+            let (played, forced) = (record.played, record.forced);
+            self.restore(record);
+            self.reset_legal_cache();
+            // The jumps aren't events, the replayed move is already recorded.
+            let events = self.events.take();
+            match played {
+                Move::Play(x, y) if forced => {
+                    self.force_play((x, y));
+                }
+                _ => {
+                    self.play(played);
+                }
+            }
+            self.events = events;
+            self.history.set_ahead(ahead);
+        }
+        true
+    }
+
+    /// Puts the game in the state of the record, returns the goban replaced.
+    fn restore(&mut self, record: Record) -> Goban {
+        self.scoring = None;
+        self.passes = record.passes;
+        self.prisoners = record.prisoners;
+        self.outcome = record.outcome;
+        self.turn = record.turn;
+        self.ko_point = record.ko_point;
+        std::mem::replace(&mut self.goban, record.goban)
+    }
+
     /// Plays a move then return the simulated goban,
     /// used in legals for fast move simulation in Super Ko situations.
     pub fn play_for_verification(&self, (x, y): Coord) -> Goban {
         let mut test_goban = self.goban.clone();
         let (dead_go_strings, added_ren) = test_goban.push_wth_feedback((x, y), self.turn);
         test_goban.remove_captured_stones_aux(
-            self.rule.flag_illegal.contains(IllegalRules::SUICIDE),
+            !self.rule.flag_illegal.contains(IllegalRules::SUICIDE),
             &dead_go_strings,
             added_ren,
         );
@@ -224,29 +670,67 @@ impl Game {
     ///
     /// # Errors
     ///
-    /// If the move is a suicide Move return SuicideMove
-    /// If the move is a Ko Move returns Ko
-    /// if point is already filled then return PointNotEmpty
+    /// If the game has an outcome, after a resign for example, then return GameOver
+    /// If the game is in the scoring phase then return Scoring
     /// If the game is paused then return GamePaused
+    /// If the point is outside the goban then return OutsideBoard
+    /// Else if the move is illegal, the error of [`Game::check_point`]
     pub fn try_play(&mut self, play: Move) -> Result<&mut Self, PlayError> {
-        if self.passes == 2 {
+        self.is_legal(play)?;
+        Ok(self.play(play))
+    }
+
+    /// Returns a copy of the game with the move played, the game is left untouched. The copy
+    /// has its own goban, prisoners, ko and history, like the game after [`Game::try_play`].
+    ///
+    /// # Errors
+    /// The error [`Game::try_play`] returns for the move.
+    pub fn try_play_cloned(&self, play: Move) -> Result<Game, PlayError> {
+        self.is_legal(play)?;
+        let mut game = self.clone();
+        game.play(play);
+        Ok(game)
+    }
+
+    /// Checks the move like [`Game::try_play`] without playing it: the state of the game, the
+    /// point, the suicide with the captures, the ko and the super ko. Nothing is copied, the
+    /// super ko compares the [`Goban::hash_after`] the move to the history.
+    ///
+    /// # Errors
+    /// The error [`Game::try_play`] returns for the move.
+    pub fn is_legal(&self, play: Move) -> Result<(), PlayError> {
+        if self.outcome.is_some() {
+            Err(PlayError::GameOver)
+        } else if self.scoring.is_some() {
+            Err(PlayError::Scoring)
+        } else if self.passes >= self.rule.passes_to_end {
             Err(PlayError::GamePaused)
         } else {
             match play {
-                Move::Play(x, y) => {
-                    if self.goban.get_color((x, y)) != EMPTY {
-                        Err(PlayError::PointNotEmpty)
-                    } else if let Some(c) = self.check_point((x as Nat, y as Nat)) {
-                        Err(c)
-                    } else {
-                        Ok(self.play(play))
-                    }
+                Move::Play(x, y) if !is_coord_valid(self.size(), (x, y)) => {
+                    Err(PlayError::OutsideBoard((x, y)))
                 }
-                Move::Pass | Move::Resign(_) => Ok(self.play(play)),
+                Move::Play(x, y) => self.check_point((x, y)).map_or(Ok(()), Err),
+                Move::Pass | Move::Resign(_) => Ok(()),
             }
         }
     }
 
+    /// Like [`Game::try_play`] for the player of the color, for the protocols giving the
+    /// color with the move. A player can resign when it's not its turn.
+    ///
+    /// # Errors
+    ///
+    /// If it's not the turn of the player then return WrongTurn, else the errors of
+    /// [`Game::try_play`].
+    pub fn try_play_as(&mut self, color: Color, play: Move) -> Result<&mut Self, PlayError> {
+        match play {
+            Move::Resign(player) if player != color => Err(PlayError::WrongTurn(color)),
+            Move::Pass | Move::Play(..) if color != self.turn => Err(PlayError::WrongTurn(color)),
+            _ => self.try_play(play),
+        }
+    }
+
     /// Put the handicap stones on the goban.
     /// This put the turn for white but doesn't update the komi.
     pub fn put_handicap(&mut self, points: &[Coord]) {
@@ -255,16 +739,110 @@ impl Game {
             self.goban.push(coord, Color::Black);
         });
         self.turn = Color::White;
+        self.reset_legal_cache();
     }
 
     /// Put an aritary stone on the goban.
     pub fn put_stone(&mut self, point: Coord, c: Color) {
         self.goban.push(point, c);
+        self.reset_legal_cache();
     }
 
     /// Put an aritary empty stone on the goban.
     pub fn put_empty_stone(&mut self, point: Coord) {
         self.goban.put_empty(point);
+        self.reset_legal_cache();
+    }
+
+    /// Enables the cache of the legal moves, updated after each move and undo around the
+    /// points changed. See [`Game::legal_moves_cached`].
+    pub fn enable_legal_move_cache(&mut self) {
+        if self.legal_cache.is_none() {
+            self.legal_cache = Some(LegalMoveCache::new(self));
+        }
+    }
+
+    /// Disables the cache of the legal moves.
+    pub fn disable_legal_move_cache(&mut self) {
+        self.legal_cache = None;
+    }
+
+    /// Returns the legal moves of the color in row major order, from the cache if it's enabled.
+    /// The ko and the super ko only forbid the player to move, for the other color only the
+    /// rules local to the point are checked.
+    pub fn legal_moves_cached(&self, color: Color) -> impl Iterator<Item = Coord> + '_ {
+        let size = self.size();
+        let flags = self.rule.flag_illegal;
+        let super_ko = color == self.turn && flags.contains(IllegalRules::SUPERKO);
+        let cached = self.legal_cache.as_ref().map(|cache| {
+            cache
+                .legals(color)
+                .filter(move |&index| {
+                    !super_ko
+                        || !cache.superko_candidate(index, color)
+                        || !self.check_super_ko(Stone {
+                            coord: index.to_coord(size),
+                            color,
+                        })
+                })
+                .map(move |index| index.to_coord(size))
+        });
+        let computed = self.legal_cache.is_none().then(|| {
+            self.pseudo_legals().filter(move |&coord| {
+                let stone = Stone { coord, color };
+                self.check_stone_locally(stone, flags).is_none()
+                    && (!super_ko || !self.check_super_ko(stone))
+            })
+        });
+        cached
+            .into_iter()
+            .flatten()
+            .chain(computed.into_iter().flatten())
+    }
+
+    /// Number of legal moves of the color, like `legal_moves_cached(color).count()`. With the
+    /// cache the count is kept by the updates, only the captures and the suicides of the player
+    /// to move are checked against the super ko. Without the cache every point is checked, it's
+    /// O(N²) on a NxN goban.
+    pub fn legal_move_count(&self, color: Color) -> usize {
+        let Some(cache) = &self.legal_cache else {
+            return self.legal_moves_cached(color).count();
+        };
+        let super_ko =
+            color == self.turn && self.rule.flag_illegal.contains(IllegalRules::SUPERKO);
+        let forbidden = if super_ko {
+            cache
+                .legal_superko_candidates(color)
+                .filter(|index| {
+                    self.check_super_ko(Stone {
+                        coord: index.to_coord(self.size()),
+                        color,
+                    })
+                })
+                .count()
+        } else {
+            0
+        };
+        cache.count(color) - forbidden
+    }
+
+    /// Updates the cache after a move or an undo. Without `before` the goban before the move is
+    /// the last of the history.
+    fn update_legal_cache(&mut self, before: Option<&Goban>, old_ko_point: Option<Coord>) {
+        if let Some(mut cache) = self.legal_cache.take() {
+            let before = before.unwrap_or_else(|| {
+                &self.history.records().last().expect("A move was played").goban
+            });
+            cache.update(self, before, old_ko_point);
+            self.legal_cache = Some(cache);
+        }
+    }
+
+    /// Computes again the whole cache, after the goban or the rule was modified directly.
+    pub(crate) fn reset_legal_cache(&mut self) {
+        if self.legal_cache.is_some() {
+            self.legal_cache = Some(LegalMoveCache::new(self));
+        }
     }
 
     /// Calculates score. with prisoners and komi.
@@ -276,23 +854,22 @@ impl Game {
 
     /// Calculates the score by the rule passed in parameter.
     pub fn calculate_score_by(&self, rule: ScoreRules) -> (f32, f32) {
-        let (black_score, white_score) = self.goban.calculate_territories();
-        let mut black_score = black_score as f32;
-        let mut white_score = white_score as f32;
-        if rule.contains(ScoreRules::PRISONNERS) {
-            black_score += self.prisoners.0 as f32;
-            white_score += self.prisoners.1 as f32;
-        }
-        if rule.contains(ScoreRules::STONES) {
-            let (black_stones, white_stones) = self.goban.number_of_stones();
-            black_score += black_stones as f32;
-            white_score += white_stones as f32;
-        }
-        if rule.contains(ScoreRules::KOMI) {
-            white_score += self.komi();
-        }
+        self.calculate_score_with_dead(rule, &[])
+    }
 
-        (black_score, white_score)
+    /// Calculates the score by the rule with the chains of the goban marked dead, like the
+    /// [scoring session](crate::rules::scoring::ScoringSession). The dead stones are removed
+    /// before the territories are counted and become prisoners of the opponent, so they count
+    /// as prisoners under the territory rules and as area of the opponent under the area rules.
+    pub fn calculate_score_with_dead(&self, rule: ScoreRules, dead: &[GroupIdx]) -> (f32, f32) {
+        score_position(
+            &self.goban,
+            self.prisoners,
+            self.komi(),
+            self.handicap,
+            rule,
+            dead,
+        )
     }
 
     /// Returns true if the stone played in that point will capture another
@@ -312,20 +889,52 @@ impl Game {
 
     /// Test if a point is legal or not by the rule passed in parameter.
     pub fn check_point_by(&self, coord: Coord, illegal_rules: IllegalRules) -> Option<PlayError> {
+        self.illegal_reason_by(coord, illegal_rules)
+            .map(|reason| reason.error_at(coord))
+    }
+
+    /// Every point of the goban where the player to move can't play with the rule of the
+    /// game, with the reason, in row major order. It's the complement of [`Game::legals`].
+    pub fn forbidden_points(&self) -> impl Iterator<Item = (Coord, IllegalReason)> + '_ {
+        let (height, width) = self.size();
+        (0..height)
+            .flat_map(move |row| (0..width).map(move |column| (row, column)))
+            .filter_map(move |coord| {
+                self.illegal_reason_by(coord, self.rule.flag_illegal)
+                    .map(|reason| (coord, reason))
+            })
+    }
+
+    /// Why the point isn't legal for the player to move by the rule, None if it's legal.
+    fn illegal_reason_by(&self, coord: Coord, illegal_rules: IllegalRules) -> Option<IllegalReason> {
         let stone = Stone {
             coord,
             color: self.turn,
         };
-        if self.goban.get_color(coord).is_some() {
-            Some(PlayError::PointNotEmpty)
-        } else if illegal_rules.contains(IllegalRules::SUICIDE) && self.check_suicide(stone) {
-            Some(PlayError::Suicide)
-        } else if illegal_rules.contains(IllegalRules::KO) && self.check_ko(stone) {
-            Some(PlayError::Ko)
+        self.check_stone_locally(stone, illegal_rules).or_else(|| {
+            (illegal_rules.contains(IllegalRules::SUPERKO) && self.check_super_ko(stone))
+                .then_some(IllegalReason::Superko)
+        })
+    }
+
+    /// Test the rules which only depend on the neighborhood of the point, so every rule except
+    /// the super ko. The ko only forbids the player to move.
+    pub(crate) fn check_stone_locally(
+        &self,
+        stone: Stone,
+        illegal_rules: IllegalRules,
+    ) -> Option<IllegalReason> {
+        if self.goban.get_color(stone.coord).is_some() {
+            Some(IllegalReason::Occupied)
+        } else if self.check_forbidden_suicide(stone, illegal_rules) {
+            Some(IllegalReason::Suicide)
+        } else if illegal_rules.contains(IllegalRules::KO)
+            && stone.color == self.turn
+            && self.check_ko(stone)
+        {
+            Some(IllegalReason::Ko)
         } else if illegal_rules.contains(IllegalRules::FILLEYE) && self.check_eye(stone) {
-            Some(PlayError::FillEye)
-        } else if illegal_rules.contains(IllegalRules::SUPERKO) && self.check_super_ko(stone) {
-            Some(PlayError::Ko)
+            Some(IllegalReason::FillEye)
         } else {
             None
         }
@@ -430,20 +1039,23 @@ impl Game {
     }
 
     /// Rule of the super Ko, if any before configuration was already played then return true.
+    /// Only a move removing stones, a capture or a suicide when the rule allows it, can repeat
+    /// a position.
     pub fn check_super_ko(&self, stone: Stone) -> bool {
-        if self.history.len() <= 2 || !self.will_capture(stone.coord) {
+        if self.history.len() <= 2
+            || !(self.will_capture(stone.coord) || self.check_suicide(stone))
+        {
             false
         } else {
-            let to_check = self.play_for_verification(stone.coord);
-            let is_it_ko = self.check_ko(stone);
-            is_it_ko || self.history.contains(&to_check)
+            let hash = self.goban.hash_after(stone.coord, stone.color);
+            self.check_ko(stone) || self.position_seen(hash).is_some()
         }
     }
 
     /// Add a stone to the board and then test if the stone or stone group is dead.
     /// Returns true if the move is a suicide
     pub fn check_suicide(&self, stone: Stone) -> bool {
-        if self.goban.has_liberties(stone.coord) {
+        if self.goban.point_has_liberty(stone.coord) {
             false
         } else {
             !self
@@ -462,6 +1074,23 @@ impl Game {
         }
     }
 
+    /// True if the move is a suicide forbidden by the rules, with [`IllegalRules::SINGLE_SUICIDE`]
+    /// only the stones not connecting to a chain of their color are.
+    fn check_forbidden_suicide(&self, stone: Stone, illegal_rules: IllegalRules) -> bool {
+        if illegal_rules.contains(IllegalRules::SUICIDE) {
+            self.check_suicide(stone)
+        } else if illegal_rules.contains(IllegalRules::SINGLE_SUICIDE) {
+            self.check_suicide(stone)
+                && self
+                    .goban
+                    .get_connected_groups(stone.coord)
+                    .into_iter()
+                    .all(|chain| chain.color != stone.color)
+        } else {
+            false
+        }
+    }
+
     /// Displays the internal board.
     pub fn display_goban(&self) {
         println!("{}", self.goban)
@@ -485,3 +1114,61 @@ impl Default for Game {
         Game::new(GobanSizes::Nineteen, CHINESE)
     }
 }
+
+/// Score of the position with the prisoners and komi, by the rule passed in parameter. The
+/// dead chains are removed first, see [`remove_dead_chains`]. Every score goes through it, so
+/// the scoring phase, the outcome and GTP agree.
+pub(crate) fn score_position(
+    goban: &Goban,
+    prisoners: (u32, u32),
+    komi: f32,
+    handicap: u32,
+    rule: ScoreRules,
+    dead: &[GroupIdx],
+) -> (f32, f32) {
+    let (goban, prisoners) = if dead.is_empty() {
+        (Cow::Borrowed(goban), prisoners)
+    } else {
+        let (mut goban, mut prisoners) = (goban.clone(), prisoners);
+        remove_dead_chains(&mut goban, &mut prisoners, dead);
+        (Cow::Owned(goban), prisoners)
+    };
+    let (black_stones, white_stones) = goban.number_of_stones();
+    let (black_territory, white_territory) = goban.calculate_territories();
+    let mut black_score = black_territory as f32;
+    let mut white_score = white_territory as f32;
+    if rule.contains(ScoreRules::PRISONNERS) {
+        black_score += prisoners.0 as f32;
+        white_score += prisoners.1 as f32;
+    }
+    if rule.contains(ScoreRules::STONES) {
+        black_score += black_stones as f32;
+        white_score += white_stones as f32;
+    }
+    if rule.contains(ScoreRules::KOMI) {
+        white_score += komi;
+    }
+    white_score += rule.handicap_compensation(handicap);
+
+    (black_score, white_score)
+}
+
+/// Removes the chains of the goban, their stones are added to the prisoners of the opponent.
+pub(crate) fn remove_dead_chains(
+    goban: &mut Goban,
+    prisoners: &mut (u32, u32),
+    dead: &[GroupIdx],
+) {
+    let mut dead = dead.to_vec();
+    dead.sort_unstable();
+    dead.dedup();
+    for chain in dead {
+        for stone in goban.chain_stones(chain) {
+            match stone.color {
+                Color::Black => prisoners.1 += 1,
+                Color::White => prisoners.0 += 1,
+            }
+        }
+        goban.remove_chain(chain);
+    }
+}
@@ -18,11 +18,11 @@
 //!     .build();
 //! ```
 
-use crate::pieces::goban::Goban;
-use crate::pieces::stones::{Color, Stone};
+use crate::pieces::stones::{Color, MaybeColor, Stone, EMPTY};
 use crate::pieces::util::coord::{Coord, Size};
 use crate::rules::game::Game;
-use crate::rules::{EndGame, Move, Rule, CHINESE};
+use crate::rules::setup::SetupBuilder;
+use crate::rules::{EndGame, Move, Rule, CHINESE, HANDICAP_KOMI};
 use std::mem::take;
 
 pub struct GameBuilder {
@@ -31,12 +31,13 @@ pub struct GameBuilder {
     white_player: String,
     rule: Rule,
     komi: Option<f32>,
+    handicap_komi: f32,
     handicap: Option<u32>,
     handicap_points: Vec<Coord>,
     turn: Option<Color>,
     moves: Vec<Move>,
     outcome: Option<EndGame>,
-    setup: Vec<Stone>,
+    setup: Vec<(Coord, MaybeColor)>,
 }
 
 impl GameBuilder {
@@ -71,6 +72,12 @@ impl GameBuilder {
         self
     }
 
+    /// The komi of a game with handicap when no komi is given, [`HANDICAP_KOMI`] by default.
+    pub fn handicap_komi(&mut self, komi: f32) -> &mut Self {
+        self.handicap_komi = komi;
+        self
+    }
+
     pub fn turn(&mut self, turn: Color) -> &mut Self {
         self.turn = Some(turn);
         self
@@ -92,26 +99,40 @@ impl GameBuilder {
     }
 
     pub fn add(&mut self, stone: Stone) -> &mut Self {
-        self.setup.push(stone);
+        self.setup.push((stone.coord, Some(stone.color)));
+        self
+    }
+
+    /// Removes a stone added before, like the handicap or setup stones.
+    pub fn remove(&mut self, point: Coord) -> &mut Self {
+        self.setup.push((point, EMPTY));
         self
     }
 
     fn build_inner(mut self) -> Result<Game, String> {
-        let mut goban: Goban = Goban::new(self.size);
+        if self.rule.passes_to_end == 0 {
+            return Err("the game must end after at least one pass".to_string());
+        }
+        let mut setup = SetupBuilder::new(self.size);
 
         let handicap = self.handicap.unwrap_or(self.handicap_points.len() as u32);
 
         for point in self.handicap_points {
-            goban.push(point, Color::Black);
+            setup.put(point, Color::Black);
         }
 
         // Setup
-        for s in self.setup {
-            goban.push_stone(s);
+        for (point, color) in self.setup {
+            match color {
+                Some(color) => setup.put(point, color),
+                EMPTY => setup.remove(point),
+            };
         }
 
         if let Some(komi) = self.komi {
             self.rule.komi = komi;
+        } else if handicap != 0 {
+            self.rule.komi = self.handicap_komi;
         }
 
         let turn = {
@@ -122,20 +143,19 @@ impl GameBuilder {
             })
         };
 
-        let mut g = Game {
-            goban: goban.clone(),
-            passes: 0,
-            prisoners: (0, 0),
-            outcome: self.outcome,
-            turn,
-            rule: self.rule,
-            handicap,
-            history: Default::default(),
-            ko_point: None,
-        };
+        let mut g = setup
+            .rule(self.rule)
+            .set_to_move(turn)
+            .finish()
+            .map_err(|e| e.to_string())?;
+        g.outcome = self.outcome;
+        g.handicap = handicap;
 
-        // Moves to play
+        // Moves to play, a pass after the passes ending the game resumes it
         for &m in &self.moves {
+            if m == Move::Pass && g.passes >= g.rule.passes_to_end {
+                g.resume();
+            }
             g.play(m);
         }
 
@@ -158,6 +178,7 @@ impl Default for GameBuilder {
             handicap_points: vec![],
             rule: CHINESE,
             komi: None,
+            handicap_komi: HANDICAP_KOMI,
             turn: None,
             moves: vec![],
             outcome: None,
@@ -0,0 +1,145 @@
+//! Serde support of [`Game`], with the `serde` feature.
+//!
+//! A game is stored as its configuration, its starting position and the moves played. The
+//! goban, the prisoners, the ko and the hashes of the super ko are computed again by replaying
+//! the moves, so a stored game can't contradict itself. The annotations, the clocks recorded at
+//! the moves, the SGF source and the scoring phase aren't stored, neither are the moves ahead
+//! after a [`Game::jump_to`].
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::pieces::stones::Color;
+use crate::pieces::util::coord::{is_coord_valid, Coord, Size};
+use crate::rules::clock::Clock;
+use crate::rules::game::Game;
+use crate::rules::setup::SetupBuilder;
+use crate::rules::{EndGame, IllegalRules, Move, Rule, ScoreRules};
+
+#[derive(Serialize, Deserialize)]
+struct GameRepr {
+    size: Size,
+    komi: f32,
+    illegal_rules: u32,
+    score_rules: u32,
+    /// Missing in the games stored before it was configurable.
+    #[serde(default = "default_passes_to_end")]
+    passes_to_end: u32,
+    handicap: u32,
+    /// Stones of the goban before the first move.
+    black: Vec<Coord>,
+    white: Vec<Coord>,
+    /// Player to move before the first move.
+    turn: Color,
+    /// Outcome before the first move, like the result of a loaded record.
+    initial_outcome: Option<EndGame>,
+    moves: Vec<Move>,
+    /// Numbers of the moves put against the rules, counted from 1.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    forced: Vec<usize>,
+    outcome: Option<EndGame>,
+    clock: Clock,
+}
+
+fn default_passes_to_end() -> u32 {
+    2
+}
+
+impl From<&Game> for GameRepr {
+    fn from(game: &Game) -> Self {
+        let (start, turn, initial_outcome) = match game.history.records().first() {
+            Some(record) => (&record.goban, record.turn, record.outcome),
+            None => (&game.goban, game.turn, game.outcome),
+        };
+        let stones_of = |color: Color| {
+            start
+                .get_stones()
+                .filter(|stone| stone.color == color)
+                .map(|stone| stone.coord)
+                .collect()
+        };
+        GameRepr {
+            size: game.size(),
+            komi: game.rule.komi,
+            illegal_rules: game.rule.flag_illegal.bits(),
+            score_rules: game.rule.flag_score.bits(),
+            passes_to_end: game.rule.passes_to_end,
+            handicap: game.handicap,
+            black: stones_of(Color::Black),
+            white: stones_of(Color::White),
+            turn,
+            initial_outcome,
+            moves: game.moves().collect(),
+            forced: (1..=game.moves().count())
+                .filter(|&number| game.is_forced(number))
+                .collect(),
+            outcome: game.outcome,
+            clock: game.clock,
+        }
+    }
+}
+
+impl TryFrom<GameRepr> for Game {
+    type Error = String;
+
+    fn try_from(repr: GameRepr) -> Result<Self, Self::Error> {
+        let rule = Rule {
+            komi: repr.komi,
+            flag_illegal: IllegalRules::from_bits(repr.illegal_rules)
+                .ok_or_else(|| format!("invalid illegal rules {}", repr.illegal_rules))?,
+            flag_score: ScoreRules::from_bits(repr.score_rules)
+                .ok_or_else(|| format!("invalid score rules {}", repr.score_rules))?,
+            passes_to_end: repr.passes_to_end,
+        };
+        if rule.passes_to_end == 0 {
+            return Err("the game must end after at least one pass".to_string());
+        }
+        let (height, width) = repr.size;
+        if !(1..=19).contains(&height) || !(1..=19).contains(&width) {
+            return Err(format!("invalid goban size {height}x{width}"));
+        }
+        let mut setup = SetupBuilder::new(repr.size);
+        for coord in repr.black {
+            setup.put(coord, Color::Black);
+        }
+        for coord in repr.white {
+            setup.put(coord, Color::White);
+        }
+        let mut game = setup
+            .rule(rule)
+            .set_to_move(repr.turn)
+            .finish()
+            .map_err(|e| e.to_string())?;
+        game.handicap = repr.handicap;
+        game.outcome = repr.initial_outcome;
+        game.clock = repr.clock;
+        for (index, &played) in repr.moves.iter().enumerate() {
+            match played {
+                Move::Play(x, y) if repr.forced.contains(&(index + 1)) => {
+                    if !is_coord_valid(repr.size, (x, y)) {
+                        return Err(format!("move {index} {played:?}: outside the goban"));
+                    }
+                    game.force_play((x, y));
+                }
+                _ => game
+                    .replay(played)
+                    .map_err(|e| format!("move {index} {played:?}: {e}"))?,
+            }
+        }
+        game.outcome = repr.outcome;
+        Ok(game)
+    }
+}
+
+impl Serialize for Game {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GameRepr::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = GameRepr::deserialize(deserializer)?;
+        Game::try_from(repr).map_err(D::Error::custom)
+    }
+}
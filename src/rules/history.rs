@@ -0,0 +1,151 @@
+//! Module with the history of a game, used for super ko detection and undo.
+
+use crate::pieces::goban::Goban;
+use crate::pieces::stones::Color;
+use crate::pieces::util::coord::Coord;
+use crate::rules::{EndGame, Move};
+
+/// State of the game just before a move was played.
+#[derive(Debug)]
+pub(crate) struct Record {
+    pub(crate) played: Move,
+    pub(crate) goban: Goban,
+    pub(crate) passes: u32,
+    pub(crate) prisoners: (u32, u32),
+    pub(crate) outcome: Option<EndGame>,
+    pub(crate) turn: Color,
+    pub(crate) ko_point: Option<Coord>,
+    /// The move was put on the goban against the rules, see [`Game::force_play`].
+    ///
+    /// [`Game::force_play`]: crate::rules::game::Game::force_play
+    pub(crate) forced: bool,
+}
+
+impl Clone for Record {
+    fn clone(&self) -> Self {
+        Record {
+            played: self.played,
+            goban: self.goban.clone(),
+            passes: self.passes,
+            prisoners: self.prisoners,
+            outcome: self.outcome,
+            turn: self.turn,
+            ko_point: self.ko_point,
+            forced: self.forced,
+        }
+    }
+
+    /// Reuses the buffers of the goban.
+    fn clone_from(&mut self, source: &Self) {
+        self.played = source.played;
+        self.goban.clone_from(&source.goban);
+        self.passes = source.passes;
+        self.prisoners = source.prisoners;
+        self.outcome = source.outcome;
+        self.turn = source.turn;
+        self.ko_point = source.ko_point;
+        self.forced = source.forced;
+    }
+}
+
+/// History of the game, one record for each move played.
+/// The hashes are only pushed and popped with their record, so they can't drift from the moves.
+#[derive(Debug, Default)]
+pub(crate) struct History {
+    hashes: Vec<u64>,
+    records: Vec<Record>,
+    /// Records of the moves after the position when the game jumped back, the next move last.
+    ahead: Vec<Record>,
+}
+
+impl Clone for History {
+    fn clone(&self) -> Self {
+        History {
+            hashes: self.hashes.clone(),
+            records: self.records.clone(),
+            ahead: self.ahead.clone(),
+        }
+    }
+
+    /// Reuses the buffers of the records, see [`Record::clone_from`].
+    fn clone_from(&mut self, source: &Self) {
+        self.hashes.clone_from(&source.hashes);
+        self.records.clone_from(&source.records);
+        self.ahead.clone_from(&source.ahead);
+    }
+}
+
+impl History {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        History {
+            hashes: Vec::with_capacity(capacity),
+            records: Vec::with_capacity(capacity),
+            ahead: Vec::new(),
+        }
+    }
+
+    /// Pushes the record of a new move, the moves ahead are dropped.
+    #[inline]
+    pub(crate) fn push(&mut self, record: Record) {
+        self.ahead.clear();
+        self.hashes.push(record.goban.zobrist_hash());
+        self.records.push(record);
+    }
+
+    /// Pops the record of the last move, the moves ahead are dropped.
+    #[inline]
+    pub(crate) fn pop(&mut self) -> Option<Record> {
+        self.ahead.clear();
+        self.hashes.pop();
+        self.records.pop()
+    }
+
+    /// Moves the record of the last move to the moves ahead.
+    pub(crate) fn rewind(&mut self) {
+        if let Some(record) = self.records.pop() {
+            self.hashes.pop();
+            self.ahead.push(record);
+        }
+    }
+
+    /// Moves the record of the next move back to the moves played.
+    pub(crate) fn advance(&mut self) {
+        if let Some(record) = self.ahead.pop() {
+            self.hashes.push(record.goban.zobrist_hash());
+            self.records.push(record);
+        }
+    }
+
+    /// Records of the moves ahead, the next move last.
+    #[inline]
+    pub(crate) fn ahead(&self) -> &[Record] {
+        &self.ahead
+    }
+
+    #[inline]
+    pub(crate) fn take_ahead(&mut self) -> Vec<Record> {
+        std::mem::take(&mut self.ahead)
+    }
+
+    #[inline]
+    pub(crate) fn set_ahead(&mut self, ahead: Vec<Record>) {
+        self.ahead = ahead;
+    }
+
+    /// Number of moves in the history.
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Hash of the position before each move.
+    #[inline]
+    pub(crate) fn hashes(&self) -> &[u64] {
+        &self.hashes
+    }
+
+    #[inline]
+    pub(crate) fn records(&self) -> &[Record] {
+        &self.records
+    }
+}
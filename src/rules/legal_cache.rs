@@ -0,0 +1,150 @@
+//! Cache of the legal moves of each color, updated around the points changed by each move.
+
+use crate::pieces::goban::{BoardIdx, Goban};
+use crate::pieces::group::{get, iter_ones, merge, set, Liberties, EMPTY_LIBERTIES};
+use crate::pieces::stones::{Color, Stone};
+use crate::pieces::util::coord::{is_coord_valid, valid_coords, Coord};
+use crate::rules::IllegalRules;
+use crate::rules::game::Game;
+
+/// Bitsets of the points legal for each color, without the super ko which depends on the
+/// whole history. The points where a move captures or is a suicide are kept too, it's only
+/// there that the super ko can forbid a move.
+#[derive(Clone, Debug)]
+pub(crate) struct LegalMoveCache {
+    legal: [Liberties; 2],
+    superko_candidates: [Liberties; 2],
+    /// Number of points set in `legal`.
+    counts: [usize; 2],
+}
+
+impl LegalMoveCache {
+    /// Computes the legality of every point.
+    pub(crate) fn new(game: &Game) -> Self {
+        let mut cache = LegalMoveCache {
+            legal: [EMPTY_LIBERTIES; 2],
+            superko_candidates: [EMPTY_LIBERTIES; 2],
+            counts: [0; 2],
+        };
+        for index in BoardIdx::all(game.size()) {
+            cache.evaluate(game, index);
+        }
+        cache
+    }
+
+    /// Board indexes of the legal points of the color in increasing order.
+    #[inline]
+    pub(crate) fn legals(&self, color: Color) -> impl Iterator<Item = BoardIdx> + '_ {
+        iter_ones(&self.legal[slot(color)])
+    }
+
+    /// Number of legal points of the color.
+    #[inline]
+    pub(crate) fn count(&self, color: Color) -> usize {
+        self.counts[slot(color)]
+    }
+
+    /// Board indexes of the legal points of the color where it captures or commits suicide.
+    pub(crate) fn legal_superko_candidates(
+        &self,
+        color: Color,
+    ) -> impl Iterator<Item = BoardIdx> + '_ {
+        iter_ones(&self.superko_candidates[slot(color)])
+            .filter(move |&index| get(index, &self.legal[slot(color)]))
+    }
+
+    /// True if a stone of the color captures or commits suicide on the point.
+    #[inline]
+    pub(crate) fn superko_candidate(&self, index: BoardIdx, color: Color) -> bool {
+        get(index, &self.superko_candidates[slot(color)])
+    }
+
+    /// Re-evaluates the points whose legality can change when the goban goes from `before` to
+    /// the goban of the game: the changed points and their neighbors, or the points at distance
+    /// 2 when filling eyes is illegal, the liberties of the chains touching them because their
+    /// atari status, so the captures and the suicides, can change, and the old and new ko points.
+    pub(crate) fn update(&mut self, game: &Game, before: &Goban, old_ko_point: Option<Coord>) {
+        let goban = game.goban();
+        let size = goban.size();
+        let fill_eye = game.rule().flag_illegal.contains(IllegalRules::FILLEYE);
+        let mut affected = EMPTY_LIBERTIES;
+        let mark = |coord: Coord, affected: &mut Liberties| {
+            if is_coord_valid(size, coord) {
+                set::<true>(BoardIdx::from_coord(size, coord), affected);
+            }
+        };
+        for index in BoardIdx::all(size) {
+            if before.get_color(index) == goban.get_color(index) {
+                continue;
+            }
+            let coord = index.to_coord(size);
+            mark(coord, &mut affected);
+            if fill_eye {
+                // The eye detection looks at the corners of the corners.
+                let (row, col) = coord;
+                for d_row in 0..5 {
+                    for d_col in 0..5 {
+                        let point = ((row + d_row).wrapping_sub(2), (col + d_col).wrapping_sub(2));
+                        mark(point, &mut affected);
+                    }
+                }
+            } else {
+                for point in valid_coords(coord, size) {
+                    mark(point, &mut affected);
+                }
+            }
+            let chains = goban.get_connected_groups_idx(index);
+            for chain in chains.into_iter().chain(goban.chain_idx(index)) {
+                merge(&mut affected, &goban.chain_liberties(chain));
+            }
+        }
+        for coord in old_ko_point.into_iter().chain(game.ko_point()) {
+            mark(coord, &mut affected);
+        }
+        for index in BoardIdx::all(size) {
+            if get(index, &affected) {
+                self.evaluate(game, index);
+            }
+        }
+    }
+
+    fn evaluate(&mut self, game: &Game, index: BoardIdx) {
+        let goban = game.goban();
+        let coord = index.to_coord(goban.size());
+        let flags = game.rule().flag_illegal;
+        for color in [Color::Black, Color::White] {
+            let stone = Stone { coord, color };
+            let legal = game.check_stone_locally(stone, flags).is_none();
+            let bits = &mut self.legal[slot(color)];
+            if legal != get(index, bits) {
+                if legal {
+                    set::<true>(index, bits);
+                    self.counts[slot(color)] += 1;
+                } else {
+                    set::<false>(index, bits);
+                    self.counts[slot(color)] -= 1;
+                }
+            }
+            let candidate = goban.get_color(index).is_none()
+                && (goban
+                    .get_connected_groups(index)
+                    .iter()
+                    .any(|group| group.color != color && group.is_atari())
+                    || game.check_suicide(stone));
+            if candidate {
+                set::<true>(index, &mut self.superko_candidates[slot(color)]);
+            } else {
+                set::<false>(index, &mut self.superko_candidates[slot(color)]);
+            }
+        }
+    }
+}
+
+/// Index of the bitsets of the color.
+#[inline]
+fn slot(color: Color) -> usize {
+    match color {
+        Color::Black => 0,
+        Color::White => 1,
+    }
+}
@@ -1,16 +1,30 @@
 //! Module for ruling in the game of go.
 
+use std::fmt::{self, Display, Formatter};
 use std::str::FromStr;
 
 use crate::pieces::stones::Color;
 use crate::pieces::util::coord::{Coord, Size};
 use crate::pieces::Nat;
 
+pub mod annotation;
+pub mod clock;
 #[cfg(feature = "deadstones")]
 mod dead_stones;
+mod encoding;
+pub mod events;
 pub mod game;
+#[cfg(feature = "serde")]
+mod game_serde;
 pub mod game_builder;
+mod history;
+mod legal_cache;
+mod move_list;
+pub mod psgo;
+pub mod scoring;
+pub mod setup;
 mod sgf_bridge;
+pub mod snapshot;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum GobanSizes {
@@ -26,7 +40,10 @@ impl From<GobanSizes> for Size {
             GobanSizes::Nine => (9, 9),
             GobanSizes::Thirteen => (13, 13),
             GobanSizes::Nineteen => (19, 19),
-            GobanSizes::Custom(height, width) => (height as u8, width as u8),
+            GobanSizes::Custom(height, width) => (
+                Nat::try_from(height).expect("the height of the goban doesn't fit in a u8"),
+                Nat::try_from(width).expect("the width of the goban doesn't fit in a u8"),
+            ),
         }
     }
 }
@@ -42,10 +59,41 @@ impl From<usize> for GobanSizes {
     }
 }
 
+/// Standard placement of fixed handicap stones, from the Go Text Protocol. The stones are
+/// on the 4th line from 13x13, on the 3rd line below. None if the goban is smaller than 7x7,
+/// not square, or the number of stones is outside 2 to 9, 4 for even sizes and 7x7.
+pub fn fixed_handicap_points((height, width): Size, stones: usize) -> Option<Vec<Coord>> {
+    if height != width || height < 7 {
+        return None;
+    }
+    let max = if height % 2 == 0 || height == 7 { 4 } else { 9 };
+    if !(2..=max).contains(&stones) {
+        return None;
+    }
+    let edge = if height >= 13 { 3 } else { 2 };
+    let (low, middle, high) = (edge, height / 2, height - 1 - edge);
+    // Lower left and upper right corners first.
+    let mut points = vec![(high, low), (low, high), (low, low), (high, high)];
+    points.truncate(stones);
+    if stones >= 6 {
+        points.extend([(middle, low), (middle, high)]);
+    }
+    if stones >= 8 {
+        points.extend([(high, middle), (low, middle)]);
+    }
+    if stones % 2 == 1 && stones >= 5 {
+        points.push((middle, middle));
+    }
+    Some(points)
+}
+
 /// Enum for playing in the Goban.
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// Moves are ordered Pass, then Resign, then plays in row major order.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Move {
     Pass,
+    /// The player of this color resigns, the opponent wins.
     Resign(Color),
     Play(Nat, Nat),
 }
@@ -57,6 +105,7 @@ impl From<Coord> for Move {
 }
 
 #[derive(Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EndGame {
     WinnerByScore(Color, f32),
     WinnerByResign(Color),
@@ -66,6 +115,28 @@ pub enum EndGame {
 }
 
 impl EndGame {
+    /// Outcome from the score of black and white.
+    pub fn from_score((black, white): (f32, f32)) -> Self {
+        if (black - white).abs() < f32::EPSILON {
+            EndGame::Draw
+        } else if black > white {
+            EndGame::WinnerByScore(Color::Black, black - white)
+        } else {
+            EndGame::WinnerByScore(Color::White, white - black)
+        }
+    }
+
+    /// Outcome from the score of black and white by the scoring rules, a tie is won by black
+    /// with [`ScoreRules::BLACK_WINS_TIES`].
+    pub fn from_score_by((black, white): (f32, f32), flag_score: ScoreRules) -> Self {
+        match EndGame::from_score((black, white)) {
+            EndGame::Draw if flag_score.contains(ScoreRules::BLACK_WINS_TIES) => {
+                EndGame::WinnerByScore(Color::Black, 0.)
+            }
+            outcome => outcome,
+        }
+    }
+
     /// Return the winner of the game, if none the game is draw.
     #[inline]
     pub const fn get_winner(self) -> Option<Color> {
@@ -79,15 +150,149 @@ impl EndGame {
     }
 }
 
+/// Why a move can't be played, the illegal moves carry their point.
 #[derive(Clone, Eq, PartialEq, Debug, Copy)]
 pub enum PlayError {
-    Ko,
-    Suicide,
+    OutsideBoard(Coord),
+    /// There is already a stone on the point.
+    Occupied(Coord),
+    Suicide(Coord),
+    /// The move retakes a ko right away.
+    Ko(Coord),
+    /// The move repeats an older position.
+    Superko(Coord),
+    /// The move fills an eye, only with [`IllegalRules::FILLEYE`].
+    FillEye(Coord),
+    /// It's not the turn of the player of this color.
+    WrongTurn(Color),
     GamePaused,
+    /// The game has an outcome, by resign for example.
+    GameOver,
+    /// The game is in the scoring phase, it must be resumed before playing.
+    Scoring,
+}
+
+impl Display for PlayError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PlayError::OutsideBoard(coord) => write!(f, "the point {coord:?} is outside the goban"),
+            PlayError::Occupied(coord) => write!(f, "the point {coord:?} is occupied"),
+            PlayError::Suicide(coord) => write!(f, "playing at {coord:?} is a suicide"),
+            PlayError::Ko(coord) => write!(f, "playing at {coord:?} retakes the ko"),
+            PlayError::Superko(coord) => write!(f, "playing at {coord:?} repeats a position"),
+            PlayError::FillEye(coord) => write!(f, "playing at {coord:?} fills an eye"),
+            PlayError::WrongTurn(color) => write!(f, "it's not the turn of {color:?}"),
+            PlayError::GamePaused => write!(f, "the game is paused"),
+            PlayError::GameOver => write!(f, "the game is over"),
+            PlayError::Scoring => write!(f, "the game is in the scoring phase"),
+        }
+    }
+}
+
+impl std::error::Error for PlayError {}
+
+/// Why a point isn't a legal move for a player, see [`game::Game::forbidden_points`].
+#[derive(Clone, Eq, PartialEq, Debug, Copy, Hash)]
+pub enum IllegalReason {
+    /// There is already a stone on the point.
+    Occupied,
+    Suicide,
+    /// The move retakes a ko right away.
+    Ko,
+    /// The move repeats an older position.
+    Superko,
+    /// The move fills an eye, only with [`IllegalRules::FILLEYE`].
     FillEye,
-    PointNotEmpty,
 }
 
+impl IllegalReason {
+    /// The error of a move on the point for this reason.
+    pub fn error_at(self, coord: Coord) -> PlayError {
+        match self {
+            IllegalReason::Occupied => PlayError::Occupied(coord),
+            IllegalReason::Suicide => PlayError::Suicide(coord),
+            IllegalReason::Ko => PlayError::Ko(coord),
+            IllegalReason::Superko => PlayError::Superko(coord),
+            IllegalReason::FillEye => PlayError::FillEye(coord),
+        }
+    }
+}
+
+/// Errors when using the scoring phase in the wrong state of the game.
+#[derive(Clone, Eq, PartialEq, Debug, Copy)]
+pub enum ScoringError {
+    /// The scoring phase starts only after the passes ending the game.
+    NotPaused,
+    /// The game is already in the scoring phase.
+    AlreadyScoring,
+    /// The game isn't in the scoring phase.
+    NotScoring,
+    /// The game already has an outcome.
+    GameOver,
+}
+
+/// Errors when a position set up with [`setup::SetupBuilder`] can't start a game.
+#[derive(Clone, Eq, PartialEq, Debug, Copy)]
+pub enum SetupError {
+    /// A stone was put or removed out of the goban.
+    OutOfBounds(Coord),
+    /// The chain with this stone has no liberties.
+    NoLiberties(Coord),
+}
+
+impl Display for SetupError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupError::OutOfBounds(coord) => write!(f, "the point {coord:?} is out of the goban"),
+            SetupError::NoLiberties(coord) => {
+                write!(f, "the chain at {coord:?} has no liberties")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SetupError {}
+
+/// Errors when reading a move list with [`Game::from_move_list`](game::Game::from_move_list).
+#[derive(Clone, Eq, PartialEq, Debug, Copy)]
+pub enum MoveListError {
+    /// The line isn't a color and a vertex, with an optional move number.
+    Syntax,
+    /// The move of the line can't be played.
+    IllegalMove(PlayError),
+}
+
+impl Display for MoveListError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveListError::Syntax => write!(f, "expected a color and a vertex like `B D4`"),
+            MoveListError::IllegalMove(e) => write!(f, "illegal move: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for MoveListError {}
+
+/// Errors when decoding moves encoded with [`Move::encode`].
+#[derive(Clone, Eq, PartialEq, Debug, Copy)]
+pub enum EncodingError {
+    /// The value is neither a point of the goban nor a reserved value.
+    InvalidValue(u16),
+    /// The move decoded can't be played.
+    IllegalMove(PlayError),
+}
+
+impl Display for EncodingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodingError::InvalidValue(value) => write!(f, "{value} doesn't encode a move"),
+            EncodingError::IllegalMove(e) => write!(f, "illegal move: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
 type FlagUInt = u32;
 bitflags! {
     /// Behaviours not permitted, if the flag is up then the move is not legal.
@@ -100,6 +305,8 @@ bitflags! {
         const SUICIDE = 1 << 2;
         /// Rule that filters eyes from the legals
         const FILLEYE = 1 << 3;
+        /// Rule that filters the suicide of a lone stone, the suicide of a chain stays legal.
+        const SINGLE_SUICIDE = 1 << 4;
     }
 }
 bitflags! {
@@ -111,14 +318,40 @@ bitflags! {
         const KOMI = 1 << 1;
         /// The prisoners need to be added to the score.
         const PRISONNERS = 1 << 2;
+        /// Black wins when the scores are equal.
+        const BLACK_WINS_TIES = 1 << 3;
+        /// White receives a point for each handicap stone.
+        const HANDICAP = 1 << 4;
+        /// White receives a point for each handicap stone but the first.
+        const HANDICAP_LESS_ONE = 1 << 5;
+    }
+}
+
+impl ScoreRules {
+    /// The points white receives for the handicap stones of black.
+    pub const fn handicap_compensation(self, handicap: u32) -> f32 {
+        if self.contains(ScoreRules::HANDICAP) {
+            handicap as f32
+        } else if self.contains(ScoreRules::HANDICAP_LESS_ONE) {
+            handicap.saturating_sub(1) as f32
+        } else {
+            0.
+        }
     }
 }
 
+/// The komi of a game with handicap when it isn't given, see
+/// [`GameBuilder::handicap_komi`](game_builder::GameBuilder::handicap_komi).
+pub const HANDICAP_KOMI: f32 = 0.5;
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Rule {
     pub komi: f32,
     pub flag_illegal: IllegalRules,
     pub flag_score: ScoreRules,
+    /// Number of consecutive passes ending the game, 2 in most rules, 3 leaves a pass for the
+    /// cleanup of the dead stones. It can't be 0, a game must end after at least one pass.
+    pub passes_to_end: u32,
 }
 
 pub static JAPANESE: Rule = Rule {
@@ -129,6 +362,7 @@ pub static JAPANESE: Rule = Rule {
     flag_score: ScoreRules::from_bits_truncate(
         ScoreRules::KOMI.bits() | ScoreRules::PRISONNERS.bits(),
     ),
+    passes_to_end: 2,
 };
 
 pub static CHINESE: Rule = Rule {
@@ -136,7 +370,38 @@ pub static CHINESE: Rule = Rule {
     flag_illegal: IllegalRules::from_bits_truncate(
         IllegalRules::KO.bits() | IllegalRules::SUPERKO.bits() | IllegalRules::SUICIDE.bits(),
     ),
-    flag_score: ScoreRules::from_bits_truncate(ScoreRules::KOMI.bits() | ScoreRules::STONES.bits()),
+    flag_score: ScoreRules::from_bits_truncate(
+        ScoreRules::KOMI.bits() | ScoreRules::STONES.bits() | ScoreRules::HANDICAP.bits(),
+    ),
+    passes_to_end: 2,
+};
+
+/// The AGA rules, scored by area. White receives a point for each handicap stone but the
+/// first, which makes the area score equal to the territory score with the prisoners.
+pub static AGA: Rule = Rule {
+    komi: 7.5,
+    flag_illegal: IllegalRules::from_bits_truncate(
+        IllegalRules::KO.bits() | IllegalRules::SUPERKO.bits() | IllegalRules::SUICIDE.bits(),
+    ),
+    flag_score: ScoreRules::from_bits_truncate(
+        ScoreRules::KOMI.bits() | ScoreRules::STONES.bits() | ScoreRules::HANDICAP_LESS_ONE.bits(),
+    ),
+    passes_to_end: 2,
+};
+
+/// The Ing rules. The fill-in counting gives the same result as the area scoring with a komi
+/// of 8, and black wins the ties as in the SST rules. The suicide of a chain is legal, not the
+/// one of a lone stone. The positional super ko approximates the Ing ko rules, which resolve
+/// the repetitions by the kind of the ko.
+pub static ING: Rule = Rule {
+    komi: 8.,
+    flag_illegal: IllegalRules::from_bits_truncate(
+        IllegalRules::KO.bits() | IllegalRules::SUPERKO.bits() | IllegalRules::SINGLE_SUICIDE.bits(),
+    ),
+    flag_score: ScoreRules::from_bits_truncate(
+        ScoreRules::KOMI.bits() | ScoreRules::STONES.bits() | ScoreRules::BLACK_WINS_TIES.bits(),
+    ),
+    passes_to_end: 2,
 };
 
 impl FromStr for Rule {
@@ -146,6 +411,8 @@ impl FromStr for Rule {
         match s {
             "JAP" => Ok(JAPANESE),
             "CHI" => Ok(CHINESE),
+            "ING" => Ok(ING),
+            "AGA" => Ok(AGA),
             _ => Err(format!("The rule {s} is not implemented yet.")),
         }
     }
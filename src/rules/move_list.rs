@@ -0,0 +1,77 @@
+//! Plain text list of the moves, one move by line like `B D4`, `W Q16` or `B pass`, with the
+//! vertices of GTP. A line can start with its move number and `#` starts a comment.
+//! # Example
+//! ```
+//! use goban::rules::game::Game;
+//! use goban::rules::{GobanSizes, MoveListError, CHINESE};
+//!
+//! let text = "# Opening\n1 B D4\n2 W Q16\n3. B pass\n";
+//! let game = Game::from_move_list(GobanSizes::Nineteen, CHINESE, text).unwrap();
+//! assert_eq!(game.to_move_list(), "B D4\nW Q16\nB pass\n");
+//!
+//! let error = Game::from_move_list(GobanSizes::Nineteen, CHINESE, "B D4\nW D4\n").unwrap_err();
+//! assert!(matches!(error, (2, MoveListError::IllegalMove(_))));
+//! ```
+
+use crate::gtp::{move_to_vertex, parse_color, parse_vertex};
+use crate::pieces::stones::Color;
+use crate::rules::game::Game;
+use crate::rules::{GobanSizes, Move, MoveListError, Rule};
+
+impl Game {
+    /// Writes the moves played as a move list, without move numbers.
+    pub fn to_move_list(&self) -> String {
+        let size = self.size();
+        self.history
+            .records()
+            .iter()
+            .map(|record| {
+                let color = match record.played {
+                    Move::Resign(color) => color,
+                    _ => record.turn,
+                };
+                let letter = match color {
+                    Color::Black => 'B',
+                    Color::White => 'W',
+                };
+                format!("{letter} {}\n", move_to_vertex(record.played, size))
+            })
+            .collect()
+    }
+
+    /// Creates a game by playing the moves of the list from an empty goban, each move is
+    /// checked, the colors must alternate.
+    ///
+    /// # Errors
+    /// Returns the number of the first line, from 1, which can't be read or played, with the
+    /// reason.
+    pub fn from_move_list(
+        size: GobanSizes,
+        rule: Rule,
+        text: &str,
+    ) -> Result<Game, (usize, MoveListError)> {
+        let mut game = Game::new(size, rule);
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace().peekable();
+            // The move number is optional.
+            words.next_if(|word| word.trim_end_matches('.').parse::<usize>().is_ok());
+            let (color, vertex) = match (words.next(), words.next(), words.next()) {
+                (None, _, _) => continue,
+                (Some(color), Some(vertex), None) => (color, vertex),
+                _ => return Err((line_number, MoveListError::Syntax)),
+            };
+            let color = parse_color(color).ok_or((line_number, MoveListError::Syntax))?;
+            let play = if vertex.eq_ignore_ascii_case("resign") {
+                Some(Move::Resign(color))
+            } else {
+                parse_vertex(vertex, game.size())
+            };
+            let play = play.ok_or((line_number, MoveListError::Syntax))?;
+            game.try_play_as(color, play)
+                .map_err(|e| (line_number, MoveListError::IllegalMove(e)))?;
+        }
+        Ok(game)
+    }
+}
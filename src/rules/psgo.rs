@@ -0,0 +1,236 @@
+//! # LaTeX diagrams
+//! Export of a goban as a fragment for the `psgo` LaTeX package, with the stones, the markup
+//! and the numbers of a range of moves. The columns are the letters of psgo, without `i`,
+//! and the rows are numbered from the bottom.
+//! # Example
+//! ```
+//! use goban::rules::game::Game;
+//! use goban::rules::psgo::PsgoOptions;
+//! use goban::rules::{GobanSizes, Move, CHINESE};
+//!
+//! let mut game = Game::new(GobanSizes::Nine, CHINESE);
+//! game.play(Move::Play(6, 2)).play(Move::Play(2, 6));
+//! assert_eq!(
+//!     game.goban().to_psgo(&PsgoOptions::default()),
+//!     "\\begin{psgoboard}[9]\n\\stone{white}{g}{7}\n\\stone{black}{c}{3}\n\\end{psgoboard}\n"
+//! );
+//! let diagram = game.moves_to_psgo(0..2, &PsgoOptions::default());
+//! assert!(diagram.contains("\\stone[\\marklb{1}]{black}{c}{3}"));
+//! ```
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::pieces::goban::Goban;
+use crate::pieces::stones::Color;
+use crate::pieces::util::coord::{Coord, Size};
+use crate::rules::annotation::Markup;
+use crate::rules::game::Game;
+use crate::rules::Move;
+
+/// Columns of psgo, the letter i is skipped.
+const COLUMNS: &str = "abcdefghjklmnopqrstuvwxyz";
+
+/// Part of the goban shown by the diagram.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Crop {
+    /// The whole goban.
+    #[default]
+    Full,
+    /// The rectangle between two opposite corners, included.
+    Region(Coord, Coord),
+    /// The smallest rectangle with the stones and the markup, grown by `margin` lines. A side
+    /// that ends up at most `margin` lines from the edge is extended to the edge.
+    Fit { margin: u8 },
+}
+
+/// Options of the psgo export.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PsgoOptions {
+    pub crop: Crop,
+    /// Marks drawn over the stones and the empty points.
+    pub markup: Markup,
+}
+
+impl Goban {
+    /// Writes the goban as a `psgoboard`, or a `psgopartialboard` when cropped.
+    /// psgo only draws square gobans, a rectangular one is drawn on a square of its largest side.
+    pub fn to_psgo(&self, options: &PsgoOptions) -> String {
+        let stones = self
+            .get_stones()
+            .map(|stone| (stone.coord, (stone.color, None)))
+            .collect();
+        psgo(self.size(), &stones, options, &[])
+    }
+}
+
+impl Game {
+    /// Writes the diagram of the moves of the range, counted from 0 like [`Game::moves`]: the
+    /// position before the first move with each stone of the range numbered by its move number.
+    /// The captured stones stay on the diagram. A stone played where the diagram already has
+    /// one, after a capture, and the passes are written as notes after the diagram, like
+    /// `7 at 3`.
+    pub fn moves_to_psgo(&self, moves: Range<usize>, options: &PsgoOptions) -> String {
+        let records = self.history.records();
+        let end = moves.end.min(records.len());
+        let start = moves.start.min(end);
+        let goban = records
+            .get(start)
+            .map_or(&self.goban, |record| &record.goban);
+        let mut stones: BTreeMap<Coord, (Color, Option<usize>)> = goban
+            .get_stones()
+            .map(|stone| (stone.coord, (stone.color, None)))
+            .collect();
+        let mut notes = vec![];
+        for (index, record) in records.iter().enumerate().take(end).skip(start) {
+            let number = index + 1;
+            match record.played {
+                Move::Play(row, column) => match stones.get(&(row, column)) {
+                    Some((_, Some(first))) => notes.push(format!("{number} at {first}")),
+                    Some((_, None)) => {
+                        let (height, _) = self.size();
+                        notes.push(format!("{number} at {}", vertex((row, column), height)))
+                    }
+                    None => {
+                        stones.insert((row, column), (record.turn, Some(number)));
+                    }
+                },
+                Move::Pass => notes.push(format!("{number} pass")),
+                Move::Resign(_) => (),
+            }
+        }
+        psgo(self.size(), &stones, options, &notes)
+    }
+}
+
+/// The point in the letter and number of psgo, like `d4`.
+fn vertex((row, column): Coord, height: u8) -> String {
+    format!("{}{}", letter(column), height - row)
+}
+
+fn letter(column: u8) -> char {
+    COLUMNS.as_bytes()[column as usize] as char
+}
+
+/// The fragment of the stones with their number, followed by the notes.
+fn psgo(
+    (height, width): Size,
+    stones: &BTreeMap<Coord, (Color, Option<usize>)>,
+    options: &PsgoOptions,
+    notes: &[String],
+) -> String {
+    let side = height.max(width);
+    let number = |row: u8| height - row;
+    let ((top, left), (low, right)) = bounds((height, width), stones, options);
+    let cropped = (top, left, low, right) != (0, 0, height - 1, width - 1);
+
+    let mut fragment = if cropped {
+        format!(
+            "\\begin{{psgopartialboard}}[{side}]{{({},{})({},{})}}\n",
+            letter(left),
+            number(low),
+            letter(right),
+            number(top)
+        )
+    } else {
+        format!("\\begin{{psgoboard}}[{side}]\n")
+    };
+    let markup = &options.markup;
+    for row in top..=low {
+        for column in left..=right {
+            let point = (row, column);
+            let (x, y) = (letter(column), number(row));
+            let mark = if let Some(label) = markup.labels.get(&point) {
+                Some(format!("\\marklb{{{label}}}"))
+            } else if markup.triangles.contains(&point) {
+                Some("\\marktr".to_string())
+            } else if markup.squares.contains(&point) {
+                Some("\\marksq".to_string())
+            } else if markup.circles.contains(&point) {
+                Some("\\markcr".to_string())
+            } else {
+                None
+            };
+            match stones.get(&point) {
+                Some(&(color, number)) => {
+                    let color = match color {
+                        Color::Black => "black",
+                        Color::White => "white",
+                    };
+                    let mark = number.map(|n| format!("\\marklb{{{n}}}")).or(mark);
+                    let mark = mark.map(|mark| format!("[{mark}]")).unwrap_or_default();
+                    fragment += &format!("\\stone{mark}{{{color}}}{{{x}}}{{{y}}}\n");
+                }
+                None => {
+                    if let Some(mark) = mark {
+                        fragment += &format!("\\markpos{{{mark}}}{{{x}}}{{{y}}}\n");
+                    }
+                }
+            }
+        }
+    }
+    fragment += if cropped {
+        "\\end{psgopartialboard}\n"
+    } else {
+        "\\end{psgoboard}\n"
+    };
+    if !notes.is_empty() {
+        fragment += &notes.join(", ");
+        fragment.push('\n');
+    }
+    fragment
+}
+
+/// Top left and bottom right corners of the part of the goban shown.
+fn bounds(
+    (height, width): Size,
+    stones: &BTreeMap<Coord, (Color, Option<usize>)>,
+    options: &PsgoOptions,
+) -> (Coord, Coord) {
+    let full = ((0, 0), (height - 1, width - 1));
+    match options.crop {
+        Crop::Full => full,
+        Crop::Region((row1, column1), (row2, column2)) => (
+            (row1.min(row2), column1.min(column2)),
+            (
+                row1.max(row2).min(height - 1),
+                column1.max(column2).min(width - 1),
+            ),
+        ),
+        Crop::Fit { margin } => {
+            let markup = &options.markup;
+            let points: Vec<Coord> = stones
+                .keys()
+                .chain(&markup.triangles)
+                .chain(&markup.squares)
+                .chain(&markup.circles)
+                .chain(markup.labels.keys())
+                .copied()
+                .collect();
+            if points.is_empty() {
+                return full;
+            }
+            let grow_low = |value: u8| value.saturating_sub(margin);
+            let grow_high = |value: u8, last: u8| value.saturating_add(margin).min(last);
+            let snap_low = |value: u8| if value <= margin { 0 } else { value };
+            let snap_high = |value: u8, last: u8| {
+                if last - value <= margin {
+                    last
+                } else {
+                    value
+                }
+            };
+            let top = points.iter().map(|p| p.0).min().unwrap();
+            let low = points.iter().map(|p| p.0).max().unwrap();
+            let left = points.iter().map(|p| p.1).min().unwrap();
+            let right = points.iter().map(|p| p.1).max().unwrap();
+            (
+                (snap_low(grow_low(top)), snap_low(grow_low(left))),
+                (
+                    snap_high(grow_high(low, height - 1), height - 1),
+                    snap_high(grow_high(right, width - 1), width - 1),
+                ),
+            )
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Scoring phase at the end of the game, when the players agree on the dead stones.
+//!
+//! The dead stones are only marked, the goban of the game isn't modified until the score is
+//! finalized, so the game can be resumed exactly as it was when the players disagree.
+//! # Example
+//! ```
+//! use goban::rules::game::Game;
+//! use goban::rules::{GobanSizes, Move, CHINESE};
+//!
+//! let mut game = Game::new(GobanSizes::Nine, CHINESE);
+//! game.play(Move::Play(4, 4)).play(Move::Pass).play(Move::Pass);
+//! let session = game.enter_scoring().unwrap();
+//! session.toggle_dead((4, 4));
+//! let (black, white) = session.score();
+//! assert_eq!(black, 0.);
+//! game.resume_play().unwrap();
+//! game.play(Move::Play(3, 3));
+//! ```
+
+use std::collections::BTreeSet;
+
+use crate::pieces::goban::{Goban, GroupIdx};
+use crate::pieces::util::coord::Coord;
+use crate::rules::events::GameEvent;
+use crate::rules::game::{remove_dead_chains, score_position, Game};
+use crate::rules::{EndGame, Rule, ScoringError};
+
+/// Dead stones marked by the players and the provisional score.
+#[derive(Clone, Debug)]
+pub struct ScoringSession {
+    goban: Goban,
+    prisoners: (u32, u32),
+    rule: Rule,
+    handicap: u32,
+    dead: BTreeSet<Coord>,
+    /// The chains toggled since the game took them, Some when the game records its events.
+    toggles: Option<Vec<GroupIdx>>,
+}
+
+impl ScoringSession {
+    fn new(game: &Game) -> Self {
+        ScoringSession {
+            goban: game.goban().clone(),
+            prisoners: game.prisoners(),
+            rule: game.rule(),
+            handicap: game.handicap(),
+            dead: BTreeSet::new(),
+            toggles: game.events.as_ref().map(|_| vec![]),
+        }
+    }
+
+    /// Marks the chain of the stone on the point as dead, or alive if it was marked dead.
+    /// Returns true if the chain is now dead, false if alive or if the point is empty.
+    pub fn toggle_dead(&mut self, point: Coord) -> bool {
+        let Some(chain) = self.goban.chain_idx(point) else {
+            return false;
+        };
+        let dead = !self.dead.contains(&point);
+        for stone in self.goban.chain_stones(chain) {
+            if dead {
+                self.dead.insert(stone.coord);
+            } else {
+                self.dead.remove(&stone.coord);
+            }
+        }
+        if let Some(toggles) = &mut self.toggles {
+            toggles.push(chain);
+        }
+        dead
+    }
+
+    /// Returns true if the stone on the point is marked dead.
+    #[inline]
+    pub fn is_dead(&self, point: Coord) -> bool {
+        self.dead.contains(&point)
+    }
+
+    /// Returns the stones marked dead in row major order.
+    pub fn dead_stones(&self) -> impl Iterator<Item = Coord> + '_ {
+        self.dead.iter().copied()
+    }
+
+    /// The goban being scored, with the dead stones still on it.
+    #[inline]
+    pub fn goban(&self) -> &Goban {
+        &self.goban
+    }
+
+    /// Score with the dead stones removed as prisoners, by the rule of the game, like
+    /// [`Game::calculate_score_with_dead`].
+    pub fn score(&self) -> (f32, f32) {
+        score_position(
+            &self.goban,
+            self.prisoners,
+            self.rule.komi,
+            self.handicap,
+            self.rule.flag_score,
+            &self.dead_chains(),
+        )
+    }
+
+    /// The chains of the dead stones, in the goban of the session.
+    pub fn dead_chains(&self) -> Vec<GroupIdx> {
+        let mut chains: Vec<GroupIdx> = self
+            .dead
+            .iter()
+            .filter_map(|&point| self.goban.chain_idx(point))
+            .collect();
+        chains.sort_unstable();
+        chains.dedup();
+        chains
+    }
+
+    /// The goban with the dead stones removed and the prisoners.
+    fn scored_position(&self) -> (Goban, (u32, u32)) {
+        let mut goban = self.goban.clone();
+        let mut prisoners = self.prisoners;
+        remove_dead_chains(&mut goban, &mut prisoners, &self.dead_chains());
+        (goban, prisoners)
+    }
+
+    /// Starts or stops keeping the chains toggled for the events of the game.
+    pub(super) fn record_toggles(&mut self, record: bool) {
+        self.toggles = record.then(Vec::new);
+    }
+
+    /// The chains toggled since the last call.
+    pub(super) fn take_toggles(&mut self) -> impl Iterator<Item = GroupIdx> + '_ {
+        self.toggles.iter_mut().flat_map(|toggles| toggles.drain(..))
+    }
+
+    pub(crate) fn swap_colors_mut(&mut self) {
+        self.goban.swap_colors_mut();
+        self.prisoners = (self.prisoners.1, self.prisoners.0);
+    }
+}
+
+impl Game {
+    /// Starts the scoring phase after the passes ending the game, where the players mark the
+    /// dead stones.
+    ///
+    /// # Errors
+    /// Returns `NotPaused` if the players haven't passed enough, `AlreadyScoring` if the game
+    /// is already in the scoring phase and `GameOver` if the game has an outcome.
+    pub fn enter_scoring(&mut self) -> Result<&mut ScoringSession, ScoringError> {
+        if self.outcome.is_some() {
+            Err(ScoringError::GameOver)
+        } else if self.scoring.is_some() {
+            Err(ScoringError::AlreadyScoring)
+        } else if self.passes < self.rule.passes_to_end {
+            Err(ScoringError::NotPaused)
+        } else {
+            self.push_event(GameEvent::ScoringStarted);
+            Ok(self.scoring.insert(ScoringSession::new(self)))
+        }
+    }
+
+    /// The scoring session, None if the game isn't in the scoring phase.
+    #[inline]
+    pub fn scoring_session(&self) -> Option<&ScoringSession> {
+        self.scoring.as_ref()
+    }
+
+    /// The scoring session to mark stones, None if the game isn't in the scoring phase.
+    #[inline]
+    pub fn scoring_session_mut(&mut self) -> Option<&mut ScoringSession> {
+        self.scoring.as_mut()
+    }
+
+    /// Abandons the scoring phase. The marks are discarded, the goban is unchanged and the
+    /// passes are cleared, so the play continues with the same super ko history.
+    ///
+    /// # Errors
+    /// Returns `NotScoring` if the game isn't in the scoring phase.
+    pub fn resume_play(&mut self) -> Result<(), ScoringError> {
+        self.flush_toggles();
+        self.scoring.take().ok_or(ScoringError::NotScoring)?;
+        self.passes = 0;
+        Ok(())
+    }
+
+    /// Ends the scoring phase: the dead stones are removed as prisoners and the outcome of the
+    /// game is set from the score.
+    ///
+    /// # Errors
+    /// Returns `NotScoring` if the game isn't in the scoring phase.
+    pub fn finalize_score(&mut self) -> Result<EndGame, ScoringError> {
+        self.flush_toggles();
+        let session = self.scoring.take().ok_or(ScoringError::NotScoring)?;
+        let (goban, prisoners) = session.scored_position();
+        let outcome = EndGame::from_score_by(session.score(), self.rule.flag_score);
+        self.goban = goban;
+        self.prisoners = prisoners;
+        self.outcome = Some(outcome);
+        self.reset_legal_cache();
+        self.push_event(GameEvent::GameEnded(outcome));
+        Ok(outcome)
+    }
+}
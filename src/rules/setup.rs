@@ -0,0 +1,148 @@
+//! # Position setup
+//! Places stones of both colors without any rule check, like a problem editor does.
+//! The position is only validated when the game is created.
+//! # Example
+//! ```
+//! use goban::pieces::stones::Color;
+//! use goban::rules::game::Game;
+//! use goban::rules::SetupError;
+//!
+//! let mut setup = Game::setup((9, 9));
+//! setup
+//!     .put((0, 0), Color::White)
+//!     .put((0, 1), Color::Black)
+//!     .put((1, 0), Color::Black);
+//! assert_eq!(setup.finish().unwrap_err(), SetupError::NoLiberties((0, 0)));
+//!
+//! let game = setup.auto_capture(true).set_to_move(Color::White).finish().unwrap();
+//! assert_eq!(game.goban().get_color((0, 0)), None);
+//! ```
+
+use std::mem::replace;
+
+use crate::pieces::goban::Goban;
+use crate::pieces::stones::Color;
+use crate::pieces::util::coord::{Coord, Size};
+use crate::rules::game::Game;
+use crate::rules::{GobanSizes, Rule, SetupError, CHINESE};
+
+/// Builder of a position with stones of both colors, the rules are only checked by
+/// [`SetupBuilder::finish`]. Intermediate positions can have chains without liberties.
+#[derive(Clone, Debug)]
+pub struct SetupBuilder {
+    goban: Goban,
+    rule: Rule,
+    turn: Color,
+    auto_capture: bool,
+    out_of_bounds: Option<Coord>,
+}
+
+impl SetupBuilder {
+    pub fn new(size: Size) -> Self {
+        SetupBuilder {
+            goban: Goban::new(size),
+            rule: CHINESE,
+            turn: Color::Black,
+            auto_capture: false,
+            out_of_bounds: None,
+        }
+    }
+
+    /// Puts a stone on the point, the stone already there is replaced.
+    pub fn put(&mut self, coord: Coord, color: Color) -> &mut Self {
+        if self.check_bounds(coord) {
+            self.goban.push(coord, color);
+        }
+        self
+    }
+
+    /// Removes the stone on the point if any.
+    pub fn remove(&mut self, coord: Coord) -> &mut Self {
+        if self.check_bounds(coord) {
+            self.goban.put_empty(coord);
+        }
+        self
+    }
+
+    pub fn set_to_move(&mut self, color: Color) -> &mut Self {
+        self.turn = color;
+        self
+    }
+
+    pub fn rule(&mut self, rule: Rule) -> &mut Self {
+        self.rule = rule;
+        self
+    }
+
+    /// If true, [`SetupBuilder::finish`] removes the chains without liberties instead of
+    /// returning an error. The removed stones aren't counted as prisoners.
+    pub fn auto_capture(&mut self, auto_capture: bool) -> &mut Self {
+        self.auto_capture = auto_capture;
+        self
+    }
+
+    /// The position being set up.
+    #[inline]
+    pub fn goban(&self) -> &Goban {
+        &self.goban
+    }
+
+    /// Validates the position and creates a game starting from it, with an empty history.
+    /// The builder is then reset to an empty goban of the same size, on an error it's left
+    /// untouched so the position can be fixed.
+    ///
+    /// # Errors
+    /// If a stone was put or removed out of the goban, or if a chain has no liberties
+    /// and auto capture isn't requested. The error gives the first stone of the chain in
+    /// row major order.
+    pub fn finish(&mut self) -> Result<Game, SetupError> {
+        if let Some(coord) = self.out_of_bounds {
+            return Err(SetupError::OutOfBounds(coord));
+        }
+        let dead: Vec<_> = self
+            .goban
+            .get_groups()
+            .filter(|(_, chain)| chain.is_dead())
+            .map(|(idx, _)| idx)
+            .collect();
+        if let (Some(&first), false) = (dead.first(), self.auto_capture) {
+            let coord = self.goban.chain_stones(first).map(|s| s.coord).min().unwrap();
+            return Err(SetupError::NoLiberties(coord));
+        }
+
+        let (height, width) = self.goban.size();
+        let mut goban = replace(&mut self.goban, Goban::new((height, width)));
+        for idx in dead {
+            goban.remove_chain(idx);
+        }
+        let mut game = Game::new(
+            GobanSizes::Custom(height as usize, width as usize),
+            self.rule,
+        );
+        game.goban = goban;
+        game.turn = self.turn;
+        Ok(game)
+    }
+
+    fn check_bounds(&mut self, coord: Coord) -> bool {
+        let (height, width) = self.goban.size();
+        let inside = coord.0 < height && coord.1 < width;
+        if !inside && self.out_of_bounds.is_none() {
+            self.out_of_bounds = Some(coord);
+        }
+        inside
+    }
+}
+
+impl Default for SetupBuilder {
+    fn default() -> Self {
+        SetupBuilder::new((19, 19))
+    }
+}
+
+impl Game {
+    /// Starts setting up a position on an empty goban of the size.
+    pub fn setup(size: Size) -> SetupBuilder {
+        SetupBuilder::new(size)
+    }
+}
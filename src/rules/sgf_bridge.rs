@@ -1,22 +1,91 @@
-use sgf_parser::{Action, Outcome, RuleSet, SgfToken};
+use std::collections::BTreeMap;
+use std::mem::take;
+use std::time::Duration;
+
+use sgf_parser::{Action, Encoding, GameNode, GameTree, Outcome, RuleSet, SgfToken};
 
 use crate::pieces::stones::{Color, Stone};
-use crate::pieces::Nat;
+use crate::pieces::util::coord::{
+    coord_to_sgf, coord_to_sgf_pair, parse_sgf_pair, sgf_pair_to_coord, Coord,
+};
+use crate::rules::annotation::{Annotation, MoveQuality};
+use crate::rules::clock::{Clock, TimeControl};
 use crate::rules::game::Game;
 use crate::rules::game_builder::GameBuilder;
-use crate::rules::{EndGame, Move, Rule, CHINESE, JAPANESE};
+use crate::rules::{EndGame, Move, PlayError, Rule, AGA, CHINESE, ING, JAPANESE};
+use crate::sgf::{
+    decode_sgf, IllegalMovePolicy, SgfCharsetWarning, SgfCollection, SgfImportOptions,
+    SgfImportWarning,
+};
 
 type SgfColor = sgf_parser::Color;
 
 impl Game {
+    /// Loads the main line of a SGF. The parsed record is kept in the game, so
+    /// [`Game::to_sgf`] gives back the original record with the moves played after it.
+    /// The setup of the root, `AB`, `AW` and `AE`, goes through a
+    /// [`SetupBuilder`](crate::rules::setup::SetupBuilder), so a chain left without
+    /// liberties is an error.
+    /// If the SGF is a collection, only its first game is loaded, see
+    /// [`Game::from_sgf_first`] and [`SgfCollection`](crate::sgf::SgfCollection).
+    ///
+    /// The moves of the record must be legal, see [`Game::from_sgf_with`] for the records with
+    /// illegal moves.
     pub fn from_sgf(sgf_str: &str) -> Result<Self, String> {
         let game_tree = match sgf_parser::parse(sgf_str) {
             Ok(game) => Ok(game),
             Err(e) => Err(e.to_string()),
         }?;
+        Game::from_game_tree(game_tree)
+    }
+
+    /// Loads the main line of a SGF like [`Game::from_sgf`], the illegal moves are handled by
+    /// the options. Returns the game with the moves skipped or forced.
+    /// When a move was skipped, the record isn't kept: [`Game::to_sgf`] exports the moves played.
+    pub fn from_sgf_with(
+        sgf_str: &str,
+        options: SgfImportOptions,
+    ) -> Result<(Self, Vec<SgfImportWarning>), String> {
+        let game_tree = sgf_parser::parse(sgf_str).map_err(|e| e.to_string())?;
+        Game::from_game_tree_with(game_tree, options)
+    }
+
+    /// Loads the main line of a SGF file read as bytes, like [`Game::from_sgf`]. The text is
+    /// decoded with the charset of its `CA` property, see [`decode_sgf`] for the charsets
+    /// supported. Returns the game with a warning when the text wasn't decoded exactly.
+    pub fn from_sgf_bytes(bytes: &[u8]) -> Result<(Self, Option<SgfCharsetWarning>), String> {
+        let (text, warning) = decode_sgf(bytes);
+        Game::from_sgf(&text).map(|game| (game, warning))
+    }
+
+    /// Loads the first game of a SGF collection, with the number of the other games which
+    /// were not loaded.
+    pub fn from_sgf_first(sgf_str: &str) -> Result<(Self, usize), String> {
+        let collection = SgfCollection::parse(sgf_str).map_err(|e| e.to_string())?;
+        let game = collection
+            .game(0)
+            .unwrap_or_else(|| Game::from_sgf(sgf_str))?;
+        Ok((game, collection.len().saturating_sub(1)))
+    }
+
+    /// Loads the main line of a parsed SGF, like [`Game::from_sgf`].
+    pub fn from_game_tree(game_tree: GameTree) -> Result<Self, String> {
+        Game::from_game_tree_with(game_tree, SgfImportOptions::default()).map(|(game, _)| game)
+    }
+
+    /// Loads the main line of a parsed SGF, like [`Game::from_sgf_with`].
+    pub fn from_game_tree_with(
+        game_tree: GameTree,
+        options: SgfImportOptions,
+    ) -> Result<(Self, Vec<SgfImportWarning>), String> {
         let mut game_builder: GameBuilder = Default::default();
         let mut first = true;
         let mut moves = vec![];
+        let mut annotations = BTreeMap::new();
+        let mut main_time = Duration::ZERO;
+        let mut overtime = None;
+        let mut clock = Clock::default();
+        let mut clocks = BTreeMap::new();
 
         for node in game_tree.iter() {
             if first {
@@ -27,8 +96,13 @@ impl Game {
                         SgfToken::Komi(komi) => {
                             game_builder.komi(*komi);
                         }
-                        SgfToken::Size(x, y) => {
-                            game_builder.size((*x as u8, *y as u8));
+                        SgfToken::Size(width, height) => {
+                            let (Ok(height), Ok(width)) =
+                                (u8::try_from(*height), u8::try_from(*width))
+                            else {
+                                return Err(format!("invalid goban size {width}x{height}"));
+                            };
+                            game_builder.size((height, width));
                         }
                         SgfToken::Result(o) => {
                             game_builder.outcome((*o).into());
@@ -38,13 +112,18 @@ impl Game {
                             coordinate: (x, y),
                         } => {
                             game_builder.add(Stone {
-                                coord: ((*y - 1) as Nat, (*x - 1) as Nat),
+                                coord: sgf_pair_to_coord((*x, *y)),
                                 color: match color {
                                     SgfColor::Black => Color::Black,
                                     SgfColor::White => Color::White,
                                 },
                             });
                         }
+                        SgfToken::Unknown((key, value)) if key == "AE" => {
+                            if let Ok(pair) = parse_sgf_pair(value) {
+                                game_builder.remove(sgf_pair_to_coord(pair));
+                            }
+                        }
                         SgfToken::Rule(rule) => {
                             game_builder.rule(rule.clone().into());
                         }
@@ -54,6 +133,13 @@ impl Game {
                         SgfToken::Game(go) => {
                             assert_eq!(*go, sgf_parser::Game::Go);
                         }
+                        SgfToken::TimeLimit(seconds) => {
+                            main_time = Duration::from_secs((*seconds).into());
+                        }
+                        SgfToken::Invalid((key, value)) if key == "TM" => {
+                            main_time = parse_seconds(value).unwrap_or_default();
+                        }
+                        SgfToken::Overtime(text) => overtime = Some(text.clone()),
 
                         //TODO another options
                         _ => (),
@@ -75,16 +161,494 @@ impl Game {
                     }
                 }
                 first = false;
-            } else if !node.tokens.is_empty() {
-                let token = node.tokens.first().unwrap();
-                if let SgfToken::Move { action, .. } = token {
-                    moves.push((*action).into());
+                if let Some(annotation) = node_annotation(node) {
+                    annotations.insert(0, annotation);
+                }
+                let control = time_control(main_time, overtime.as_deref());
+                if control.is_some() {
+                    overtime = None;
+                }
+                clock = Clock::new(control.unwrap_or(if main_time.is_zero() {
+                    TimeControl::None
+                } else {
+                    TimeControl::Absolute { main: main_time }
+                }));
+                if update_clock(&mut clock, node) {
+                    clocks.insert(0, clock);
+                }
+            } else if let Some(m) = node_move(node) {
+                let timed = update_clock(&mut clock, node).then_some(clock);
+                moves.push((m, node_annotation(node), timed));
+            }
+        }
+        let mut game = game_builder.build()?;
+        game.overtime = overtime;
+        let mut warnings = vec![];
+        let mut skipped = false;
+        for (index, (played, annotation, timed)) in moves.into_iter().enumerate() {
+            if let Err(error) = game.replay(played) {
+                let warning = SgfImportWarning {
+                    move_number: index + 1,
+                    played,
+                    error,
+                };
+                match (options.on_illegal, played) {
+                    (IllegalMovePolicy::Abort, _) => return Err(warning.to_string()),
+                    (IllegalMovePolicy::ForcePlace, Move::Play(x, y))
+                        if !matches!(error, PlayError::OutsideBoard(_)) =>
+                    {
+                        game.force_play((x, y));
+                        warnings.push(warning);
+                    }
+                    _ => {
+                        warnings.push(warning);
+                        skipped = true;
+                        continue;
+                    }
+                }
+            }
+            if let Some(annotation) = annotation {
+                annotations.insert(game.history.len(), annotation);
+            }
+            if let Some(timed) = timed {
+                clocks.insert(game.history.len(), timed);
+            }
+        }
+        if !skipped {
+            game.sgf_source = Some(game_tree);
+        }
+        game.annotations = annotations;
+        game.clock = clock;
+        game.clocks = clocks;
+        Ok((game, warnings))
+    }
+
+    /// Exports the game in SGF.
+    ///
+    /// If the game was loaded with [`Game::from_sgf`], the original record is kept, comments,
+    /// markup and variations included, and the moves played since are appended to its main line.
+    /// When moves of the record were undone, the new moves become the main line and the old
+    /// ones a variation. The result of the root is updated with the outcome of the game.
+    /// SGF has no resign move, so a resign is only exported as the result, like `RE[W+R]`.
+    /// The [annotations](Game::annotate) replace the comments and markup of the root and of the
+    /// nodes of the moves.
+    pub fn to_sgf(&self) -> String {
+        let mut tree = self
+            .sgf_source
+            .clone()
+            .unwrap_or_else(|| GameTree {
+                nodes: vec![self.sgf_root()],
+                variations: vec![],
+            });
+
+        let root = &mut tree.nodes[0];
+        root.tokens
+            .retain(|token| !matches!(token, SgfToken::Result(_) | SgfToken::Charset(_)));
+        // The text is written in UTF-8 whatever the charset of the source.
+        root.tokens.push(SgfToken::Charset(Encoding::UTF8));
+        if let Some(outcome) = self.outcome() {
+            root.tokens.push(SgfToken::Result(outcome.into()));
+        }
+
+        // Index in the main line of the node of each move in the record.
+        let move_nodes: Vec<usize> = tree
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter_map(|(i, node)| node_move(node).map(|_| i))
+            .collect();
+        let recorded: Vec<Move> = tree.iter().skip(1).filter_map(node_move).collect();
+        let played: Vec<(Move, Color)> = self
+            .history
+            .records()
+            .iter()
+            .filter(|record| !matches!(record.played, Move::Resign(_)))
+            .map(|record| (record.played, record.turn))
+            .collect();
+        let common = recorded
+            .iter()
+            .zip(&played)
+            .take_while(|(&a, &(b, _))| a == b)
+            .count();
+
+        let at = match move_nodes.get(common) {
+            Some(&i) => i - 1,
+            None => tree.iter().count() - 1,
+        };
+        let line = played[common..]
+            .iter()
+            .zip(common + 1..)
+            .map(|(&(m, color), move_number)| {
+                let mut tokens = vec![SgfToken::Move {
+                    color: color.into(),
+                    action: m.into(),
+                }];
+                if self.clocks.contains_key(&move_number) {
+                    let previous = self.clock_at(move_number - 1);
+                    tokens.extend(time_tokens(&self.clock_at(move_number), &previous));
+                }
+                GameNode { tokens }
+            })
+            .collect();
+        graft(&mut tree, at, line);
+
+        let mut nodes = main_line_mut(&mut tree).into_iter();
+        let root = nodes.next().expect("The tree has a root");
+        let move_nodes = nodes.filter(|node| node_move(node).is_some());
+        for (move_number, node) in std::iter::once(root).chain(move_nodes).enumerate() {
+            node.tokens.retain(|token| !is_annotation_token(token));
+            if let Some(annotation) = self.annotations.get(&move_number) {
+                node.tokens.extend(annotation_tokens(annotation));
+            }
+        }
+        tree.into()
+    }
+
+    /// Root node of a game without a SGF source.
+    fn sgf_root(&self) -> GameNode {
+        let (height, width) = self.size();
+        let mut tokens = vec![
+            SgfToken::Game(sgf_parser::Game::Go),
+            SgfToken::FileFormat(4),
+            SgfToken::Size(width as u32, height as u32),
+            SgfToken::Komi(self.komi()),
+        ];
+        let flags = |rule: Rule| (rule.flag_illegal, rule.flag_score);
+        if flags(self.rule()) == flags(JAPANESE) {
+            tokens.push(SgfToken::Rule(RuleSet::Japanese));
+        } else if flags(self.rule()) == flags(CHINESE) {
+            tokens.push(SgfToken::Rule(RuleSet::Chinese));
+        } else if flags(self.rule()) == flags(ING) {
+            tokens.push(SgfToken::Rule(RuleSet::GOE));
+        } else if flags(self.rule()) == flags(AGA) {
+            tokens.push(SgfToken::Rule(RuleSet::AGA));
+        }
+        if self.handicap != 0 {
+            tokens.push(SgfToken::Handicap(self.handicap));
+        }
+        tokens.extend(time_control_tokens(self.clock.control(), self.sgf_overtime()));
+        if self.clocks.contains_key(&0) {
+            let start = Clock::new(self.clock.control());
+            tokens.extend(time_tokens(&self.clock_at(0), &start));
+        }
+        let (setup, turn) = match self.history.records().first() {
+            Some(record) => (&record.goban, record.turn),
+            None => (&self.goban, self.turn),
+        };
+        tokens.extend(setup.get_stones().map(|stone| SgfToken::Add {
+            color: stone.color.into(),
+            coordinate: coord_to_sgf_pair(stone.coord),
+        }));
+        if turn == Color::White && self.handicap == 0 {
+            tokens.push(SgfToken::Unknown(("PL".to_string(), "W".to_string())));
+        }
+        GameNode { tokens }
+    }
+}
+
+/// Time control of the `TM` and `OT` properties, `None` if the overtime isn't a byo-yomi like
+/// `5x30 byo-yomi` or a canadian overtime like `25/600 Canadian`.
+fn time_control(main: Duration, overtime: Option<&str>) -> Option<TimeControl> {
+    let Some(overtime) = overtime else {
+        return Some(if main.is_zero() {
+            TimeControl::None
+        } else {
+            TimeControl::Absolute { main }
+        });
+    };
+    let (numbers, kind) = overtime.trim().split_once(char::is_whitespace)?;
+    let kind = kind.trim().to_ascii_lowercase();
+    if let Some((periods, period)) = numbers.split_once(['x', 'X']) {
+        if kind.starts_with("byo") {
+            return Some(TimeControl::ByoYomi {
+                main,
+                period: parse_seconds(period)?,
+                periods: periods.parse().ok()?,
+            });
+        }
+    }
+    if let Some((stones, period)) = numbers.split_once('/') {
+        if kind.starts_with("canadian") {
+            return Some(TimeControl::Canadian {
+                main,
+                period: parse_seconds(period)?,
+                stones: stones.parse().ok()?,
+            });
+        }
+    }
+    None
+}
+
+/// `TM` and `OT` of the time control, the overtime is written when it isn't known.
+fn time_control_tokens(control: TimeControl, overtime: Option<&str>) -> Vec<SgfToken> {
+    let main = |main: Duration| SgfToken::TimeLimit(main.as_secs() as u32);
+    match control {
+        TimeControl::None => vec![],
+        TimeControl::Absolute { main: time } => {
+            let overtime = overtime.map(|text| SgfToken::Overtime(text.to_owned()));
+            std::iter::once(main(time)).chain(overtime).collect()
+        }
+        TimeControl::ByoYomi {
+            main: time,
+            period,
+            periods,
+        } => vec![
+            main(time),
+            SgfToken::Overtime(format!("{periods}x{} byo-yomi", period.as_secs())),
+        ],
+        TimeControl::Canadian {
+            main: time,
+            period,
+            stones,
+        } => vec![
+            main(time),
+            SgfToken::Overtime(format!("{stones}/{} Canadian", period.as_secs())),
+        ],
+    }
+}
+
+/// Updates the clock with the `BL`, `WL`, `OB` and `OW` of the node, returns true if there
+/// was any.
+fn update_clock(clock: &mut Clock, node: &GameNode) -> bool {
+    let mut updated = false;
+    for token in &node.tokens {
+        let (color, time, stones) = match token {
+            SgfToken::Time { color, time } => {
+                (color, Some(Duration::from_secs((*time).into())), None)
+            }
+            SgfToken::Invalid((key, value)) if key == "BL" || key == "WL" => {
+                let color = if key == "BL" { &SgfColor::Black } else { &SgfColor::White };
+                match parse_seconds(value) {
+                    Some(time) => (color, Some(time), None),
+                    None => continue,
+                }
+            }
+            SgfToken::MovesRemaining { color, moves } => (color, None, Some(*moves)),
+            _ => continue,
+        };
+        let color = match color {
+            SgfColor::Black => Color::Black,
+            SgfColor::White => Color::White,
+        };
+        let mut time_left = clock.time_left(color);
+        time_left.time = time.unwrap_or(time_left.time);
+        time_left.stones = stones.unwrap_or(time_left.stones);
+        clock.set_time_left(color, time_left);
+        updated = true;
+    }
+    updated
+}
+
+/// `BL`, `WL`, `OB` and `OW` of the players whose time left changed since the previous clock,
+/// in whole seconds.
+fn time_tokens(clock: &Clock, previous: &Clock) -> Vec<SgfToken> {
+    let mut tokens = vec![];
+    for color in [Color::Black, Color::White] {
+        let time_left = clock.time_left(color);
+        if time_left == previous.time_left(color) {
+            continue;
+        }
+        tokens.push(SgfToken::Time {
+            color: color.into(),
+            time: time_left.time.as_secs() as u32,
+        });
+        if time_left.in_overtime() {
+            tokens.push(SgfToken::MovesRemaining {
+                color: color.into(),
+                moves: time_left.stones,
+            });
+        }
+    }
+    tokens
+}
+
+/// Seconds of a time property, which may have decimals.
+fn parse_seconds(value: &str) -> Option<Duration> {
+    Duration::try_from_secs_f64(value.trim().parse().ok()?).ok()
+}
+
+/// Returns the move of the node, the same way the main line is read.
+fn node_move(node: &GameNode) -> Option<Move> {
+    node.tokens.iter().find_map(|token| match token {
+        SgfToken::Move { action, .. } => Some((*action).into()),
+        _ => None,
+    })
+}
+
+/// The nodes of the main line, following the first variation.
+fn main_line_mut(tree: &mut GameTree) -> Vec<&mut GameNode> {
+    let mut nodes: Vec<&mut GameNode> = tree.nodes.iter_mut().collect();
+    if let Some(variation) = tree.variations.first_mut() {
+        nodes.extend(main_line_mut(variation));
+    }
+    nodes
+}
+
+/// The parser reads the circles `CR` as a copyright, a point tells them apart.
+fn circle(token: &SgfToken) -> Option<Coord> {
+    let value = match token {
+        SgfToken::Copyright(value) => value,
+        SgfToken::Unknown((key, value)) if key == "CR" => value,
+        _ => return None,
+    };
+    parse_sgf_pair(value).ok().map(sgf_pair_to_coord)
+}
+
+/// The parser keeps the move quality properties as unknown. The emphasis of `TE` and `BM` is
+/// 2 for an emphasized move, any other value is read as a normal one.
+fn quality(token: &SgfToken) -> Option<MoveQuality> {
+    let SgfToken::Unknown((key, value)) = token else {
+        return None;
+    };
+    let emphasized = value.trim() == "2";
+    match key.as_str() {
+        "TE" => Some(MoveQuality::Good { emphasized }),
+        "BM" => Some(MoveQuality::Bad { emphasized }),
+        "DO" => Some(MoveQuality::Doubtful),
+        "IT" => Some(MoveQuality::Interesting),
+        _ => None,
+    }
+}
+
+fn quality_token(quality: MoveQuality) -> SgfToken {
+    let emphasis = |emphasized| if emphasized { "2" } else { "1" };
+    let (key, value) = match quality {
+        MoveQuality::Good { emphasized } => ("TE", emphasis(emphasized)),
+        MoveQuality::Bad { emphasized } => ("BM", emphasis(emphasized)),
+        MoveQuality::Doubtful => ("DO", ""),
+        MoveQuality::Interesting => ("IT", ""),
+    };
+    SgfToken::Unknown((key.to_string(), value.to_string()))
+}
+
+fn is_annotation_token(token: &SgfToken) -> bool {
+    matches!(
+        token,
+        SgfToken::Comment(_)
+            | SgfToken::Triangle { .. }
+            | SgfToken::Square { .. }
+            | SgfToken::Label { .. }
+    ) || circle(token).is_some()
+        || quality(token).is_some()
+}
+
+/// The comment and markup of the node, None if it has none.
+fn node_annotation(node: &GameNode) -> Option<Annotation> {
+    let mut annotation = Annotation::default();
+    for token in &node.tokens {
+        match token {
+            SgfToken::Comment(text) => annotation.comment = Some(unescape_text(text)),
+            SgfToken::Triangle { coordinate } => {
+                let coord = sgf_pair_to_coord(*coordinate);
+                annotation.markup.triangles.insert(coord);
+            }
+            SgfToken::Square { coordinate } => {
+                let coord = sgf_pair_to_coord(*coordinate);
+                annotation.markup.squares.insert(coord);
+            }
+            SgfToken::Label { label, coordinate } => {
+                let (coord, label) = (sgf_pair_to_coord(*coordinate), unescape_text(label));
+                annotation.markup.labels.insert(coord, label);
+            }
+            token => {
+                if let Some(coord) = circle(token) {
+                    annotation.markup.circles.insert(coord);
+                }
+                if let Some(quality) = quality(token) {
+                    annotation.quality = Some(quality);
                 }
             }
         }
-        game_builder.moves(&moves);
-        game_builder.build()
     }
+    (!annotation.is_empty()).then_some(annotation)
+}
+
+fn annotation_tokens(annotation: &Annotation) -> Vec<SgfToken> {
+    let markup = &annotation.markup;
+    let comment = annotation
+        .comment
+        .iter()
+        .map(|text| SgfToken::Comment(escape_text(text)));
+    let triangles = markup.triangles.iter().map(|&coord| SgfToken::Triangle {
+        coordinate: coord_to_sgf_pair(coord),
+    });
+    let squares = markup.squares.iter().map(|&coord| SgfToken::Square {
+        coordinate: coord_to_sgf_pair(coord),
+    });
+    let circles = markup
+        .circles
+        .iter()
+        .map(|&coord| SgfToken::Unknown(("CR".to_string(), coord_to_sgf(coord))));
+    let labels = markup.labels.iter().map(|(&coord, label)| SgfToken::Label {
+        label: escape_text(label),
+        coordinate: coord_to_sgf_pair(coord),
+    });
+    comment
+        .chain(triangles)
+        .chain(squares)
+        .chain(circles)
+        .chain(labels)
+        .chain(annotation.quality.map(quality_token))
+        .collect()
+}
+
+/// Escapes the backslashes and the right brackets of a SGF text. The parser reads a
+/// backslash before the closing bracket as an escaped bracket, so a trailing backslash is
+/// followed by a soft line break.
+fn escape_text(text: &str) -> String {
+    let mut escaped = text.replace('\\', "\\\\").replace(']', "\\]");
+    if text.ends_with('\\') {
+        escaped.push_str("\\\n");
+    }
+    escaped
+}
+
+/// Reads a SGF text, the escaped characters are kept and the soft line breaks removed.
+fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+        } else if let Some(escaped) = chars.next().filter(|&escaped| escaped != '\n') {
+            unescaped.push(escaped);
+        }
+    }
+    unescaped
+}
+
+/// Puts the line after the node at the index `at` of the main line. What followed the node
+/// is kept as a variation after the new line.
+fn graft(tree: &mut GameTree, at: usize, mut line: Vec<GameNode>) {
+    if at >= tree.nodes.len() {
+        let len = tree.nodes.len();
+        return graft(&mut tree.variations[0], at - len, line);
+    }
+    let rest = tree.nodes.split_off(at + 1);
+    let variations = take(&mut tree.variations);
+    if rest.is_empty() && variations.is_empty() {
+        tree.nodes.extend(line);
+        return;
+    }
+    if line.is_empty() {
+        // An empty node so the main line stops here.
+        line.push(GameNode { tokens: vec![] });
+    }
+    let new = GameTree {
+        nodes: line,
+        variations: vec![],
+    };
+    tree.variations = if rest.is_empty() {
+        std::iter::once(new).chain(variations).collect()
+    } else {
+        vec![
+            new,
+            GameTree {
+                nodes: rest,
+                variations,
+            },
+        ]
+    };
 }
 
 impl From<RuleSet> for Rule {
@@ -92,6 +656,8 @@ impl From<RuleSet> for Rule {
         match r {
             RuleSet::Japanese => JAPANESE,
             RuleSet::Chinese => CHINESE,
+            RuleSet::GOE => ING,
+            RuleSet::AGA => AGA,
             _ => panic!("The rule {} is not implemented yet !", r.to_string()),
         }
     }
@@ -121,8 +687,44 @@ impl From<Outcome> for EndGame {
 impl From<Action> for Move {
     fn from(a: Action) -> Self {
         match a {
-            Action::Move(col, line) => Move::Play((line - 1) as Nat, (col - 1) as Nat),
+            Action::Move(col, line) => sgf_pair_to_coord((col, line)).into(),
             Action::Pass => Move::Pass,
         }
     }
 }
+
+impl From<Color> for SgfColor {
+    fn from(c: Color) -> Self {
+        match c {
+            Color::Black => SgfColor::Black,
+            Color::White => SgfColor::White,
+        }
+    }
+}
+
+impl From<EndGame> for Outcome {
+    fn from(o: EndGame) -> Self {
+        match o {
+            EndGame::WinnerByResign(c) => Outcome::WinnerByResign(c.into()),
+            EndGame::WinnerByForfeit(c) => Outcome::WinnerByForfeit(c.into()),
+            EndGame::WinnerByScore(c, p) => Outcome::WinnerByPoints(c.into(), p),
+            EndGame::WinnerByTime(c) => Outcome::WinnerByTime(c.into()),
+            EndGame::Draw => Outcome::Draw,
+        }
+    }
+}
+
+impl From<Move> for Action {
+    /// # Panics
+    /// If the move is a resign, it's not a move in SGF.
+    fn from(m: Move) -> Self {
+        match m {
+            Move::Play(line, col) => {
+                let (col, line) = coord_to_sgf_pair((line, col));
+                Action::Move(col, line)
+            }
+            Move::Pass => Action::Pass,
+            Move::Resign(_) => panic!("A resign is not a SGF move"),
+        }
+    }
+}
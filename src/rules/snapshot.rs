@@ -0,0 +1,43 @@
+//! Positions shared by the threads of a parallel search.
+//!
+//! A [`GameSnapshot`] is an immutable copy of a game behind an [`Arc`], each worker reads it
+//! and forks its own game to play on.
+
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::rules::game::Game;
+
+/// Immutable copy of a game, the accessors of [`Game`] are reached through [`Deref`].
+#[derive(Debug)]
+pub struct GameSnapshot {
+    game: Game,
+}
+
+impl Deref for GameSnapshot {
+    type Target = Game;
+
+    fn deref(&self) -> &Self::Target {
+        &self.game
+    }
+}
+
+impl GameSnapshot {
+    /// Returns a game independent from the snapshot, to play on.
+    pub fn fork(&self) -> Game {
+        self.game.clone()
+    }
+
+    /// Copies the snapshot into a game of the worker, reusing its buffers, so going back to the
+    /// root after each line doesn't allocate.
+    pub fn fork_into(&self, scratch: &mut Game) {
+        scratch.clone_from(&self.game);
+    }
+}
+
+impl Game {
+    /// Returns an immutable copy of the game to share between threads, see [`GameSnapshot`].
+    pub fn snapshot(&self) -> Arc<GameSnapshot> {
+        Arc::new(GameSnapshot { game: self.clone() })
+    }
+}
@@ -0,0 +1,262 @@
+//! SGF collections, files with several game trees back to back.
+//!
+//! The games of a [`SgfCollection`] are only found when it's created, each one is parsed when
+//! it's accessed, so a dump of thousands of games can be opened without parsing all of them.
+//! # Example
+//! ```
+//! use goban::sgf::{write_collection, SgfCollection};
+//!
+//! let sgf = "(;GM[1]SZ[9];B[cc])\n(;GM[1]SZ[9];B[gg];W[cc])\n(;GM[1]SZ[13])";
+//! let collection = SgfCollection::parse(sgf).unwrap();
+//! assert_eq!(collection.len(), 3);
+//! let game = collection.game(1).unwrap().unwrap();
+//! assert_eq!(game.moves().count(), 2);
+//!
+//! let trees: Vec<_> = collection.iter().map(Result::unwrap).collect();
+//! let written = write_collection(&trees);
+//! assert_eq!(SgfCollection::parse(&written).unwrap().len(), 3);
+//! ```
+
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+
+use sgf_parser::{GameTree, SgfError};
+
+use crate::rules::game::Game;
+use crate::rules::{Move, PlayError};
+
+/// Errors when looking for the games of a collection, with the byte offset in the input.
+#[derive(Clone, Eq, PartialEq, Debug, Copy)]
+pub enum CollectionError {
+    /// The game tree starting at the offset isn't closed.
+    Unclosed(usize),
+    /// A parenthesis closes a game tree which wasn't opened.
+    UnexpectedClose(usize),
+}
+
+impl Display for CollectionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CollectionError::Unclosed(offset) => {
+                write!(f, "the game tree at byte {offset} isn't closed")
+            }
+            CollectionError::UnexpectedClose(offset) => {
+                write!(f, "unexpected `)` at byte {offset}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CollectionError {}
+
+/// What the import of a SGF does with a move of the record which can't be played, like a
+/// recording error in an old game.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum IllegalMovePolicy {
+    /// The move is dropped with a warning and the rest of the record is played.
+    Skip,
+    /// The stone is put with [`Game::force_play`] and a warning, the ko, the super ko, the
+    /// suicide and the occupied points aren't checked. A move outside the goban is dropped.
+    ForcePlace,
+    /// The import fails.
+    #[default]
+    Abort,
+}
+
+/// Options of [`Game::from_sgf_with`], strict by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SgfImportOptions {
+    pub on_illegal: IllegalMovePolicy,
+}
+
+/// A move of the record which couldn't be played normally.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SgfImportWarning {
+    /// Number of the move in the record, counted from 1 like the SGF nodes.
+    pub move_number: usize,
+    pub played: Move,
+    pub error: PlayError,
+}
+
+impl Display for SgfImportWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "move {} {:?}: {}", self.move_number, self.played, self.error)
+    }
+}
+
+/// The text of a SGF read from bytes which wasn't decoded exactly.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SgfCharsetWarning {
+    /// The charset of the `CA` property isn't supported, the text was read as UTF-8. Without
+    /// the `sgf-encodings` feature only UTF-8 is supported.
+    Unsupported(String),
+    /// Some bytes aren't valid in the charset, they were replaced by `U+FFFD`.
+    Malformed(String),
+}
+
+impl Display for SgfCharsetWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SgfCharsetWarning::Unsupported(charset) => {
+                write!(f, "the charset {charset} isn't supported, the SGF was read as UTF-8")
+            }
+            SgfCharsetWarning::Malformed(charset) => {
+                write!(f, "the SGF has bytes which aren't valid in {charset}")
+            }
+        }
+    }
+}
+
+/// Decodes a SGF file with the charset of its `CA` property, UTF-8 without it.
+///
+/// The other charsets than UTF-8, like GB2312, Shift_JIS or EUC-KR in the old game records,
+/// need the `sgf-encodings` feature. An unsupported charset or invalid bytes give the text
+/// with the invalid bytes replaced and a warning.
+pub fn decode_sgf(bytes: &[u8]) -> (String, Option<SgfCharsetWarning>) {
+    let charset = sniff_charset(bytes).unwrap_or("UTF-8");
+    match decode_as(charset, bytes) {
+        Some((text, false)) => (text, None),
+        Some((text, true)) => (text, Some(SgfCharsetWarning::Malformed(charset.to_owned()))),
+        None => (
+            String::from_utf8_lossy(bytes).into_owned(),
+            Some(SgfCharsetWarning::Unsupported(charset.to_owned())),
+        ),
+    }
+}
+
+/// Value of the first `CA` property. The property values of the SGF syntax are ASCII in all
+/// the charsets, so it's read before the decoding.
+fn sniff_charset(bytes: &[u8]) -> Option<&str> {
+    let start = (0..bytes.len()).find(|&i| {
+        bytes[i..].starts_with(b"CA[") && (i == 0 || !bytes[i - 1].is_ascii_uppercase())
+    })? + 3;
+    let len = bytes[start..].iter().position(|&b| b == b']')?;
+    std::str::from_utf8(&bytes[start..start + len])
+        .ok()
+        .map(str::trim)
+}
+
+/// Decodes the bytes in the charset, with `true` if some bytes were invalid. `None` if the
+/// charset isn't supported.
+#[cfg(feature = "sgf-encodings")]
+fn decode_as(charset: &str, bytes: &[u8]) -> Option<(String, bool)> {
+    let encoding = encoding_rs::Encoding::for_label(charset.as_bytes())?;
+    let (text, _, malformed) = encoding.decode(bytes);
+    Some((text.into_owned(), malformed))
+}
+
+#[cfg(not(feature = "sgf-encodings"))]
+fn decode_as(charset: &str, bytes: &[u8]) -> Option<(String, bool)> {
+    if !["utf-8", "utf8"].contains(&charset.to_ascii_lowercase().as_str()) {
+        return None;
+    }
+    Some(match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_owned(), false),
+        Err(_) => (String::from_utf8_lossy(bytes).into_owned(), true),
+    })
+}
+
+/// The game trees of a SGF file, parsed when accessed.
+#[derive(Clone, Debug)]
+pub struct SgfCollection<'a> {
+    input: &'a str,
+    /// Bytes of each game tree in the input, parentheses included.
+    trees: Vec<Range<usize>>,
+}
+
+impl<'a> SgfCollection<'a> {
+    /// Finds the game trees of the input without parsing them. The text between the game
+    /// trees is ignored.
+    ///
+    /// # Errors
+    /// If the parentheses of the game trees, outside the property values, don't match.
+    pub fn parse(input: &'a str) -> Result<Self, CollectionError> {
+        let mut trees = vec![];
+        let mut depth = 0usize;
+        let mut start = 0;
+        let mut in_value = false;
+        let mut escaped = false;
+        for (offset, byte) in input.bytes().enumerate() {
+            if in_value {
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b']' => in_value = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match byte {
+                b'[' if depth > 0 => in_value = true,
+                b'(' => {
+                    if depth == 0 {
+                        start = offset;
+                    }
+                    depth += 1;
+                }
+                b')' if depth == 0 => return Err(CollectionError::UnexpectedClose(offset)),
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        trees.push(start..offset + 1);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if depth > 0 {
+            return Err(CollectionError::Unclosed(start));
+        }
+        Ok(SgfCollection { input, trees })
+    }
+
+    /// Number of games.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.trees.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.trees.is_empty()
+    }
+
+    /// The text of the game tree, None if there are fewer games.
+    pub fn source(&self, index: usize) -> Option<&'a str> {
+        self.trees
+            .get(index)
+            .map(|range| &self.input[range.clone()])
+    }
+
+    /// Parses the game tree, None if there are fewer games.
+    pub fn get(&self, index: usize) -> Option<Result<GameTree, SgfError>> {
+        self.source(index).map(sgf_parser::parse)
+    }
+
+    /// Loads the game like [`Game::from_sgf`], None if there are fewer games.
+    pub fn game(&self, index: usize) -> Option<Result<Game, String>> {
+        self.source(index).map(Game::from_sgf)
+    }
+
+    /// Parses the game trees one by one.
+    pub fn iter(&self) -> impl Iterator<Item = Result<GameTree, SgfError>> + '_ {
+        (0..self.len()).filter_map(move |index| self.get(index))
+    }
+
+    /// Loads the games one by one.
+    pub fn games(&self) -> impl Iterator<Item = Result<Game, String>> + '_ {
+        (0..self.len()).filter_map(move |index| self.game(index))
+    }
+}
+
+/// Writes the game trees back to back, one by line.
+pub fn write_collection(trees: &[GameTree]) -> String {
+    trees
+        .iter()
+        .map(|tree| {
+            let mut sgf: String = tree.into();
+            sgf.push('\n');
+            sgf
+        })
+        .collect()
+}
@@ -0,0 +1,58 @@
+//! Proptest strategies to generate random valid coordinates, moves, games and gobans.
+//!
+//! The positions are generated by playing random legal games, so they never contain a chain
+//! without liberties. The random choices are proptest indexes, so failing cases shrink to
+//! shorter games.
+
+use std::ops::RangeInclusive;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+use crate::pieces::goban::Goban;
+use crate::pieces::util::coord::{Coord, Size};
+use crate::rules::game::Game;
+use crate::rules::{GobanSizes, Move, Rule};
+
+/// Strategy for a coordinate valid in a goban of the given size.
+pub fn coord((height, width): Size) -> impl Strategy<Value = Coord> {
+    (0..height, 0..width)
+}
+
+/// Strategy for a sequence of at most `max_moves` legal moves, passes included,
+/// starting from an empty goban of the given size.
+pub fn legal_moves(size: Size, rule: Rule, max_moves: usize) -> impl Strategy<Value = Vec<Move>> {
+    vec(any::<Index>(), 0..=max_moves)
+        .prop_map(move |picks| play_picks(size, rule, &picks).moves().collect())
+}
+
+/// Strategy for a game on a square goban with a size in the range, after at most `max_moves`
+/// random legal moves.
+pub fn game(
+    sizes: RangeInclusive<u8>,
+    rule: Rule,
+    max_moves: usize,
+) -> impl Strategy<Value = Game> {
+    (sizes, vec(any::<Index>(), 0..=max_moves))
+        .prop_map(move |(size, picks)| play_picks((size, size), rule, &picks))
+}
+
+/// Strategy for a goban on a square goban with a size in the range, built from a random game.
+pub fn goban(sizes: RangeInclusive<u8>, rule: Rule, max_moves: usize) -> impl Strategy<Value = Goban> {
+    game(sizes, rule, max_moves).prop_map(|game| game.goban().clone())
+}
+
+/// Plays a move for each index, the index is used to pick the move between the legals and pass.
+fn play_picks(size: Size, rule: Rule, picks: &[Index]) -> Game {
+    let mut game = Game::new(GobanSizes::Custom(size.0 as usize, size.1 as usize), rule);
+    for pick in picks {
+        if game.is_over() {
+            break;
+        }
+        let mut moves: Vec<Move> = game.legals().map(Move::from).collect();
+        moves.push(Move::Pass);
+        game.play(*pick.get(&moves));
+    }
+    game
+}
@@ -0,0 +1,409 @@
+//! Binary files of training samples for neural networks: the input planes, the index of the
+//! move played and the outcome of the game.
+//!
+//! # Layout
+//! All the integers are little endian. The file starts with a header of 12 bytes:
+//!
+//! | Bytes | Content |
+//! |-------|---------|
+//! | 0..4  | The magic `GOTS` |
+//! | 4..6  | The version of the format, [`FORMAT_VERSION`] |
+//! | 6     | The height of the goban |
+//! | 7     | The width of the goban |
+//! | 8..10 | The number of planes |
+//! | 10    | The encoding of the planes, 0 for `f32` and 1 for bits |
+//! | 11    | Reserved, 0 |
+//!
+//! Then the samples follow, all of the same size:
+//! - The planes one after the other, each of them is the goban in row major order. A `f32` takes
+//!   4 bytes, the bits are packed 8 by byte from the lowest bit and the last byte is padded
+//!   with zeros.
+//! - The policy index on 2 bytes, `row * width + column` or the area of the goban for a pass.
+//! - The outcome on 1 byte for the player to move: 1 for a win, -1 for a loss, 0 for a draw or
+//!   an unknown result.
+//!
+//! # Example
+//! ```
+//! use goban::training::{PlaneEncoding, SampleConfig, SampleReader, SampleWriter};
+//!
+//! let config = SampleConfig {
+//!     size: (9, 9),
+//!     planes: 2,
+//!     encoding: PlaneEncoding::Bits,
+//! };
+//! let mut writer = SampleWriter::from_writer(vec![], config).unwrap();
+//! let mut planes = vec![0.; 2 * 81];
+//! planes[40] = 1.;
+//! writer.write(&planes, 40, 1).unwrap();
+//! let bytes = writer.finish().unwrap();
+//!
+//! let mut reader = SampleReader::from_reader(bytes.as_slice()).unwrap();
+//! let sample = reader.next_sample().unwrap().unwrap();
+//! assert_eq!(sample.planes, planes);
+//! assert_eq!((sample.policy_index, sample.outcome), (40, 1));
+//! assert!(reader.next_sample().unwrap().is_none());
+//! ```
+
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::pieces::util::coord::{two_to_1dim, Size};
+use crate::rules::game::Game;
+use crate::rules::{EndGame, Move};
+use crate::sgf::SgfCollection;
+
+/// Version of the layout written in the header.
+pub const FORMAT_VERSION: u16 = 1;
+/// Number of planes of [`game_planes`].
+pub const GAME_PLANES: u16 = 3;
+
+const MAGIC: &[u8; 4] = b"GOTS";
+const HEADER_LEN: usize = 12;
+
+/// Encoding of the values of the planes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PlaneEncoding {
+    /// Each value is a `f32`.
+    Float,
+    /// Each value is a bit, the values other than 0 are written as 1.
+    Bits,
+}
+
+/// Shape of the samples of a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SampleConfig {
+    pub size: Size,
+    /// Number of planes by sample.
+    pub planes: u16,
+    pub encoding: PlaneEncoding,
+}
+
+impl SampleConfig {
+    /// Number of values of the planes of a sample.
+    pub fn values(&self) -> usize {
+        self.planes as usize * self.size.0 as usize * self.size.1 as usize
+    }
+
+    /// Number of bytes of a sample in the file.
+    pub fn record_len(&self) -> usize {
+        let planes = match self.encoding {
+            PlaneEncoding::Float => 4 * self.values(),
+            PlaneEncoding::Bits => self.values().div_ceil(8),
+        };
+        planes + 3
+    }
+}
+
+/// A sample read from a file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+    pub planes: Vec<f32>,
+    pub policy_index: u16,
+    pub outcome: i8,
+}
+
+/// Errors when writing or reading training samples.
+#[derive(Debug)]
+pub enum TrainingError {
+    Io(io::Error),
+    /// The file doesn't start with the magic of the format.
+    BadMagic,
+    /// The file was written by another version of the format.
+    UnsupportedVersion(u16),
+    /// The header has an unknown encoding of the planes.
+    BadEncoding(u8),
+    /// The file ends in the middle of the header or of a sample.
+    Truncated,
+    /// The planes given don't have the number of values of the configuration.
+    PlaneLength { expected: usize, found: usize },
+    /// A game of the collection can't be loaded, with its index.
+    Sgf { game: usize, error: String },
+}
+
+impl Display for TrainingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TrainingError::Io(error) => write!(f, "{error}"),
+            TrainingError::BadMagic => write!(f, "not a file of training samples"),
+            TrainingError::UnsupportedVersion(version) => {
+                write!(f, "unsupported version {version} of the training samples")
+            }
+            TrainingError::BadEncoding(encoding) => {
+                write!(f, "unknown encoding {encoding} of the planes")
+            }
+            TrainingError::Truncated => write!(f, "the file of training samples is truncated"),
+            TrainingError::PlaneLength { expected, found } => {
+                write!(f, "expected {expected} values in the planes, found {found}")
+            }
+            TrainingError::Sgf { game, error } => write!(f, "game {game}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for TrainingError {}
+
+impl From<io::Error> for TrainingError {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::UnexpectedEof => TrainingError::Truncated,
+            _ => TrainingError::Io(error),
+        }
+    }
+}
+
+/// Appends samples to a file of training samples, see the [module](self) for the layout.
+#[derive(Debug)]
+pub struct SampleWriter<W: Write = BufWriter<File>> {
+    writer: W,
+    config: SampleConfig,
+    buffer: Vec<u8>,
+}
+
+impl SampleWriter {
+    /// Creates the file, replacing an existing one, and writes the header.
+    ///
+    /// # Errors
+    /// If the file can't be created or written.
+    pub fn new(path: impl AsRef<Path>, config: SampleConfig) -> Result<Self, TrainingError> {
+        SampleWriter::from_writer(BufWriter::new(File::create(path)?), config)
+    }
+}
+
+impl<W: Write> SampleWriter<W> {
+    /// Writes the header to the writer.
+    ///
+    /// # Errors
+    /// If the writer fails.
+    pub fn from_writer(mut writer: W, config: SampleConfig) -> Result<Self, TrainingError> {
+        let mut header = [0; HEADER_LEN];
+        header[0..4].copy_from_slice(MAGIC);
+        header[4..6].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+        header[6] = config.size.0;
+        header[7] = config.size.1;
+        header[8..10].copy_from_slice(&config.planes.to_le_bytes());
+        header[10] = match config.encoding {
+            PlaneEncoding::Float => 0,
+            PlaneEncoding::Bits => 1,
+        };
+        writer.write_all(&header)?;
+        Ok(SampleWriter {
+            writer,
+            config,
+            buffer: Vec::with_capacity(config.record_len()),
+        })
+    }
+
+    pub fn config(&self) -> SampleConfig {
+        self.config
+    }
+
+    /// Appends a sample.
+    ///
+    /// # Errors
+    /// If the planes don't have the number of values of the configuration or the writer fails.
+    pub fn write(
+        &mut self,
+        planes: &[f32],
+        policy_index: u16,
+        outcome: i8,
+    ) -> Result<(), TrainingError> {
+        let expected = self.config.values();
+        if planes.len() != expected {
+            return Err(TrainingError::PlaneLength {
+                expected,
+                found: planes.len(),
+            });
+        }
+        self.buffer.clear();
+        match self.config.encoding {
+            PlaneEncoding::Float => {
+                for value in planes {
+                    self.buffer.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            PlaneEncoding::Bits => {
+                for chunk in planes.chunks(8) {
+                    let byte = chunk
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, &value)| value != 0.)
+                        .fold(0u8, |byte, (bit, _)| byte | 1 << bit);
+                    self.buffer.push(byte);
+                }
+            }
+        }
+        self.buffer.extend_from_slice(&policy_index.to_le_bytes());
+        self.buffer.extend_from_slice(&outcome.to_le_bytes());
+        self.writer.write_all(&self.buffer)?;
+        Ok(())
+    }
+
+    /// Flushes the samples and returns the writer.
+    ///
+    /// # Errors
+    /// If the writer fails.
+    pub fn finish(mut self) -> Result<W, TrainingError> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads the samples of a file written by [`SampleWriter`].
+#[derive(Debug)]
+pub struct SampleReader<R: Read = BufReader<File>> {
+    reader: R,
+    config: SampleConfig,
+    buffer: Vec<u8>,
+}
+
+impl SampleReader {
+    /// Opens the file and reads the header.
+    ///
+    /// # Errors
+    /// If the file can't be read or the header is invalid.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, TrainingError> {
+        SampleReader::from_reader(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> SampleReader<R> {
+    /// Reads the header from the reader.
+    ///
+    /// # Errors
+    /// If the reader fails or the header is invalid.
+    pub fn from_reader(mut reader: R) -> Result<Self, TrainingError> {
+        let mut header = [0; HEADER_LEN];
+        reader.read_exact(&mut header)?;
+        if &header[0..4] != MAGIC {
+            return Err(TrainingError::BadMagic);
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != FORMAT_VERSION {
+            return Err(TrainingError::UnsupportedVersion(version));
+        }
+        let encoding = match header[10] {
+            0 => PlaneEncoding::Float,
+            1 => PlaneEncoding::Bits,
+            encoding => return Err(TrainingError::BadEncoding(encoding)),
+        };
+        let config = SampleConfig {
+            size: (header[6], header[7]),
+            planes: u16::from_le_bytes([header[8], header[9]]),
+            encoding,
+        };
+        Ok(SampleReader {
+            reader,
+            config,
+            buffer: vec![0; config.record_len()],
+        })
+    }
+
+    pub fn config(&self) -> SampleConfig {
+        self.config
+    }
+
+    /// Reads the next sample, None at the end of the file.
+    ///
+    /// # Errors
+    /// If the reader fails or the file ends in the middle of a sample.
+    pub fn next_sample(&mut self) -> Result<Option<Sample>, TrainingError> {
+        let mut read = 0;
+        while read < self.buffer.len() {
+            match self.reader.read(&mut self.buffer[read..]) {
+                Ok(0) if read == 0 => return Ok(None),
+                Ok(0) => return Err(TrainingError::Truncated),
+                Ok(n) => read += n,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+        let values = self.config.values();
+        let (planes, tail) = self.buffer.split_at(self.buffer.len() - 3);
+        let planes = match self.config.encoding {
+            PlaneEncoding::Float => planes
+                .chunks_exact(4)
+                .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect(),
+            PlaneEncoding::Bits => (0..values)
+                .map(|index| f32::from((planes[index / 8] >> (index % 8)) & 1))
+                .collect(),
+        };
+        Ok(Some(Sample {
+            planes,
+            policy_index: u16::from_le_bytes([tail[0], tail[1]]),
+            outcome: tail[2] as i8,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for SampleReader<R> {
+    type Item = Result<Sample, TrainingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_sample().transpose()
+    }
+}
+
+/// The [`GAME_PLANES`] planes of the position: the stones of the player to move, the stones of
+/// the opponent and a plane of ones, which shows the edges of the goban to convolutions.
+pub fn game_planes(game: &Game) -> Vec<f32> {
+    let size = game.size();
+    let area = size.0 as usize * size.1 as usize;
+    let mut planes = vec![0.; GAME_PLANES as usize * area];
+    for stone in game.goban().get_stones() {
+        let plane = if stone.color == game.turn() { 0 } else { 1 };
+        planes[plane * area + two_to_1dim(size, stone.coord)] = 1.;
+    }
+    planes[2 * area..].fill(1.);
+    planes
+}
+
+/// Writes a sample by move of the games of the collection, the planes are the [`game_planes`]
+/// of the position before the move. The resignations and the games of another size than the
+/// configuration are skipped. Returns the number of samples written.
+///
+/// # Errors
+/// If a game can't be loaded, the configuration doesn't have [`GAME_PLANES`] planes or the
+/// writer fails.
+pub fn write_collection_samples<W: Write>(
+    collection: &SgfCollection,
+    writer: &mut SampleWriter<W>,
+) -> Result<usize, TrainingError> {
+    let config = writer.config();
+    let mut written = 0;
+    for (index, game) in collection.games().enumerate() {
+        let mut game = game.map_err(|error| TrainingError::Sgf { game: index, error })?;
+        if game.size() != config.size {
+            continue;
+        }
+        let winner = match game.outcome() {
+            Some(EndGame::WinnerByScore(color, _))
+            | Some(EndGame::WinnerByResign(color))
+            | Some(EndGame::WinnerByTime(color))
+            | Some(EndGame::WinnerByForfeit(color)) => Some(color),
+            Some(EndGame::Draw) | None => None,
+        };
+        let moves: Vec<Move> = game.moves().collect();
+        game.jump_to(0);
+        for (number, played) in moves.into_iter().enumerate() {
+            let policy_index = match played {
+                Move::Play(x, y) => Some(two_to_1dim(config.size, (x, y))),
+                Move::Pass => Some(config.size.0 as usize * config.size.1 as usize),
+                Move::Resign(_) => None,
+            };
+            if let Some(policy_index) = policy_index {
+                let outcome = match winner {
+                    Some(color) if color == game.turn() => 1,
+                    Some(_) => -1,
+                    None => 0,
+                };
+                writer.write(&game_planes(&game), policy_index as u16, outcome)?;
+                written += 1;
+            }
+            game.jump_to(number + 1);
+        }
+    }
+    Ok(written)
+}
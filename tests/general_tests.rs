@@ -1,17 +1,44 @@
 #[cfg(test)]
 mod tests {
-    use std::collections::HashSet;
+    use std::cmp::Reverse;
+    use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
     use std::mem;
+    use std::sync::Arc;
     use rand::prelude::IndexedRandom;
     use rand::rng;
 
-    use goban::pieces::goban::Goban;
+    use goban::analysis::{
+        augment, captured_stones, classify_eyespace, escapes_atari, estimate_score, gives_atari,
+        ko_threats, largest_open_regions, local_moves, move_category, near_last_moves,
+        ordered_moves, proximity_bonus, standard_opening_points, weighted_moves, ConcurrentTranspositionTable,
+        EyespaceStatus, GamePool, MoveCategory, OpeningKind, OrderingWeights, SymmetryTable,
+        ThreatSize, LOCAL_RADIUS,
+    };
+    use goban::pieces::goban::{BoardIdx, ChainInfo, Goban, GobanError, InvariantViolation};
     use goban::pieces::stones::{Color, Point, Stone, EMPTY};
-    use goban::pieces::zobrist::index_zobrist;
+    use goban::pieces::symmetry::Symmetry;
+    use goban::pieces::territory::TerritoryCount;
+    use goban::pieces::zobrist::{index_zobrist, ZobristTable, ZobristTableError};
+    use goban::pieces::util::coord::{
+        coord_to_sgf, sgf_to_coord, two_to_1dim, Coord, SgfCoordError,
+    };
+    use goban::rules::annotation::{Annotation, Markup, MoveQuality};
+    use goban::rules::events::GameEvent;
+    use goban::sgf::{IllegalMovePolicy, SgfCharsetWarning, SgfImportOptions, SgfImportWarning};
+    use goban::rules::psgo::{Crop, PsgoOptions};
+    use goban::rules::snapshot::GameSnapshot;
     use goban::rules::game::Game;
-    use goban::rules::{EndGame, GobanSizes, Move, PlayError};
-    use goban::rules::{CHINESE, JAPANESE};
+    use goban::rules::{
+        EncodingError, EndGame, GobanSizes, IllegalReason, IllegalRules, Move, PlayError, Rule,
+    };
+    use goban::rules::{MoveListError, ScoringError, SetupError};
+    use goban::rules::{AGA, CHINESE, HANDICAP_KOMI, ING, JAPANESE};
     use goban::rules::Move::Play;
+    use goban::sgf::{write_collection, CollectionError, SgfCollection};
+    use goban::training::{
+        game_planes, write_collection_samples, PlaneEncoding, Sample, SampleConfig, SampleReader,
+        SampleWriter, TrainingError, GAME_PLANES,
+    };
     use goban::rules::PlayError::Suicide;
 
     #[test]
@@ -38,6 +65,180 @@ mod tests {
         assert_eq!(g, g2)
     }
 
+    #[test]
+    fn goban_transaction_rollback() {
+        let mut goban = Goban::new((9, 9));
+        goban.push_many(&[(2, 2), (2, 3)], Color::White);
+        goban.push_many(&[(1, 2), (1, 3), (2, 1), (3, 2), (3, 3)], Color::Black);
+        let original = goban.clone();
+
+        goban.begin_transaction().unwrap();
+        assert_eq!(
+            goban.begin_transaction(),
+            Err(GobanError::TransactionAlreadyActive)
+        );
+        goban.push((2, 4), Color::Black);
+        let white_chain = goban
+            .get_connected_groups_idx((2, 4))
+            .into_iter()
+            .find(|&idx| goban.chain_stones(idx).next().unwrap().color == Color::White)
+            .unwrap();
+        goban.remove_chain(white_chain);
+        goban.push((2, 2), Color::Black);
+        assert_eq!(goban.get_color((2, 3)), EMPTY);
+        assert_ne!(goban, original);
+
+        goban.rollback().unwrap();
+        assert!(!goban.in_transaction());
+        assert_eq!(goban, original);
+        assert_eq!(goban.zobrist_hash(), original.zobrist_hash());
+        assert_eq!(goban.to_vec(), original.to_vec());
+        assert_eq!(
+            goban.get_stones().collect::<Vec<_>>(),
+            original.get_stones().collect::<Vec<_>>()
+        );
+        // Liberties are restored too, the white chain is in atari again.
+        let white_chain = goban.chains().find(|chain| chain.color == Color::White).unwrap();
+        assert!(white_chain.is_atari());
+        assert_eq!(goban.rollback(), Err(GobanError::NoActiveTransaction));
+    }
+
+    #[test]
+    fn goban_transaction_commit() {
+        let mut goban = Goban::new((9, 9));
+        goban.begin_transaction().unwrap();
+        goban.push((4, 4), Color::Black);
+        goban.commit().unwrap();
+        assert_eq!(goban.commit(), Err(GobanError::NoActiveTransaction));
+        assert_eq!(goban.get_color((4, 4)), Some(Color::Black));
+        assert_eq!(goban.zobrist_hash(), index_zobrist(4 * 9 + 4, Color::Black));
+    }
+
+    #[test]
+    fn goban_transaction_rollback_reuses_chains() {
+        let mut goban = Goban::new((9, 9));
+        goban.push((4, 4), Color::Black);
+        let original = goban.clone();
+        for _ in 0..=u16::MAX as usize {
+            goban.begin_transaction().unwrap();
+            goban.push((4, 5), Color::Black);
+            goban.rollback().unwrap();
+        }
+        assert_eq!(goban, original);
+        assert_eq!(goban.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn goban_from_iterator() {
+        let empty: Goban = std::iter::empty::<Stone>().collect();
+        assert_eq!(empty, Goban::new((19, 19)));
+        assert_eq!(empty.zobrist_hash(), 0);
+
+        let stones = [
+            ((2, 2), Color::Black),
+            ((2, 3), Color::Black),
+            ((2, 2), Color::White),
+        ]
+        .map(Stone::from);
+        let goban = Goban::from_stones((9, 9), stones);
+        assert_eq!(goban.get_color((2, 2)), Some(Color::White));
+        assert_eq!(goban.number_of_stones(), (1, 1));
+        assert_eq!(
+            goban.zobrist_hash(),
+            index_zobrist(2 * 9 + 2, Color::White) ^ index_zobrist(2 * 9 + 3, Color::Black)
+        );
+
+        let mut g = Game::new(GobanSizes::Nine, CHINESE);
+        for m in [(2, 2), (2, 3), (3, 3), (3, 2), (4, 2), (1, 2), (3, 1), (4, 4)] {
+            g.play(Move::Play(m.0, m.1));
+        }
+        let round_trip = Goban::from_stones(g.size(), g.get_stones());
+        assert_eq!(&round_trip, g.goban());
+        let mut extended = Goban::new((9, 9));
+        extended.extend(g.get_stones());
+        assert_eq!(&extended, g.goban());
+    }
+
+    #[test]
+    fn goban_eq_slice_row_major() {
+        let mut goban = Goban::new((3, 3));
+        goban.push((0, 1), Color::Black);
+        goban.push((1, 2), Color::White);
+        let b = Some(Color::Black);
+        let w = Some(Color::White);
+        assert_eq!(goban, &[EMPTY, b, EMPTY, EMPTY, EMPTY, w, EMPTY, EMPTY, EMPTY][..]);
+        assert_eq!(&[EMPTY, b, EMPTY, EMPTY, EMPTY, w, EMPTY, EMPTY, EMPTY][..], goban);
+        // Column major order must not match.
+        assert_ne!(goban, &[EMPTY, EMPTY, EMPTY, b, EMPTY, EMPTY, EMPTY, w, EMPTY][..]);
+        // Length mismatch is just not equal.
+        assert_ne!(goban, &[EMPTY, b, EMPTY][..]);
+        assert_ne!(goban, &[EMPTY; 10][..]);
+    }
+
+    #[test]
+    fn game_invariants() {
+        let mut g = Game::new(GobanSizes::Nine, CHINESE);
+        for _ in 0..120 {
+            if g.is_over() {
+                break;
+            }
+            let m = g.legals().map(Move::from).collect::<Vec<_>>().choose(&mut rng()).copied();
+            g.play(m.unwrap_or(Move::Pass));
+            assert_eq!(g.check_invariants(), Ok(()));
+        }
+
+        let mut g = Game::new(GobanSizes::Nine, CHINESE);
+        g.put_stone((0, 1), Color::White);
+        g.put_stone((1, 0), Color::White);
+        g.put_stone((0, 0), Color::Black);
+        assert_eq!(g.goban().check_invariants(), Ok(()));
+        let violation = g.check_invariants().unwrap_err();
+        assert!(matches!(
+            violation,
+            InvariantViolation::ChainWithoutLiberties { coord: (0, 0), .. }
+        ));
+        assert!(violation.to_string().contains("(0, 0)"));
+    }
+
+    #[test]
+    fn swap_colors() {
+        let mut g = Game::new(GobanSizes::Nine, JAPANESE);
+        for m in [(0, 1), (0, 0), (5, 5), (1, 1), (1, 0), (6, 6), (4, 4)] {
+            g.try_play(Move::Play(m.0, m.1)).unwrap();
+        }
+        let goban = g.goban().clone();
+        let swapped = goban.swap_colors();
+        let expected = Goban::from_stones(
+            goban.size(),
+            goban.get_stones().map(|s| Stone::from((s.coord, !s.color))),
+        );
+        assert_eq!(swapped, expected);
+        assert_eq!(swapped.zobrist_hash(), expected.zobrist_hash());
+        assert_eq!(swapped.number_of_stones(), (2, 4));
+        assert_eq!(swapped.check_invariants(), Ok(()));
+
+        let twice = swapped.swap_colors();
+        assert_eq!(twice, goban);
+        assert_eq!(twice.zobrist_hash(), goban.zobrist_hash());
+
+        let mut in_transaction = goban.clone();
+        in_transaction.begin_transaction().unwrap();
+        in_transaction.swap_colors_mut();
+        in_transaction.rollback().unwrap();
+        assert_eq!(in_transaction, goban);
+
+        let swapped_game = g.swap_colors();
+        assert_eq!(g.prisoners(), (1, 0));
+        assert_eq!(swapped_game.prisoners(), (0, 1));
+        assert_eq!(swapped_game.turn(), !g.turn());
+        assert_eq!(swapped_game.goban(), &swapped);
+        assert_eq!(swapped_game.check_invariants(), Ok(()));
+        let mut undone = swapped_game.clone();
+        undone.undo();
+        assert_eq!(undone.get_color((4, 4)), EMPTY);
+        assert_eq!(undone.turn(), Color::White);
+    }
+
     #[test]
     fn passes() {
         let mut g = Game::new(GobanSizes::Nine, CHINESE);
@@ -68,6 +269,134 @@ mod tests {
         assert_eq!(expected, vec)
     }
 
+    #[test]
+    fn deterministic_iteration_order() {
+        let mut goban = Goban::new((9, 9));
+        // Pushed in a scrambled order so the storage order differs from the board order.
+        goban.push((6, 6), Color::White);
+        goban.push((0, 5), Color::Black);
+        goban.push((6, 5), Color::White);
+        goban.push((0, 4), Color::Black);
+        goban.push((3, 0), Color::White);
+        goban.push((1, 0), Color::Black);
+
+        let coords: Vec<_> = goban.get_stones().map(|s| s.coord).collect();
+        assert_eq!(coords, vec![(0, 4), (0, 5), (1, 0), (3, 0), (6, 5), (6, 6)]);
+        let whites: Vec<_> = goban
+            .get_stones_by_color(Some(Color::White))
+            .map(|p| p.coord)
+            .collect();
+        assert_eq!(whites, vec![(3, 0), (6, 5), (6, 6)]);
+
+        let origins: Vec<_> = goban.chains().map(|chain| (chain.origin, chain.color)).collect();
+        assert_eq!(
+            origins,
+            vec![
+                (4, Color::Black),
+                (9, Color::Black),
+                (27, Color::White),
+                (59, Color::White)
+            ]
+        );
+        let groups: Vec<_> = goban
+            .get_groups()
+            .map(|(idx, _)| goban.chain_stones(idx).map(|s| s.coord).collect::<Vec<_>>())
+            .collect();
+        assert_eq!(
+            groups,
+            vec![vec![(0, 4), (0, 5)], vec![(1, 0)], vec![(3, 0)], vec![(6, 5), (6, 6)]]
+        );
+
+        let empty: Vec<_> = goban.get_empty_coords().collect();
+        let mut sorted = empty.clone();
+        sorted.sort();
+        assert_eq!(empty, sorted);
+        assert_eq!(empty.len(), 81 - 6);
+        assert_eq!(goban.get_empty_idx().count(), 81 - 6);
+
+        let mut game = Game::new(GobanSizes::Nine, CHINESE);
+        game.play(Move::Play(4, 4));
+        let legals: Vec<_> = game.legals().collect();
+        let mut sorted = legals.clone();
+        sorted.sort();
+        assert_eq!(legals, sorted);
+    }
+
+    #[test]
+    fn ordering_is_row_major() {
+        let mut stones = [
+            Stone::from(((2, 0), Color::White)),
+            Stone::from(((0, 3), Color::Black)),
+            Stone::from(((0, 3), Color::White)),
+            Stone::from(((1, 8), Color::Black)),
+            Stone::from(((0, 1), Color::White)),
+        ];
+        stones.sort();
+        let sorted: Vec<_> = stones.iter().map(|s| (s.coord, s.color)).collect();
+        assert_eq!(
+            sorted,
+            vec![
+                ((0, 1), Color::White),
+                ((0, 3), Color::Black),
+                ((0, 3), Color::White),
+                ((1, 8), Color::Black),
+                ((2, 0), Color::White)
+            ]
+        );
+        assert!(Color::Black < Color::White);
+
+        let mut moves = [Move::Play(3, 0), Move::Play(0, 7), Move::Pass, Move::Play(0, 2)];
+        moves.sort();
+        assert_eq!(
+            moves,
+            [Move::Pass, Move::Play(0, 2), Move::Play(0, 7), Move::Play(3, 0)]
+        );
+
+        let goban = Goban::from_stones((9, 9), stones.iter().copied());
+        let expected: Vec<_> = goban.get_stones().map(|s| s.coord).collect();
+        let coords: BTreeSet<_> = expected.iter().rev().copied().collect();
+        assert_eq!(coords.into_iter().collect::<Vec<_>>(), expected);
+        let by_color: BTreeMap<_, _> = goban.get_stones().map(|s| (s.coord, s.color)).collect();
+        assert_eq!(by_color.first_key_value(), Some((&(0, 1), &Color::White)));
+    }
+
+    #[test]
+    fn group_from_point_is_ordered() {
+        let mut goban = Goban::new((9, 9));
+        goban.push_many(&[(4, 4), (3, 4), (4, 3), (5, 4), (4, 5)], Color::Black);
+        let group = goban.get_group_from_point(Point {
+            coord: (5, 4),
+            color: Some(Color::Black),
+        });
+        let coords: Vec<_> = group.iter().map(|p| p.coord).collect();
+        assert_eq!(coords, vec![(3, 4), (4, 3), (4, 4), (4, 5), (5, 4)]);
+
+        let empty_regions = goban.get_chains_from_stones(goban.get_empty_coords().map(|coord| {
+            Point {
+                coord,
+                color: EMPTY,
+            }
+        }));
+        assert_eq!(empty_regions.len(), 1);
+        assert_eq!(empty_regions[0].len(), 81 - 5);
+        assert_eq!(empty_regions[0].first().unwrap().coord, (0, 0));
+    }
+
+    #[test]
+    fn territories_in_row_major_order() {
+        let mut goban = Goban::new((5, 5));
+        goban.push_many(&[(0, 2), (1, 2), (2, 2), (3, 2), (4, 2)], Color::Black);
+        goban.push_many(&[(0, 3), (1, 3), (2, 3), (3, 3), (4, 3)], Color::White);
+        let (black, white) = goban.get_territories();
+        let black: Vec<_> = black.map(|p| p.coord).collect();
+        let white: Vec<_> = white.map(|p| p.coord).collect();
+        assert_eq!(
+            black,
+            vec![(0, 0), (0, 1), (1, 0), (1, 1), (2, 0), (2, 1), (3, 0), (3, 1), (4, 0), (4, 1)]
+        );
+        assert_eq!(white, vec![(0, 4), (1, 4), (2, 4), (3, 4), (4, 4)]);
+    }
+
     #[test]
     fn some_plays() {
         let mut g = Game::new(GobanSizes::Nineteen, CHINESE);
@@ -475,7 +804,7 @@ mod tests {
         goban.push_stone(s);
         println!("{}", goban.pretty_string());
         let cl = goban.clone();
-        let x = cl.get_liberties(s.coord);
+        let x = cl.liberties_of_point(s.coord);
 
         x.for_each(|coord| {
             println!("{coord:?}");
@@ -487,7 +816,7 @@ mod tests {
 
         println!("{}", goban.pretty_string());
 
-        assert_eq!(goban.get_liberties(s.coord).count(), 0);
+        assert_eq!(goban.liberties_of_point(s.coord).count(), 0);
     }
 
     #[test]
@@ -625,7 +954,62 @@ mod tests {
             color: Color::Black,
         }));
         assert!(!game.legals().any(|m| m == (1, 2)));
-        assert_eq!(game.try_play(Move::Play(1, 2)).err(), Some(PlayError::Ko));
+        assert_eq!(game.try_play(Move::Play(1, 2)).err(), Some(PlayError::Ko((1, 2))));
+    }
+
+    #[test]
+    fn hash_history_ko_cycle() {
+        // Rule without any ko restriction so the ko can be retaken at once.
+        let rule = Rule {
+            flag_illegal: IllegalRules::SUICIDE,
+            ..JAPANESE
+        };
+        let mut game = Game::new(GobanSizes::Nine, rule);
+        for (x, y) in [(0, 1), (0, 2), (1, 0), (1, 1), (2, 1), (2, 2), (8, 8), (1, 3)] {
+            game.try_play(Move::Play(x, y)).unwrap();
+        }
+        assert_eq!(game.hash_history().len(), 8);
+        let before_ko = game.zobrist_hash();
+        assert_eq!(game.position_seen(before_ko), Some(8));
+
+        // Black takes the ko, white takes it back.
+        game.try_play(Move::Play(1, 2)).unwrap();
+        assert_eq!(game.get_color((1, 1)), EMPTY);
+        let after_take = game.zobrist_hash();
+        game.try_play(Move::Play(1, 1)).unwrap();
+        assert_eq!(game.get_color((1, 2)), EMPTY);
+        assert_eq!(game.zobrist_hash(), before_ko);
+        assert_eq!(game.position_seen(game.zobrist_hash()), Some(8));
+        assert_eq!(game.position_seen(after_take), Some(9));
+        assert_eq!(game.hash_history()[8], before_ko);
+
+        // Undo truncates the hash history with the moves.
+        assert_eq!(game.undo(), Some(Move::Play(1, 1)));
+        assert_eq!(game.zobrist_hash(), after_take);
+        assert_eq!(game.hash_history().len(), 9);
+        assert_eq!(game.moves().count(), 9);
+        assert_eq!(game.get_color((1, 1)), EMPTY);
+        assert_eq!(game.prisoners(), (1, 0));
+        assert_eq!(game.undo(), Some(Move::Play(1, 2)));
+        assert_eq!(game.hash_history().len(), 8);
+        assert_eq!(game.zobrist_hash(), before_ko);
+        assert_eq!(game.position_seen(after_take), None);
+        assert_eq!(game.prisoners(), (0, 0));
+        assert_eq!(game.turn(), Color::Black);
+    }
+
+    #[test]
+    fn undo_everything() {
+        let mut game = Game::new(GobanSizes::Nine, CHINESE);
+        let initial = game.goban().clone();
+        game.play(Move::Play(4, 4));
+        game.play(Move::Pass);
+        game.play(Move::Play(3, 3));
+        while game.undo().is_some() {}
+        assert_eq!(game.goban(), &initial);
+        assert_eq!(game.moves().count(), 0);
+        assert_eq!(game.turn(), Color::Black);
+        assert_eq!(game.undo(), None);
     }
 
     #[test]
@@ -645,7 +1029,7 @@ mod tests {
         ;
         )";
 
-        let mut game = Game::from_sgf(&sgf).unwrap();
+        let mut game = Game::from_sgf(sgf).unwrap();
         println!("{}", game.pretty_string());
 
         for &m in &[Play(6, 5), Play(6,3)] {
@@ -656,6 +1040,37 @@ mod tests {
         assert!(game.check_super_ko(Stone{coord: (6,4), color: Color::Black}))
     }
 
+    #[test]
+    fn forbidden_points_reasons() {
+        let mut setup = Game::setup((9, 9));
+        setup.rule(JAPANESE);
+        for point in [(0, 1), (1, 0), (2, 1), (8, 7), (7, 8)] {
+            setup.put(point, Color::Black);
+        }
+        for point in [(0, 2), (1, 1), (1, 3), (2, 2)] {
+            setup.put(point, Color::White);
+        }
+        let mut game = setup.finish().unwrap();
+        // Black takes the ko, white can't retake it and can't play in the corners.
+        game.play(Play(1, 2));
+        let forbidden: BTreeMap<Coord, IllegalReason> = game.forbidden_points().collect();
+        assert_eq!(forbidden.get(&(1, 1)), Some(&IllegalReason::Ko));
+        assert_eq!(forbidden.get(&(8, 8)), Some(&IllegalReason::Suicide));
+        assert_eq!(forbidden.get(&(0, 0)), Some(&IllegalReason::Suicide));
+        assert_eq!(forbidden.get(&(0, 1)), Some(&IllegalReason::Occupied));
+        assert_eq!(forbidden.len(), 12);
+        assert_eq!(forbidden.len() + game.legals().count(), 81);
+
+        let sgf = "(;GM[1]FF[4]SZ[11]PL[W]
+        AB[dc][dd][de][df][cg][eg][dh][di][dj]
+        AW[ef][ff][gf][hf][gg][eh][fh][gh][hh]
+        ;W[ig];B[fg];W[dg])";
+        let game = Game::from_sgf(sgf).unwrap();
+        let forbidden: BTreeMap<Coord, IllegalReason> = game.forbidden_points().collect();
+        assert_eq!(forbidden.get(&(6, 4)), Some(&IllegalReason::Superko));
+        assert_eq!(game.check_point((6, 4)), Some(PlayError::Superko((6, 4))));
+    }
+
     /// https://github.com/Sagebati/goban/issues/6
     #[test]
     fn ko_test_2() {
@@ -751,7 +1166,7 @@ mod tests {
 
         println!("{}", game.pretty_string());
 
-        assert_eq!(game.try_play(Move::Play(0, 0)).err(), Some(Suicide));
+        assert_eq!(game.try_play(Move::Play(0, 0)).err(), Some(Suicide((0, 0))));
     }
 
 
@@ -777,6 +1192,121 @@ mod tests {
         );
     }
 
+    #[test]
+    fn jump_to_matches_replay() {
+        let game = Game::from_sgf(include_str!("../sgf/ShusakuvsInseki.sgf")).unwrap();
+        let moves: Vec<Move> = game.moves().take(250).collect();
+        let replay = |n: usize| {
+            let mut g = Game::new(GobanSizes::Nineteen, game.rule());
+            for &m in &moves[..n] {
+                g.play(m);
+            }
+            g
+        };
+        let mut g = replay(250);
+        for n in [0, 249, 17, 250, 1, 128, 129, 64, 200, 3, 250] {
+            assert!(g.jump_to(n));
+            let expected = replay(n);
+            assert_eq!(g.goban(), expected.goban());
+            assert_eq!(g.prisoners(), expected.prisoners());
+            assert_eq!(g.turn(), expected.turn());
+            assert_eq!(g.ko_point(), expected.ko_point());
+            assert_eq!(g.hash_history(), expected.hash_history());
+            assert_eq!(g.moves().chain(g.moves_ahead()).collect::<Vec<_>>(), moves);
+            assert_eq!(g.check_invariants(), Ok(()));
+        }
+        assert!(!g.jump_to(251));
+        assert_eq!(g.moves().count(), 250);
+
+        // Playing a move drops the moves ahead.
+        g.jump_to(100);
+        g.play(Move::Pass);
+        assert_eq!(g.moves_ahead().count(), 0);
+        assert!(!g.jump_to(102));
+    }
+
+    #[test]
+    fn jump_to_start_restores_handicap() {
+        let mut g = Game::builder()
+            .size((9, 9))
+            .put_handicap(&[(2, 2), (6, 6)])
+            .build()
+            .unwrap();
+        let start = g.clone();
+        g.play(Play(2, 6)).play(Play(6, 2)).play(Move::Pass);
+        assert!(g.jump_to(0));
+        assert_eq!(g.goban(), start.goban());
+        assert_eq!(g.turn(), Color::White);
+        assert_eq!(g.moves_ahead().count(), 3);
+        assert!(g.jump_to(3));
+        assert_eq!(g.moves().count(), 3);
+        assert_eq!(g.goban().get_color((6, 2)), Some(Color::Black));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_round_trip() {
+        use goban::rules::clock::{TimeControl, TimeLeft};
+        use std::time::Duration;
+
+        let mut g = Game::builder()
+            .size((9, 9))
+            .put_handicap(&[(2, 2), (6, 6)])
+            .komi(0.5)
+            .rule(JAPANESE)
+            .build()
+            .unwrap();
+        g.clock_mut().set_control(TimeControl::Absolute {
+            main: Duration::from_secs(600),
+        });
+        g.clock_mut()
+            .set_time_left(Color::White, TimeLeft::main(Duration::from_secs(42)));
+        // White captures the black stone at (0, 1).
+        g.play(Play(0, 0))
+            .play(Play(0, 1))
+            .play(Play(1, 1))
+            .play(Move::Pass)
+            .play(Play(0, 2))
+            .play(Move::Pass)
+            .play(Move::Pass);
+        assert_eq!(g.prisoners(), (0, 1));
+        let json = serde_json::to_string(&g).unwrap();
+        let loaded: Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.goban(), g.goban());
+        assert_eq!(loaded.moves().collect::<Vec<_>>(), g.moves().collect::<Vec<_>>());
+        assert_eq!(loaded.hash_history(), g.hash_history());
+        assert_eq!(loaded.prisoners(), g.prisoners());
+        assert_eq!(loaded.turn(), g.turn());
+        assert_eq!(loaded.rule(), g.rule());
+        assert_eq!(loaded.clock(), g.clock());
+        assert_eq!(loaded.outcome(), g.outcome());
+        assert!(loaded.outcome().is_some());
+
+        let mut resigned = loaded.clone();
+        resigned.resume();
+        resigned.play(Move::Resign(Color::Black));
+        let json = serde_json::to_string(&resigned).unwrap();
+        let loaded: Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.outcome(), Some(EndGame::WinnerByResign(Color::White)));
+        assert_eq!(loaded.moves().count(), 8);
+
+        // A move on an occupied point can't be replayed.
+        let corrupted = serde_json::to_string(&g)
+            .unwrap()
+            .replace("{\"Play\":[0,2]}", "{\"Play\":[0,0]}");
+        let error = serde_json::from_str::<Game>(&corrupted).unwrap_err();
+        assert!(error.to_string().starts_with("move 4 Play(0, 0): the point (0, 0) is occupied"));
+
+        // The size is checked before building the goban.
+        let json = serde_json::to_string(&Game::new(GobanSizes::Nine, CHINESE)).unwrap();
+        assert!(json.contains("\"size\":[9,9]"));
+        for size in ["[25,25]", "[0,0]", "[9,0]"] {
+            let corrupted = json.replace("[9,9]", size);
+            let error = serde_json::from_str::<Game>(&corrupted).unwrap_err();
+            assert!(error.to_string().starts_with("invalid goban size"), "{error}");
+        }
+    }
+
     #[test]
     fn sgf_test_1() {
         let game = Game::from_sgf(include_str!("../sgf/sgf_1.sgf")).unwrap();
@@ -789,6 +1319,2465 @@ mod tests {
         )
     }
 
+    #[test]
+    #[cfg(feature = "random")]
+    fn random_goban_and_game() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let goban = Goban::random((9, 9), 40, &mut StdRng::seed_from_u64(42));
+        assert_eq!(goban, Goban::random((9, 9), 40, &mut StdRng::seed_from_u64(42)));
+        let (black, white) = goban.number_of_stones();
+        assert_eq!((black, white), (20, 20));
+        assert!(goban.chains().all(|chain| !chain.is_dead()));
+        assert_eq!(goban.check_invariants(), Ok(()));
+
+        // The goban is full before 81 stones.
+        let full = Goban::random((9, 9), 200, &mut StdRng::seed_from_u64(7));
+        assert!(full.get_stones().count() < 81);
+        assert!(full.chains().all(|chain| !chain.is_dead()));
+
+        let game = Game::random_game(GobanSizes::Nine, CHINESE, 60, &mut StdRng::seed_from_u64(1));
+        let same = Game::random_game(GobanSizes::Nine, CHINESE, 60, &mut StdRng::seed_from_u64(1));
+        assert_eq!(game.goban(), same.goban());
+        assert_eq!(game.moves().collect::<Vec<_>>(), same.moves().collect::<Vec<_>>());
+        assert_eq!(game.moves().count(), 60);
+        assert_eq!(game.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn move_encoding_reserved_values() {
+        let size = (19, 19);
+        assert_eq!(Move::Pass.encode(size), u16::MAX);
+        assert_eq!(Move::Resign(Color::Black).encode(size), u16::MAX - 1);
+        assert_eq!(Move::Resign(Color::White).encode(size), u16::MAX - 2);
+        assert_eq!(Play(0, 0).encode(size), 0);
+        assert_eq!(Play(1, 2).encode(size), 21);
+        assert_eq!(Play(24, 24).encode((25, 25)), 624);
+        for value in [u16::MAX, u16::MAX - 1, u16::MAX - 2, 0, 21, 360] {
+            assert_eq!(Move::decode(value, size).unwrap().encode(size), value);
+        }
+        assert_eq!(Move::decode(361, size), Err(EncodingError::InvalidValue(361)));
+        assert_eq!(Move::decode(u16::MAX - 3, size), Err(EncodingError::InvalidValue(u16::MAX - 3)));
+    }
+
+    #[test]
+    fn encoded_history_round_trip() {
+        for _ in 0..20 {
+            let mut g = Game::new(GobanSizes::Nine, CHINESE);
+            while !g.is_over() && g.moves().count() < 120 {
+                let mut moves: Vec<Move> = g.legals().map(Move::from).collect();
+                moves.push(Move::Pass);
+                g.play(*moves.choose(&mut rng()).unwrap());
+            }
+            if !g.is_over() {
+                g.play(Move::Resign(Color::White));
+            }
+            let encoded = g.encode_history();
+            assert_eq!(encoded.len(), g.moves().count());
+            let replayed = Game::from_encoded_history(GobanSizes::Nine, CHINESE, &encoded).unwrap();
+            assert_eq!(replayed.goban(), g.goban());
+            assert_eq!(replayed.hash_history(), g.hash_history());
+            assert_eq!(replayed.outcome(), g.outcome());
+            assert_eq!(replayed.encode_history(), encoded);
+        }
+    }
+
+    #[test]
+    fn encoded_history_illegal_move() {
+        let size = (9, 9);
+        let moves = [Play(4, 4), Play(3, 3), Play(4, 4)].map(|m| m.encode(size));
+        assert_eq!(
+            Game::from_encoded_history(GobanSizes::Nine, JAPANESE, &moves).unwrap_err(),
+            (2, EncodingError::IllegalMove(PlayError::Occupied((4, 4))))
+        );
+        assert_eq!(
+            Game::from_encoded_history(GobanSizes::Nine, JAPANESE, &[0, 81]).unwrap_err(),
+            (1, EncodingError::InvalidValue(81))
+        );
+        assert_eq!(EncodingError::InvalidValue(81).to_string(), "81 doesn't encode a move");
+        assert_eq!(
+            EncodingError::IllegalMove(PlayError::Occupied((4, 4))).to_string(),
+            "illegal move: the point (4, 4) is occupied"
+        );
+    }
+
+    #[test]
+    fn sgf_resume_and_export() {
+        let mut g = Game::from_sgf(include_str!("../sgf/ShusakuvsInseki.sgf")).unwrap();
+        let loaded = g.moves().count();
+        let first = g.legals().next().unwrap();
+        g.play(first.into());
+        g.play(Move::Pass);
+        let next = g.legals().next().unwrap();
+        g.play(next.into());
+
+        let sgf = g.to_sgf();
+        assert!(sgf.contains("The ear-reddening move."));
+        let reloaded = Game::from_sgf(&sgf).unwrap();
+        assert_eq!(reloaded.moves().count(), loaded + 3);
+        assert_eq!(reloaded.moves().collect::<Vec<_>>(), g.moves().collect::<Vec<_>>());
+        assert_eq!(reloaded.goban(), g.goban());
+        let sgf = reloaded.to_sgf();
+        assert!(sgf.contains("C[The ear-reddening move.]"));
+        assert!(sgf.contains("PB[Shusaku]"));
+    }
+
+    #[test]
+    fn sgf_export_after_undo_keeps_variation() {
+        let mut g = Game::from_sgf(include_str!("../sgf/ShusakuvsInseki.sgf")).unwrap();
+        let loaded = g.moves().count();
+        g.undo();
+        g.undo();
+        g.play(Move::Pass);
+        let sgf = g.to_sgf();
+        assert!(sgf.contains("C[Result = Shusaku by 2 points.]"));
+        let reloaded = Game::from_sgf(&sgf).unwrap();
+        assert_eq!(reloaded.moves().count(), loaded - 1);
+        assert_eq!(reloaded.goban(), g.goban());
+    }
+
+    #[test]
+    fn sgf_collection() {
+        let input = include_str!("../sgf/collection.sgf");
+        let collection = SgfCollection::parse(input).unwrap();
+        assert_eq!(collection.len(), 3);
+        assert!(collection.get(3).is_none());
+        let files = [
+            include_str!("../sgf/gtp_9x9.sgf"),
+            include_str!("../sgf/sgf_1.sgf"),
+            include_str!("../sgf/sgf_2_2ha.sgf"),
+        ];
+        for (i, file) in files.iter().enumerate() {
+            let game = collection.game(i).unwrap().unwrap();
+            let expected = Game::from_sgf(file).unwrap();
+            assert_eq!(game.moves().collect::<Vec<_>>(), expected.moves().collect::<Vec<_>>());
+            assert_eq!(game.goban(), expected.goban());
+        }
+        let (first, others) = Game::from_sgf_first(input).unwrap();
+        assert_eq!(first.size(), (9, 9));
+        assert_eq!(others, 2);
+
+        let trees: Vec<_> = collection.iter().map(Result::unwrap).collect();
+        let written = write_collection(&trees);
+        let reread = SgfCollection::parse(&written).unwrap();
+        assert_eq!(reread.len(), 3);
+        for (game, tree) in reread.games().zip(trees) {
+            let expected = Game::from_game_tree(tree).unwrap();
+            assert_eq!(
+                game.unwrap().moves().collect::<Vec<_>>(),
+                expected.moves().collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn sgf_collection_delimiters() {
+        let collection =
+            SgfCollection::parse("header\n(;C[a ) and \\] (];B[aa](;W[bb])(;W[cc]))(;C[x])")
+                .unwrap();
+        assert_eq!(collection.len(), 2);
+        assert_eq!(collection.source(1), Some("(;C[x])"));
+        assert_eq!(collection.get(0).unwrap().unwrap().count_variations(), 2);
+        assert_eq!(
+            SgfCollection::parse("(;B[aa])(;W[bb]").unwrap_err(),
+            CollectionError::Unclosed(8)
+        );
+        assert_eq!(
+            SgfCollection::parse("(;B[aa]))").unwrap_err(),
+            CollectionError::UnexpectedClose(8)
+        );
+    }
+
+    #[test]
+    fn board_idx_max_size() {
+        let size = (19, 19);
+        let goban = Goban::new(size);
+        let indexes: Vec<BoardIdx> = BoardIdx::all(size).collect();
+        assert_eq!(indexes.len(), 361);
+        for (i, &index) in indexes.iter().enumerate() {
+            assert_eq!(index.get(), i);
+            let coord = index.to_coord(size);
+            assert_eq!(BoardIdx::from_coord(size, coord), index);
+            assert_eq!(goban.coord_of(i), coord);
+            assert_eq!(goban.index_of(coord), i);
+        }
+        assert_eq!(BoardIdx::from_coord(size, (0, 18)).get(), 18);
+        assert_eq!(BoardIdx::from_coord(size, (18, 0)).get(), 342);
+        assert_eq!(BoardIdx::from_coord(size, (18, 18)).get(), 360);
+        assert_eq!(BoardIdx::try_from(360usize).map(u16::from), Ok(360));
+        assert!(BoardIdx::try_from(usize::from(u16::MAX) + 1).is_err());
+
+        // Stones and liberties in the last corner.
+        let mut game = Game::new(GobanSizes::Nineteen, CHINESE);
+        game.play(Play(18, 17)).play(Play(18, 18)).play(Play(17, 18));
+        assert_eq!(game.prisoners(), (1, 0));
+        assert_eq!(game.goban().get_color((18, 18)), None);
+        assert!(game.goban().check_invariants().is_ok());
+    }
+
+    #[test]
+    fn full_board_iterators_row_major() {
+        for size in [(19, 19), (9, 13), (13, 9)] {
+            let mut goban = Goban::new(size);
+            for row in 0..size.0 {
+                for column in 0..size.1 {
+                    match (row as usize * 7 + column as usize * 3) % 5 {
+                        0 | 1 => goban.push((row, column), Color::Black),
+                        2 => goban.push((row, column), Color::White),
+                        _ => continue,
+                    };
+                }
+            }
+            let points: Vec<(Coord, Option<Color>)> = (0..size.0 as usize * size.1 as usize)
+                .map(|index| goban.coord_of(index))
+                .map(|coord| (coord, goban.get_color(coord)))
+                .collect();
+            let coords_of = |color: Option<Color>| -> Vec<Coord> {
+                points
+                    .iter()
+                    .filter(|point| point.1 == color)
+                    .map(|point| point.0)
+                    .collect()
+            };
+            let stones: Vec<(Coord, Option<Color>)> = goban
+                .get_stones()
+                .map(|stone| (stone.coord, Some(stone.color)))
+                .collect();
+            let expected: Vec<_> = points.iter().copied().filter(|p| p.1.is_some()).collect();
+            assert_eq!(stones, expected);
+            assert_eq!(goban.get_empty_coords().collect::<Vec<_>>(), coords_of(None));
+            for color in [Color::Black, Color::White] {
+                let coords: Vec<Coord> = goban.get_coords_by_color(Some(color)).collect();
+                assert_eq!(coords, coords_of(Some(color)));
+            }
+        }
+    }
+
+    #[test]
+    fn capturable_chains() {
+        let mut goban = Goban::new((9, 9));
+        // Two white stones whose last liberty is (0, 1), and a white chain in atari at (4, 4).
+        goban.push_many(&[(0, 0), (0, 2)], Color::White);
+        goban.push_many(&[(1, 0), (1, 2), (0, 3)], Color::Black);
+        goban.push_many(&[(4, 4), (4, 5)], Color::White);
+        goban.push_many(&[(3, 4), (3, 5), (5, 4), (5, 5), (4, 6)], Color::Black);
+        // Black chain with two liberties, not capturable by white.
+        goban.push((8, 8), Color::Black);
+
+        let captures: Vec<(Coord, Coord)> = goban
+            .capturable_chains(Color::Black)
+            .map(|(group, point)| (goban.coord_of(group.origin as usize), point))
+            .collect();
+        assert_eq!(captures, vec![((0, 0), (0, 1)), ((0, 2), (0, 1)), ((4, 4), (4, 3))]);
+        assert!(goban
+            .capturable_chains(Color::Black)
+            .all(|(group, _)| group.color == Color::White && group.is_atari()));
+        assert_eq!(goban.capturable_chains(Color::White).count(), 0);
+    }
+
+    #[test]
+    fn eyespace_shapes() {
+        use EyespaceStatus::{Alive, Dead, Killable, Unknown};
+
+        assert_eq!(classify_eyespace(&[(3, 3)]), Dead);
+        assert_eq!(classify_eyespace(&[(5, 2), (4, 2)]), Dead);
+        // Straight and bent three, turned and moved.
+        assert_eq!(
+            classify_eyespace(&[(2, 7), (3, 7), (4, 7)]),
+            Killable { vital: (3, 7) }
+        );
+        assert_eq!(
+            classify_eyespace(&[(6, 5), (7, 4), (7, 5)]),
+            Killable { vital: (7, 5) }
+        );
+        assert_eq!(classify_eyespace(&[(0, 0), (0, 1), (0, 2), (0, 3)]), Alive);
+        assert_eq!(classify_eyespace(&[(4, 4), (4, 5), (4, 6), (3, 6)]), Alive);
+        assert_eq!(classify_eyespace(&[(1, 1), (2, 1), (2, 2), (3, 2)]), Alive);
+        assert_eq!(
+            classify_eyespace(&[(4, 8), (5, 8), (6, 8), (5, 7)]),
+            Killable { vital: (5, 8) }
+        );
+        assert_eq!(classify_eyespace(&[(0, 0), (0, 1), (1, 0), (1, 1)]), Dead);
+        // Bulky five upside down, the vital point is the one with three neighbors.
+        assert_eq!(
+            classify_eyespace(&[(8, 8), (8, 7), (7, 8), (7, 7), (6, 7)]),
+            Killable { vital: (7, 7) }
+        );
+        assert_eq!(
+            classify_eyespace(&[(2, 3), (3, 2), (3, 3), (3, 4), (4, 3)]),
+            Killable { vital: (3, 3) }
+        );
+        assert_eq!(
+            classify_eyespace(&[(10, 10), (11, 9), (11, 10), (11, 11), (12, 10), (12, 9)]),
+            Killable { vital: (11, 10) }
+        );
+        assert_eq!(
+            classify_eyespace(&[(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]),
+            Alive
+        );
+        // Other five points spaces are alive.
+        assert_eq!(classify_eyespace(&[(0, 0), (0, 1), (0, 2), (0, 3), (0, 4)]), Alive);
+        assert_eq!(classify_eyespace(&[(0, 0), (1, 0), (1, 1), (1, 2), (2, 2)]), Alive);
+
+        assert_eq!(classify_eyespace(&[(0, 0), (0, 2)]), Unknown);
+        assert_eq!(classify_eyespace(&[(0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (0, 5)]), Unknown);
+        assert_eq!(classify_eyespace(&[]), Unknown);
+        let seven: Vec<Coord> = (0..7).map(|col| (0, col)).collect();
+        assert_eq!(classify_eyespace(&seven), Unknown);
+    }
+
+    #[test]
+    fn ko_threats_of_a_ko_fight() {
+        let mut g = Game::new(GobanSizes::Nine, CHINESE);
+        // The ko in the upper left, two black stones white can atari in the lower right and a
+        // black stone white can chase in a ladder on the left.
+        let moves = [
+            (Play(1, 0), Play(0, 2)),
+            (Play(0, 1), Play(2, 2)),
+            (Play(2, 1), Play(1, 3)),
+            (Play(6, 6), Play(5, 6)),
+            (Play(6, 7), Play(5, 7)),
+            (Play(5, 2), Play(7, 7)),
+            (Play(2, 6), Play(7, 6)),
+            (Play(3, 6), Play(5, 1)),
+            (Move::Pass, Play(6, 3)),
+            (Move::Pass, Play(1, 1)),
+        ];
+        for (black, white) in moves {
+            g.play(black).play(white);
+        }
+        g.play(Play(1, 2));
+        assert_eq!(g.ko_point(), Some((1, 1)));
+
+        let threats = ko_threats(&g, Color::White);
+        assert_eq!(
+            threats,
+            vec![
+                ((6, 5), ThreatSize::Atari(2)),
+                ((6, 8), ThreatSize::Atari(2)),
+                ((4, 2), ThreatSize::Ladder(1)),
+                ((5, 3), ThreatSize::Ladder(1)),
+            ]
+        );
+        assert_eq!(ThreatSize::Atari(2).points(), 4);
+        // The moves around the ko are part of the fight.
+        assert!(ko_threats(&g, Color::Black)
+            .iter()
+            .all(|&((row, col), _)| row.abs_diff(1) > 1 || col.abs_diff(1) > 1));
+
+        // The white chain touches (1, 1) on two sides, its three stones are counted once.
+        let mut builder = Game::builder();
+        builder.size((9, 9)).rule(CHINESE);
+        for (coords, color) in [
+            ([(1, 0), (2, 0), (2, 1)], Color::White),
+            ([(3, 0), (3, 1), (2, 2)], Color::Black),
+        ] {
+            for coord in coords {
+                builder.add(Stone { coord, color });
+            }
+        }
+        let g = builder.build().unwrap();
+        assert!(ko_threats(&g, Color::Black).contains(&((1, 1), ThreatSize::Atari(3))));
+    }
+
+    #[test]
+    fn capture_and_atari_moves() {
+        let mut goban = Goban::new((9, 9));
+        // One white stone to capture at (1, 0) and two white chains with two liberties.
+        goban.push((0, 0), Color::White);
+        goban.push((0, 1), Color::Black);
+        goban.push((4, 4), Color::White);
+        goban.push_many(&[(3, 4), (4, 3)], Color::Black);
+        goban.push_many(&[(7, 7), (7, 8)], Color::White);
+        goban.push_many(&[(6, 7), (6, 8), (7, 6)], Color::Black);
+
+        assert_eq!(goban.capture_moves(Color::Black), vec![((1, 0), 1)]);
+        assert_eq!(goban.capture_moves(Color::White), vec![]);
+
+        let origin = |chain| goban.chain_stones(chain).next().unwrap().coord;
+        let ataris = |self_atari| -> Vec<(Coord, Coord)> {
+            goban
+                .atari_moves(Color::Black, self_atari)
+                .into_iter()
+                .map(|(point, chain)| (point, origin(chain)))
+                .collect()
+        };
+        // Black on (8, 8) would have a single liberty.
+        assert_eq!(
+            ataris(false),
+            vec![((4, 5), (4, 4)), ((5, 4), (4, 4)), ((8, 7), (7, 7))]
+        );
+        assert_eq!(
+            ataris(true),
+            vec![((4, 5), (4, 4)), ((5, 4), (4, 4)), ((8, 7), (7, 7)), ((8, 8), (7, 7))]
+        );
+    }
+
+    #[test]
+    fn local_moves_around_last_move() {
+        let mut g = Game::new(GobanSizes::Nine, CHINESE);
+        assert_eq!(local_moves(&g, LOCAL_RADIUS), vec![]);
+
+        // Clipped by the corner: the 4x4 square without the stone.
+        g.play(Play(0, 0));
+        let moves = local_moves(&g, LOCAL_RADIUS);
+        assert_eq!(moves.len(), 15);
+        assert!(moves.iter().all(|&(row, col)| row <= 3 && col <= 3));
+        assert_eq!(local_moves(&g, 0), vec![]);
+        assert_eq!(local_moves(&g, 1).len(), 3);
+        assert_eq!(local_moves(&g, 100).len(), 80);
+
+        g.play(Move::Pass);
+        assert_eq!(local_moves(&g, LOCAL_RADIUS), vec![]);
+
+        // The capture comes first and the ko isn't returned.
+        let mut g = Game::new(GobanSizes::Nine, CHINESE);
+        for point in [(4, 3), (4, 4), (3, 4), (3, 5), (5, 4), (5, 5), (0, 0), (4, 6)] {
+            g.play(Play(point.0, point.1));
+        }
+        let moves = local_moves(&g, 2);
+        assert_eq!(moves[0], (4, 5));
+        g.play(Play(4, 5));
+        assert_eq!(g.ko_point(), Some((4, 4)));
+        let moves = local_moves(&g, 2);
+        assert!(!moves.contains(&(4, 4)));
+        let legals: Vec<Coord> = g.legals().collect();
+        assert!(moves.iter().all(|point| legals.contains(point)));
+    }
+
+    #[test]
+    fn weighted_moves_locality() {
+        assert_eq!(proximity_bonus((3, 3), &[(3, 3)], 0.5), 1.);
+        assert_eq!(proximity_bonus((3, 3), &[], 0.5), 0.);
+        let near = proximity_bonus((3, 4), &[(3, 3), (10, 10)], 0.5);
+        let far = proximity_bonus((15, 15), &[(3, 3), (10, 10)], 0.5);
+        assert!(near > far && far > 0.);
+        assert!((proximity_bonus((4, 5), &[(3, 3)], 1.) - (-2f32).exp()).abs() < 1e-6);
+
+        // A white stone black can atari far away, white just played in the middle.
+        let mut g = Game::new(GobanSizes::Nineteen, CHINESE);
+        for point in [(2, 1), (2, 2), (1, 2), (9, 9)] {
+            g.play(Play(point.0, point.1));
+        }
+        let moves = weighted_moves(&g, &OrderingWeights::default());
+        assert!([(2, 3), (3, 2)].contains(&moves[0]));
+        let local = OrderingWeights {
+            proximity: 100.,
+            recent: 1,
+            ..Default::default()
+        };
+        let moves = weighted_moves(&g, &local);
+        assert!(moves[0].0.abs_diff(9) <= 1 && moves[0].1.abs_diff(9) <= 1);
+        assert_eq!(moves.len(), g.legals().count());
+    }
+
+    #[test]
+    fn transform_game() {
+        let games = [
+            Game::from_sgf(include_str!("../sgf/ShusakuvsInseki.sgf")).unwrap(),
+            Game::from_sgf(include_str!("../sgf/sgf_2_2ha.sgf")).unwrap(),
+        ];
+        for g in &games {
+            for symmetry in Symmetry::ALL {
+                let t = g.transform(symmetry);
+                assert_eq!(t.goban(), &g.goban().transform(symmetry));
+                assert_eq!(t.prisoners(), g.prisoners());
+                assert_eq!(t.outcome(), g.outcome());
+                assert_eq!(t.moves().count(), g.moves().count());
+                assert_eq!(t.ko_point(), g.ko_point().map(|p| symmetry.apply(g.size(), p)));
+                assert!(t.check_invariants().is_ok());
+                let reloaded = Game::from_sgf(&t.to_sgf()).unwrap();
+                assert_eq!(reloaded.goban(), t.goban());
+                assert_eq!(t.transform(symmetry.inverse()).goban(), g.goban());
+            }
+        }
+
+        // A non square goban swaps its sides.
+        let mut g = Game::new(GobanSizes::Custom(5, 7), CHINESE);
+        for point in [(0, 1), (0, 2), (1, 0), (1, 3), (2, 1), (1, 2), (4, 6), (1, 1), (0, 0)] {
+            g.play(Play(point.0, point.1));
+        }
+        g.play(Move::Pass);
+        g.play(Move::Resign(Color::White));
+        let t = g.transform(Symmetry::Rotate90);
+        assert_eq!(t.size(), (7, 5));
+        assert_eq!(t.goban().get_color((6, 0)), Some(Color::Black));
+        assert_eq!(t.goban(), &g.goban().transform(Symmetry::Rotate90));
+        assert_eq!(t.outcome(), Some(EndGame::WinnerByResign(Color::Black)));
+        assert_eq!(t.moves().last(), Some(Move::Resign(Color::White)));
+    }
+
+    #[test]
+    fn augment_planes_match_gobans() {
+        let planes_of = |goban: &Goban| -> Vec<u8> {
+            let (height, width) = goban.size();
+            let points: Vec<Coord> =
+                (0..height).flat_map(|row| (0..width).map(move |col| (row, col))).collect();
+            [Color::Black, Color::White]
+                .into_iter()
+                .flat_map(|color| {
+                    points.iter().map(move |&p| u8::from(goban.get_color(p) == Some(color)))
+                })
+                .collect()
+        };
+        let shusaku = Game::from_sgf(include_str!("../sgf/ShusakuvsInseki.sgf")).unwrap();
+        let mut small = Goban::new((5, 7));
+        small.push_many(&[(0, 1), (1, 4), (4, 6)], Color::Black);
+        small.push_many(&[(2, 2), (3, 0)], Color::White);
+        for (goban, target) in [(shusaku.goban().clone(), (3, 15)), (small, (0, 5))] {
+            let size = goban.size();
+            let table = SymmetryTable::new(size);
+            let planes = planes_of(&goban);
+            let by_planes = table.augment_planes(&planes, two_to_1dim(size, target));
+            let mut seen = HashSet::new();
+            for ((symmetry, (moved, policy)), (moved_planes, index)) in
+                Symmetry::ALL.into_iter().zip(augment(&goban, target)).zip(by_planes)
+            {
+                assert_eq!(moved.size(), symmetry.size(size));
+                assert_eq!(moved_planes, planes_of(&moved));
+                assert_eq!(index, two_to_1dim(moved.size(), policy));
+                seen.insert(moved_planes);
+            }
+            assert_eq!(seen.len(), 8);
+            // The pass after the points of the policy doesn't move.
+            assert_eq!(table.transform_index(Symmetry::Rotate90, table.area()), table.area());
+        }
+    }
+
+    #[test]
+    fn training_samples_round_trip() {
+        let dir = std::env::temp_dir();
+        for (encoding, name) in [(PlaneEncoding::Float, "float"), (PlaneEncoding::Bits, "bits")] {
+            let config = SampleConfig {
+                size: (5, 7),
+                planes: 3,
+                encoding,
+            };
+            let samples: Vec<Sample> = (0..20u16)
+                .map(|n| Sample {
+                    planes: (0..config.values())
+                        .map(|i| match encoding {
+                            PlaneEncoding::Float => (i as f32 - 50.) / (n + 1) as f32,
+                            PlaneEncoding::Bits => {
+                                f32::from(u8::from((i * 7 + n as usize).is_multiple_of(3)))
+                            }
+                        })
+                        .collect(),
+                    policy_index: n * 3 % 36,
+                    outcome: n as i8 % 3 - 1,
+                })
+                .collect();
+            let path = dir.join(format!("goban_samples_{}_{name}.bin", std::process::id()));
+            let mut writer = SampleWriter::new(&path, config).unwrap();
+            for sample in &samples {
+                writer.write(&sample.planes, sample.policy_index, sample.outcome).unwrap();
+            }
+            assert!(matches!(
+                writer.write(&[0.; 3], 0, 0),
+                Err(TrainingError::PlaneLength { expected: 105, found: 3 })
+            ));
+            writer.finish().unwrap();
+
+            let reader = SampleReader::open(&path).unwrap();
+            assert_eq!(reader.config(), config);
+            let read: Vec<Sample> = reader.map(Result::unwrap).collect();
+            assert_eq!(read.len(), samples.len());
+            for (read, sample) in read.iter().zip(&samples) {
+                let bits = |planes: &[f32]| planes.iter().map(|v| v.to_bits()).collect::<Vec<_>>();
+                assert_eq!(bits(&read.planes), bits(&sample.planes));
+                assert_eq!(read.policy_index, sample.policy_index);
+                assert_eq!(read.outcome, sample.outcome);
+            }
+
+            // The file cut in the middle of the last sample.
+            let bytes = std::fs::read(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            let mut reader = SampleReader::from_reader(&bytes[..bytes.len() - 1]).unwrap();
+            for _ in 1..samples.len() {
+                assert!(reader.next_sample().unwrap().is_some());
+            }
+            assert!(matches!(reader.next_sample(), Err(TrainingError::Truncated)));
+            assert!(matches!(
+                SampleReader::from_reader(&bytes[..5]),
+                Err(TrainingError::Truncated)
+            ));
+            assert!(matches!(
+                SampleReader::from_reader(&b"not samples at all"[..]),
+                Err(TrainingError::BadMagic)
+            ));
+        }
+    }
+
+    #[test]
+    fn training_samples_from_collection() {
+        let collection = SgfCollection::parse(include_str!("../sgf/collection.sgf")).unwrap();
+        let config = SampleConfig {
+            size: (9, 9),
+            planes: GAME_PLANES,
+            encoding: PlaneEncoding::Bits,
+        };
+        let mut writer = SampleWriter::from_writer(vec![], config).unwrap();
+        let written = write_collection_samples(&collection, &mut writer).unwrap();
+        let nine: Vec<Game> = collection
+            .games()
+            .map(Result::unwrap)
+            .filter(|g| g.size() == (9, 9))
+            .collect();
+        let expected: usize = nine
+            .iter()
+            .map(|g| g.moves().filter(|m| !matches!(m, Move::Resign(_))).count())
+            .sum();
+        assert_eq!(written, expected);
+
+        let bytes = writer.finish().unwrap();
+        let samples: Vec<Sample> =
+            SampleReader::from_reader(bytes.as_slice()).unwrap().map(Result::unwrap).collect();
+        assert_eq!(samples.len(), written);
+        // Black to move on the empty goban plays tengen.
+        let first = &samples[0];
+        assert_eq!(first.policy_index, 4 * 9 + 4);
+        assert!(first.planes[..2 * 81].iter().all(|&v| v == 0.));
+        assert!(first.planes[2 * 81..].iter().all(|&v| v == 1.));
+        // White to move sees the black stone as the opponent's.
+        assert_eq!(samples[1].planes[81 + 4 * 9 + 4], 1.);
+        let mut g = nine[0].clone();
+        g.jump_to(2);
+        assert_eq!(samples[2].planes, game_planes(&g));
+    }
+
+    #[test]
+    fn zobrist_table_golden() {
+        // Stored hashes rely on these keys, they must never change.
+        let table = ZobristTable::default();
+        assert_eq!(table.key(0, Color::Black), 0xffed071d11b312ad);
+        assert_eq!(table.key(60, Color::Black), 0xd0c8ba9ab4653bc7);
+        assert_eq!(table.key(360, Color::Black), 0x1d3fe89b72a0c58a);
+        assert_eq!(table.key(0, Color::White), 0x946664802a9c9239);
+        assert_eq!(table.key(360, Color::White), 0x88fc7ffcd3d2e9bc);
+        let g = Game::from_sgf(include_str!("../sgf/ShusakuvsInseki.sgf")).unwrap();
+        assert_eq!(g.goban().zobrist_hash(), 0xf958c4e6ba8fb559);
+        assert_eq!(g.goban().zobrist_hash_with(&table), g.goban().zobrist_hash());
+
+        let bytes = table.to_bytes();
+        assert_eq!(&bytes[..8], b"GOZT\x01\x00\xd2\x02");
+        assert_eq!(ZobristTable::from_bytes(&bytes), Ok(table.clone()));
+        assert_eq!(
+            ZobristTable::from_bytes(&bytes[..bytes.len() - 8]),
+            Err(ZobristTableError::Length { expected: 722, found: 721 })
+        );
+        let mut other = bytes.clone();
+        other[4] = 2;
+        assert_eq!(
+            ZobristTable::from_bytes(&other),
+            Err(ZobristTableError::UnsupportedVersion(2))
+        );
+        assert_eq!(ZobristTable::from_bytes(b"GOBAN"), Err(ZobristTableError::BadMagic));
+
+        // A book shipping its own keys.
+        let mut keys = bytes.clone();
+        keys[8..16].copy_from_slice(&1u64.to_le_bytes());
+        let own = ZobristTable::from_bytes(&keys).unwrap();
+        let mut goban = Goban::new((19, 19));
+        goban.push((0, 0), Color::Black).push((3, 3), Color::White);
+        assert_eq!(goban.zobrist_hash_with(&own), 1 ^ table.key(3 * 19 + 3, Color::White));
+        assert_ne!(goban.zobrist_hash_with(&own), goban.zobrist_hash());
+    }
+
+    #[test]
+    fn concurrent_transposition_table() {
+        use std::sync::Mutex;
+        use std::thread;
+
+        let table = ConcurrentTranspositionTable::new(4096, 16);
+        assert_eq!(table.capacity(), 4096);
+        let model = Mutex::new(HashMap::new());
+        let threads = 8u64;
+        thread::scope(|scope| {
+            for thread in 0..threads {
+                let (table, model) = (&table, &model);
+                scope.spawn(move || {
+                    // The threads have their own keys, so the writes of a key are ordered.
+                    let mut state = thread + 1;
+                    for step in 0..5000u64 {
+                        state = state
+                            .wrapping_mul(6364136223846793005)
+                            .wrapping_add(1442695040888963407);
+                        let key = (state >> 8) / threads * threads + thread;
+                        let value = (thread, step);
+                        table.insert(key, value);
+                        model.lock().unwrap().insert(key, value);
+                        if let Some(read) = table.probe(key) {
+                            assert_eq!(read.0, thread);
+                        }
+                    }
+                });
+            }
+        });
+        let model = model.into_inner().unwrap();
+        let mut found = 0;
+        for (&key, &value) in &model {
+            if let Some(read) = table.get(key) {
+                assert_eq!(read, value);
+                found += 1;
+            }
+        }
+        // A slot keeps one entry, the others were replaced.
+        assert!(found > 4096 / 2 && found <= 4096, "{found}");
+
+        // Two keys in the same slot.
+        let table = ConcurrentTranspositionTable::new(1, 1);
+        table.insert(1, "one");
+        assert_eq!(table.get(1), Some("one"));
+        table.insert(2, "two");
+        assert_eq!(table.get(1), None);
+        assert_eq!(table.probe(2), Some("two"));
+        table.clear();
+        assert_eq!(table.get(2), None);
+    }
+
+    #[test]
+    fn game_snapshot_shared_by_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Goban>();
+        assert_send_sync::<Game>();
+        assert_send_sync::<GameSnapshot>();
+        assert_send_sync::<ConcurrentTranspositionTable<Game>>();
+
+        let mut root = Game::from_sgf(include_str!("../sgf/ShusakuvsInseki.sgf")).unwrap();
+        root.jump_to(100);
+        root.enable_legal_move_cache();
+        let snapshot = root.snapshot();
+        let lines: Vec<(Goban, Vec<Move>)> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..4)
+                .map(|worker| {
+                    let snapshot = Arc::clone(&snapshot);
+                    scope.spawn(move || {
+                        let mut game = snapshot.fork();
+                        for _ in 0..3 {
+                            snapshot.fork_into(&mut game);
+                            for step in 0..10 {
+                                let legals: Vec<Coord> = game.legals().collect();
+                                let point = legals[(worker * 31 + step * 7) % legals.len()];
+                                game.play(Play(point.0, point.1));
+                            }
+                        }
+                        assert!(game.check_invariants().is_ok());
+                        (game.goban().clone(), game.moves().skip(100).collect())
+                    })
+                })
+                .collect();
+            workers.into_iter().map(|w| w.join().unwrap()).collect()
+        });
+        let distinct: HashSet<&Vec<Move>> = lines.iter().map(|(_, moves)| moves).collect();
+        assert_eq!(distinct.len(), 4);
+        for (goban, moves) in &lines {
+            assert_eq!(moves.len(), 10);
+            let mut replayed = root.clone();
+            for &m in moves {
+                replayed.play(m);
+            }
+            assert_eq!(replayed.goban(), goban);
+        }
+        // The snapshot and the root are untouched.
+        assert_eq!(snapshot.moves().count(), 100);
+        assert_eq!(snapshot.goban(), root.goban());
+    }
+
+    #[test]
+    fn is_legal_matches_play() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let suicide_allowed = Rule {
+            flag_illegal: IllegalRules::SUPERKO,
+            ..CHINESE
+        };
+        let mut superkos = 0;
+        for (seed, rule) in [(1, CHINESE), (2, JAPANESE), (3, suicide_allowed)] {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut g = Game::new(GobanSizes::Custom(7, 7), rule);
+            for _ in 0..150 {
+                for point in g.goban().get_empty_coords() {
+                    let clone_hash = g.play_for_verification(point).zobrist_hash();
+                    if g.check_suicide(Stone { coord: point, color: g.turn() }) {
+                        continue;
+                    }
+                    assert_eq!(g.goban().hash_after(point, g.turn()), clone_hash);
+                }
+                let (height, width) = g.size();
+                let mut moves: Vec<Move> = (0..=height)
+                    .flat_map(|row| (0..=width).map(move |col| Play(row, col)))
+                    .collect();
+                moves.push(Move::Pass);
+                let illegal = rule.flag_illegal;
+                // The position before the last move, the one a ko would repeat.
+                let previous = g.hash_history().last().copied();
+                for m in moves {
+                    let legal = g.is_legal(m);
+                    // The verdict is checked against the goban after the move, not try_play
+                    // which asks is_legal too.
+                    let played = |m: Move| {
+                        let mut clone = g.clone();
+                        clone.play(m);
+                        clone
+                    };
+                    match (legal, m) {
+                        (Ok(()), Move::Pass) => assert!(!g.is_over()),
+                        (Ok(()), Play(x, y)) => {
+                            let after = played(Play(x, y));
+                            let hash = after.goban().zobrist_hash();
+                            if illegal.contains(IllegalRules::SUICIDE) {
+                                let chain = after.goban().chain_info((x, y)).unwrap();
+                                assert!(chain.liberty_count > 0, "{m:?}");
+                            }
+                            if illegal.contains(IllegalRules::SUPERKO) {
+                                assert_eq!(g.position_seen(hash), None, "{m:?}");
+                            } else if illegal.contains(IllegalRules::KO) {
+                                assert_ne!(Some(hash), previous, "{m:?}");
+                            }
+                        }
+                        (Err(PlayError::OutsideBoard(_)), Play(x, y)) => {
+                            assert!(x >= height || y >= width);
+                        }
+                        (Err(PlayError::Occupied(_)), Play(x, y)) => {
+                            assert!(g.goban().get_color((x, y)).is_some());
+                        }
+                        (Err(PlayError::Suicide(_)), Play(x, y)) => {
+                            assert!(illegal.contains(IllegalRules::SUICIDE));
+                            // The stone is put without the captures: it captures nothing
+                            // and its chain has no liberty.
+                            let mut goban = g.goban().clone();
+                            goban.push((x, y), g.turn());
+                            let liberties = |point| goban.chain_info(point).unwrap().liberty_count;
+                            for chain in goban.adjacent_enemy_chains((x, y)) {
+                                let stone = goban.chain_stones(chain).next().unwrap();
+                                assert!(liberties(stone.coord) > 0, "{m:?}");
+                            }
+                            assert_eq!(liberties((x, y)), 0, "{m:?}");
+                        }
+                        (Err(PlayError::Ko(_)), Play(x, y)) => {
+                            assert!(illegal.contains(IllegalRules::KO));
+                            let hash = played(Play(x, y)).goban().zobrist_hash();
+                            assert_eq!(Some(hash), previous, "{m:?}");
+                        }
+                        (Err(PlayError::Superko(_)), Play(x, y)) => {
+                            assert!(illegal.contains(IllegalRules::SUPERKO));
+                            superkos += 1;
+                            let hash = played(Play(x, y)).goban().zobrist_hash();
+                            assert!(g.position_seen(hash).is_some(), "{m:?}");
+                        }
+                        (Err(PlayError::GamePaused | PlayError::GameOver), _) => {
+                            assert!(g.is_over());
+                        }
+                        (verdict, m) => panic!("{m:?}: unexpected {verdict:?}"),
+                    }
+                }
+                let legals: Vec<Coord> = g.legals().collect();
+                let m = legals.choose(&mut rng).map_or(Move::Pass, |&(x, y)| Play(x, y));
+                if g.try_play(m).is_err() {
+                    g.resume();
+                }
+                if g.outcome().is_some() {
+                    break;
+                }
+            }
+        }
+        assert!(superkos > 0, "{superkos}");
+    }
+
+    #[test]
+    fn persistent_play() {
+        let record = Game::from_sgf(include_str!("../sgf/ShusakuvsInseki.sgf")).unwrap();
+        let mut g = Game::new(GobanSizes::Nineteen, JAPANESE);
+        for m in record.moves().take(150) {
+            g.try_play(m).unwrap();
+        }
+        let before = format!("{g:?}");
+        let mut captures = 0;
+        for point in g.goban().get_empty_coords().collect::<Vec<_>>() {
+            let m = Play(point.0, point.1);
+            let mut mutable = g.clone();
+            match g.try_play_cloned(m) {
+                Ok(played) => {
+                    mutable.try_play(m).unwrap();
+                    assert_eq!(played.goban(), mutable.goban());
+                    assert_eq!(played.prisoners(), mutable.prisoners());
+                    assert_eq!(played.ko_point(), mutable.ko_point());
+                    assert_eq!(played.hash_history(), mutable.hash_history());
+                    assert!(played.check_invariants().is_ok());
+
+                    let goban = g.goban().try_play(point, g.turn()).unwrap();
+                    assert_eq!(&goban, played.goban());
+                    assert_eq!(goban.zobrist_hash(), goban.zobrist_hash_with(&Default::default()));
+                    assert!(goban.check_invariants().is_ok());
+                    if played.prisoners() != g.prisoners() {
+                        captures += 1;
+                    }
+                }
+                Err(error) => assert_eq!(mutable.try_play(m).map(|_| ()), Err(error)),
+            }
+        }
+        assert!(captures > 0);
+        assert_eq!(format!("{g:?}"), before);
+
+        let goban = g.goban();
+        let taken = goban.get_stones().next().unwrap().coord;
+        assert_eq!(goban.try_play(taken, Color::Black), Err(GobanError::Occupied(taken)));
+        assert_eq!(goban.try_play((19, 0), Color::Black), Err(GobanError::OutsideBoard((19, 0))));
+        // The goban captures a suicide.
+        let mut corner = Goban::new((9, 9));
+        corner.push_many(&[(0, 1), (1, 0)], Color::White);
+        let after = corner.try_play((0, 0), Color::Black).unwrap();
+        assert_eq!(after, corner);
+    }
+
+    #[test]
+    fn game_pool_resets_games() {
+        use goban::rules::clock::TimeLeft;
+
+        let mut template = Game::new(GobanSizes::Nine, CHINESE);
+        template.play(Play(4, 4)).play(Play(2, 2));
+        let pristine = format!("{template:?}");
+        let pool = GamePool::new(template, 2);
+        {
+            let mut dirty = pool.acquire();
+            assert_eq!(format!("{:?}", *dirty), pristine);
+            dirty.enable_legal_move_cache();
+            for m in [Play(0, 0), Play(8, 8), Play(0, 1), Move::Pass] {
+                dirty.play(m);
+            }
+            dirty.jump_to(3);
+            dirty.set_komi(0.5);
+            dirty.annotate(3, Annotation::comment("dirty"));
+            let time = TimeLeft {
+                time: std::time::Duration::from_secs(10),
+                stones: 1,
+            };
+            dirty.clock_mut().set_time_left(Color::Black, time);
+            dirty.put_stone((7, 7), Color::White);
+            let mut other = pool.acquire();
+            other.play(Move::Pass).play(Move::Pass);
+            other.enter_scoring().unwrap();
+        }
+        assert_eq!(pool.idle(), 2);
+        let games: Vec<_> = (0..3).map(|_| pool.acquire()).collect();
+        assert_eq!(pool.idle(), 0);
+        for game in &games {
+            assert_eq!(format!("{:?}", **game), pristine);
+            assert_eq!(game.moves_ahead().count(), 0);
+        }
+        drop(games);
+        // The pool keeps at most 2 games.
+        assert_eq!(pool.idle(), 2);
+        assert_eq!(format!("{:?}", pool.template()), pristine);
+    }
+
+    #[test]
+    fn scoring_edge_cases() {
+        // An empty goban: only the komi.
+        for rule in [CHINESE, JAPANESE] {
+            let g = Game::new(GobanSizes::Custom(7, 9), rule);
+            assert_eq!(g.calculate_score(), (0., rule.komi));
+            assert_eq!(g.goban().count_territory().dame, 63);
+        }
+
+        // One color: the whole goban under area rules, the empty points under territory rules.
+        let mut setup = Game::setup((9, 9));
+        setup.put((4, 4), Color::White).put((2, 6), Color::White);
+        let mut g = setup.rule(CHINESE).finish().unwrap();
+        assert_eq!(g.calculate_score(), (0., 81. + 7.5));
+        g.set_rule(JAPANESE);
+        assert_eq!(g.calculate_score(), (0., 79. + 6.5));
+
+        // A corridor of the first line behind a black wall, open on a region touching white.
+        let mut setup = Game::setup((9, 9));
+        for col in 0..6 {
+            setup.put((1, col), Color::Black);
+        }
+        for row in 0..9 {
+            setup.put((row, 7), Color::White);
+        }
+        let mut g = setup.rule(JAPANESE).finish().unwrap();
+        assert_eq!(g.calculate_score(), (0., 9. + 6.5));
+        g.set_rule(CHINESE);
+        assert_eq!(g.calculate_score(), (6., 18. + 7.5));
+        // Closing the corridor makes it territory.
+        g.put_stone((0, 6), Color::Black);
+        assert_eq!(g.goban().count_territory().black, 6);
+        assert_eq!(g.calculate_score(), (7. + 6., 18. + 7.5));
+    }
+
+    #[test]
+    fn ing_rules() {
+        // Black owns 16 points and white 8: a tie with the komi of 8.
+        let finished = |rule: Rule| {
+            let mut game = Game::builder().size((4, 6)).rule(rule).build().unwrap();
+            for row in 0..4 {
+                game.play(Play(row, 3)).play(Play(row, 4));
+            }
+            game.play(Move::Pass).play(Move::Pass);
+            game
+        };
+        let chinese = finished(CHINESE);
+        let ing = finished(ING);
+        assert_eq!(chinese.calculate_score(), (16., 15.5));
+        assert_eq!(ing.calculate_score(), (16., 16.));
+        assert_eq!(chinese.outcome(), Some(EndGame::WinnerByScore(Color::Black, 0.5)));
+        assert_eq!(ing.outcome(), Some(EndGame::WinnerByScore(Color::Black, 0.)));
+        assert_eq!(EndGame::from_score(ing.calculate_score()), EndGame::Draw);
+
+        let mut scored = finished(ING);
+        scored.enter_scoring().unwrap();
+        assert_eq!(
+            scored.finalize_score(),
+            Ok(EndGame::WinnerByScore(Color::Black, 0.))
+        );
+        let reloaded = Game::from_sgf(&Game::new(GobanSizes::Nine, ING).to_sgf()).unwrap();
+        assert_eq!(reloaded.rule(), ING);
+        assert_eq!("ING".parse::<Rule>(), Ok(ING));
+
+        // The suicide of a chain is legal, not the one of a lone stone.
+        let moves = [(0, 0), (1, 0), (3, 3), (1, 1), (3, 2), (0, 2)];
+        let mut chinese = Game::new(GobanSizes::Custom(5, 5), CHINESE);
+        let mut ing = Game::new(GobanSizes::Custom(5, 5), ING);
+        for (row, col) in moves {
+            chinese.play(Play(row, col));
+            ing.play(Play(row, col));
+        }
+        assert_eq!(chinese.is_legal(Play(0, 1)), Err(PlayError::Suicide((0, 1))));
+        ing.try_play(Play(0, 1)).unwrap();
+        assert_eq!(ing.goban().get_color((0, 0)), None);
+        assert_eq!(ing.goban().get_color((0, 1)), None);
+        assert_eq!(ing.prisoners(), (0, 2));
+        ing.play(Play(3, 4)).play(Play(2, 2)).play(Play(4, 3));
+        assert_eq!(ing.is_legal(Play(4, 4)), Err(PlayError::Suicide((4, 4))));
+
+        // The suicide of a chain captures nothing but can repeat a position.
+        let mut cycle = Game::new(GobanSizes::Custom(5, 5), ING);
+        for (row, col) in [(3, 3), (1, 0), (3, 2), (1, 1), (4, 4), (0, 2), (0, 0)] {
+            cycle.play(Play(row, col));
+        }
+        cycle.play(Move::Pass);
+        assert_eq!(cycle.is_legal(Play(0, 1)), Err(PlayError::Superko((0, 1))));
+        assert!(!cycle.legals().any(|point| point == (0, 1)));
+    }
+
+    #[test]
+    fn game_events_in_order() {
+        let mut game = Game::new(GobanSizes::Custom(5, 5), CHINESE);
+        game.play(Play(2, 2));
+        assert!(game.take_events().is_empty());
+
+        game.enable_events();
+        game.play(Play(0, 0)).play(Play(0, 1)).play(Play(3, 3));
+        game.play(Play(1, 0)).play(Move::Pass).play(Move::Pass);
+        let chain_at = |goban: &Goban, point| {
+            let mut chains = goban.get_groups().map(|(chain, _)| chain);
+            chains.find(|&chain| goban.chain_stones(chain).any(|stone| stone.coord == point))
+        };
+        let black = chain_at(game.goban(), (2, 2)).unwrap();
+        let white = chain_at(game.goban(), (3, 3)).unwrap();
+        let session = game.enter_scoring().unwrap();
+        session.toggle_dead((3, 3));
+        session.toggle_dead((2, 2));
+        session.toggle_dead((2, 2));
+        let outcome = game.finalize_score().unwrap();
+        let played = |color, point: Coord, captures| GameEvent::MovePlayed {
+            color,
+            mv: point.into(),
+            captures,
+        };
+        assert_eq!(
+            game.take_events(),
+            [
+                played(Color::White, (0, 0), 0),
+                played(Color::Black, (0, 1), 0),
+                played(Color::White, (3, 3), 0),
+                played(Color::Black, (1, 0), 1),
+                GameEvent::Passed(Color::White),
+                GameEvent::Passed(Color::Black),
+                GameEvent::ScoringStarted,
+                GameEvent::DeadMarkToggled(white),
+                GameEvent::DeadMarkToggled(black),
+                GameEvent::DeadMarkToggled(black),
+                GameEvent::GameEnded(outcome),
+            ]
+        );
+        assert_eq!(outcome, EndGame::WinnerByScore(Color::Black, 17.5));
+        assert!(game.take_events().is_empty());
+
+        let mut game = Game::new(GobanSizes::Nine, CHINESE);
+        game.enable_events();
+        game.play(Play(4, 4));
+        game.disable_events();
+        game.play(Play(3, 3));
+        assert!(game.take_events().is_empty());
+        game.enable_events();
+        game.play(Move::Resign(Color::Black));
+        assert_eq!(
+            game.take_events(),
+            [
+                GameEvent::Resigned(Color::Black),
+                GameEvent::GameEnded(EndGame::WinnerByResign(Color::White)),
+            ]
+        );
+        // Going through the history replays the moves without pushing them again.
+        game.jump_to(0);
+        game.jump_to(1);
+        game.jump_to(3);
+        assert!(game.is_over());
+        assert!(game.take_events().is_empty());
+        game.undo();
+        game.play(Move::Pass);
+        assert_eq!(game.take_events(), [GameEvent::Passed(Color::Black)]);
+    }
+
+    #[test]
+    fn handicap_komi_and_compensation() {
+        // Black owns the 33 empty points left of its wall with 12 stones, white 27 with 9.
+        let finished = |rule: Rule, komi: Option<f32>| {
+            let mut builder = Game::builder();
+            builder
+                .size((9, 9))
+                .rule(rule)
+                .put_handicap(&[(2, 2), (4, 2), (6, 2)]);
+            if let Some(komi) = komi {
+                builder.komi(komi);
+            }
+            let mut game = builder.build().unwrap();
+            for row in 0..9 {
+                game.play(Play(row, 5)).play(Play(row, 4));
+            }
+            game.play(Move::Pass).play(Move::Pass);
+            game
+        };
+        let japanese = finished(JAPANESE, None);
+        assert_eq!(japanese.komi(), HANDICAP_KOMI);
+        assert_eq!(japanese.handicap(), 3);
+        assert_eq!(japanese.calculate_score(), (33., 27.5));
+        assert_eq!(japanese.outcome(), Some(EndGame::WinnerByScore(Color::Black, 5.5)));
+        // White receives 2 points, one per handicap stone but the first.
+        let aga = finished(AGA, None);
+        assert_eq!(aga.calculate_score(), (45., 38.5));
+        assert_eq!(aga.outcome(), Some(EndGame::WinnerByScore(Color::Black, 6.5)));
+        let chinese = finished(CHINESE, None);
+        assert_eq!(chinese.calculate_score(), (45., 39.5));
+
+        let mut scored = finished(AGA, None);
+        scored.enter_scoring().unwrap();
+        assert_eq!(scored.scoring_session().unwrap().score(), (45., 38.5));
+        let custom = Game::builder()
+            .size((9, 9))
+            .handicap_komi(1.5)
+            .put_handicap(&[(2, 2), (6, 6)])
+            .build()
+            .unwrap();
+        assert_eq!(custom.komi(), 1.5);
+
+        // The reverse komi is given to black.
+        let reverse = finished(JAPANESE, Some(-10.5));
+        assert_eq!(reverse.calculate_score(), (33., 16.5));
+        assert_eq!(reverse.outcome(), Some(EndGame::WinnerByScore(Color::Black, 16.5)));
+        let reverse = finished(AGA, Some(-10.5));
+        assert_eq!(reverse.outcome(), Some(EndGame::WinnerByScore(Color::Black, 17.5)));
+
+        assert_eq!("AGA".parse::<Rule>(), Ok(AGA));
+        let reloaded = Game::from_sgf(&aga.to_sgf()).unwrap();
+        assert_eq!(reloaded.rule().flag_score, AGA.flag_score);
+        assert_eq!(reloaded.calculate_score(), aga.calculate_score());
+    }
+
+    #[test]
+    fn passes_to_end_game() {
+        let three_passes = Rule {
+            passes_to_end: 3,
+            ..CHINESE
+        };
+        let mut two = Game::new(GobanSizes::Nine, CHINESE);
+        let mut three = Game::new(GobanSizes::Nine, three_passes);
+        for game in [&mut two, &mut three] {
+            game.play(Play(4, 4)).play(Move::Pass).play(Move::Pass);
+        }
+        assert!(two.is_over());
+        assert_eq!(two.is_legal(Play(3, 3)), Err(PlayError::GamePaused));
+        assert!(!three.is_over());
+        assert_eq!(three.enter_scoring().err(), Some(ScoringError::NotPaused));
+
+        // A move between the passes starts the count again.
+        three.play(Play(3, 3)).play(Move::Pass).play(Move::Pass);
+        assert!(!three.is_over());
+        three.play(Move::Pass);
+        assert!(three.is_over());
+        assert_eq!(three.is_legal(Move::Pass), Err(PlayError::GamePaused));
+        assert!(three.outcome().is_some());
+        three.enter_scoring().unwrap();
+        three.resume_play().unwrap();
+        three.play(Move::Pass).play(Move::Pass);
+        assert!(!three.is_over());
+        three.play(Play(2, 2)).play(Move::Pass).play(Move::Pass).play(Move::Pass);
+        assert!(three.is_over());
+
+        // The passes are all exported, a game loaded with two passes resumes after them.
+        let reloaded = Game::from_sgf(&three.to_sgf()).unwrap();
+        assert_eq!(reloaded.rule().passes_to_end, 2);
+        assert!(reloaded.moves().eq(three.moves()));
+        assert_eq!(reloaded.goban(), three.goban());
+
+        // A single pass ends the game, none is rejected.
+        let one_pass = Rule {
+            passes_to_end: 1,
+            ..CHINESE
+        };
+        let mut one = Game::builder().rule(one_pass).build().unwrap();
+        assert!(!one.is_over());
+        one.play(Play(4, 4)).play(Move::Pass);
+        assert!(one.is_over());
+        assert_eq!(one.is_legal(Move::Pass), Err(PlayError::GamePaused));
+        let no_pass = Rule {
+            passes_to_end: 0,
+            ..CHINESE
+        };
+        assert!(Game::builder().rule(no_pass).build().is_err());
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&one).unwrap();
+            assert!(serde_json::from_str::<Game>(&json).is_ok());
+            let json = json.replace("\"passes_to_end\":1", "\"passes_to_end\":0");
+            assert!(serde_json::from_str::<Game>(&json).is_err());
+        }
+    }
+
+    #[test]
+    fn sgf_import_illegal_moves() {
+        let sgf = include_str!("../sgf/superko_violation.sgf");
+        let options = |on_illegal| SgfImportOptions { on_illegal };
+        let warning = SgfImportWarning {
+            move_number: 24,
+            played: Play(4, 1),
+            error: PlayError::Superko((4, 1)),
+        };
+        assert_eq!(
+            Game::from_sgf(sgf).err(),
+            Some("move 24 Play(4, 1): playing at (4, 1) repeats a position".to_string())
+        );
+        assert!(Game::from_sgf_with(sgf, options(IllegalMovePolicy::Abort)).is_err());
+
+        let (skipped, warnings) =
+            Game::from_sgf_with(sgf, options(IllegalMovePolicy::Skip)).unwrap();
+        assert_eq!(warnings, [warning]);
+        assert_eq!(skipped.moves().count(), 24);
+        assert_eq!(skipped.moves().last(), Some(Move::Pass));
+        assert_eq!(skipped.goban().get_color((4, 1)), None);
+        assert!(!(1..=24).any(|number| skipped.is_forced(number)));
+        // The comment of the skipped move is dropped with it.
+        assert!(!skipped.to_sgf().contains("repeats"));
+
+        let (forced, warnings) =
+            Game::from_sgf_with(sgf, options(IllegalMovePolicy::ForcePlace)).unwrap();
+        assert_eq!(warnings, [warning]);
+        assert_eq!(forced.moves().count(), 25);
+        assert_eq!(forced.moves().nth(23), Some(Play(4, 1)));
+        assert_eq!(forced.goban().get_color((4, 1)), Some(Color::White));
+        assert_eq!(forced.turn(), Color::White);
+        assert!(forced.is_forced(24));
+        assert!(!forced.is_forced(23) && !forced.is_forced(25));
+        assert!(forced.to_sgf().contains("repeats"));
+        assert_eq!(forced.check_invariants(), Ok(()));
+        let mut scrubbed = forced.clone();
+        assert!(scrubbed.jump_to(23) && scrubbed.jump_to(24) && scrubbed.jump_to(25));
+        assert!(scrubbed.is_forced(24));
+        assert_eq!(scrubbed.goban(), forced.goban());
+        assert_eq!(scrubbed.check_invariants(), Ok(()));
+        let mut occupied = Game::new(GobanSizes::Nine, CHINESE);
+        occupied.play(Play(4, 4)).force_play((4, 4));
+        let before = occupied.goban().clone();
+        assert!(occupied.jump_to(1) && occupied.jump_to(2));
+        assert!(occupied.is_forced(2));
+        assert_eq!(occupied.goban(), &before);
+        assert_eq!(occupied.check_invariants(), Ok(()));
+        let symmetric = forced.transform(Symmetry::Transpose);
+        assert!(symmetric.is_forced(24));
+        assert_eq!(symmetric.goban().get_color((1, 4)), Some(Color::White));
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&forced).unwrap();
+            let loaded: Game = serde_json::from_str(&json).unwrap();
+            assert!(loaded.is_forced(24));
+            assert_eq!(loaded.goban(), forced.goban());
+        }
+    }
+
+    #[test]
+    fn goban_row_major_layout() {
+        let mut goban = Goban::new((3, 4));
+        goban.push((1, 3), Color::Black);
+        let colors = goban.to_vec();
+        assert_eq!(colors.len(), 12);
+        assert_eq!(goban.index_of((1, 3)), 7);
+        assert_eq!(colors[7], Some(Color::Black));
+        assert_eq!(colors.iter().filter(|color| color.is_some()).count(), 1);
+        let matrix = goban.matrix();
+        assert_eq!(matrix.len(), 3);
+        assert!(matrix.iter().all(|row| row.len() == 4));
+        assert_eq!(matrix[1][3], Some(Color::Black));
+        assert_eq!(goban.pretty_string(), "┏┯┯┓\n┠┼┼●\n┗┷┷┛\n");
+    }
+
+    #[test]
+    fn nearest_empty_point() {
+        let mut goban = Goban::new((5, 7));
+        assert_eq!(goban.nearest_empty((2, 3)), Some((2, 3)));
+        goban.push((2, 3), Color::Black);
+        // The first ring is walked in row major order.
+        assert_eq!(goban.nearest_empty((2, 3)), Some((1, 2)));
+        goban.push_many(&[(1, 2), (1, 3), (1, 4), (2, 2)], Color::White);
+        assert_eq!(goban.nearest_empty((2, 3)), Some((2, 4)));
+        assert_eq!(goban.nearest_empty_by((2, 3), |(row, _)| row > 2), Some((3, 2)));
+        assert_eq!(goban.nearest_empty_by((2, 3), |(_, col)| col == 6), Some((0, 6)));
+        assert_eq!(goban.nearest_empty_by((0, 0), |_| false), None);
+        // A corner only has the rings on one side.
+        goban.push((0, 0), Color::Black);
+        assert_eq!(goban.nearest_empty((0, 0)), Some((0, 1)));
+
+        let mut full = Goban::new((3, 3));
+        let points: Vec<Coord> = (0..3)
+            .flat_map(|row| (0..3).map(move |col| (row, col)))
+            .collect();
+        full.push_many(&points[1..], Color::Black);
+        assert_eq!(full.nearest_empty((2, 2)), Some((0, 0)));
+        full.push((0, 0), Color::Black);
+        assert_eq!(full.nearest_empty((1, 1)), None);
+    }
+
+    #[test]
+    fn largest_open_regions_suggestions() {
+        let regions = largest_open_regions(&Goban::new((19, 19)), 3);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].0.len(), 361);
+        assert_eq!(regions[0].1, (3, 3));
+        assert_eq!(largest_open_regions(&Goban::new((9, 9)), 1)[0].1, (2, 2));
+
+        // A wall splits the goban in two, and a corner is closed off.
+        let mut goban = Goban::new((9, 9));
+        let wall: Vec<Coord> = (0..9).map(|row| (row, 4)).collect();
+        goban.push_many(&wall, Color::Black);
+        goban.push_many(&[(7, 0), (7, 1), (8, 2)], Color::White);
+        goban.push((8, 1), Color::White);
+        let regions = largest_open_regions(&goban, 5);
+        let sizes: Vec<usize> = regions.iter().map(|(points, _)| points.len()).collect();
+        assert_eq!(sizes, [36, 31, 1]);
+        assert_eq!(regions[0].1, (2, 6));
+        assert_eq!(regions[1].1, (2, 2));
+        assert_eq!(regions[2], (vec![(8, 0)], (8, 0)));
+        assert!(regions[1].0.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(largest_open_regions(&goban, 1).len(), 1);
+        assert!(largest_open_regions(&goban, 0).is_empty());
+    }
+
+    #[test]
+    fn opening_points_by_size() {
+        let points = standard_opening_points(19);
+        let kind_of = |point: Coord| {
+            points
+                .iter()
+                .find(|&&(opening, _)| opening == point)
+                .map(|&(_, kind)| kind)
+        };
+        assert_eq!(kind_of((3, 3)), Some(OpeningKind::Hoshi));
+        assert_eq!(kind_of((15, 15)), Some(OpeningKind::Hoshi));
+        assert_eq!(kind_of((2, 3)), Some(OpeningKind::Komoku));
+        assert_eq!(kind_of((15, 16)), Some(OpeningKind::Komoku));
+        assert_eq!(kind_of((16, 2)), Some(OpeningKind::SanSan));
+        assert_eq!(kind_of((4, 2)), Some(OpeningKind::Mokuhazushi));
+        assert_eq!(kind_of((3, 14)), Some(OpeningKind::Takamoku));
+        assert_eq!(kind_of((3, 9)), Some(OpeningKind::SideStar));
+        assert_eq!(kind_of((9, 15)), Some(OpeningKind::SideStar));
+        assert_eq!(kind_of((9, 9)), Some(OpeningKind::Tengen));
+        assert_eq!(kind_of((5, 5)), None);
+        assert_eq!(points[0], ((3, 3), OpeningKind::Hoshi));
+        assert_eq!(points.last(), Some(&((9, 9), OpeningKind::Tengen)));
+
+        let count = |size, kind| {
+            standard_opening_points(size)
+                .iter()
+                .filter(|&&(_, opening)| opening == kind)
+                .count()
+        };
+        for size in [19, 13] {
+            assert_eq!(standard_opening_points(size).len(), 37);
+            assert_eq!(count(size, OpeningKind::Komoku), 8);
+            assert_eq!(count(size, OpeningKind::SideStar), 4);
+        }
+        assert_eq!(standard_opening_points(9).len(), 17);
+        assert_eq!(count(9, OpeningKind::Takamoku), 0);
+        assert_eq!(count(9, OpeningKind::SideStar), 0);
+        assert_eq!(count(9, OpeningKind::Tengen), 1);
+        assert_eq!(
+            standard_opening_points(7),
+            [
+                ((2, 2), OpeningKind::SanSan),
+                ((2, 4), OpeningKind::SanSan),
+                ((4, 2), OpeningKind::SanSan),
+                ((4, 4), OpeningKind::SanSan),
+                ((3, 3), OpeningKind::Tengen),
+            ]
+        );
+        assert_eq!(standard_opening_points(8).len(), 4);
+        assert!(standard_opening_points(0).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "sgf-encodings")]
+    fn sgf_legacy_charsets() {
+        let comment = |game: &Game, move_number| {
+            game.annotation(move_number)
+                .and_then(|annotation| annotation.comment.clone())
+        };
+        let (game, warning) =
+            Game::from_sgf_bytes(include_bytes!("../sgf/charset_gb2312.sgf")).unwrap();
+        assert_eq!(warning, None);
+        assert_eq!(comment(&game, 0).as_deref(), Some("黑棋先行"));
+        assert_eq!(comment(&game, 2).as_deref(), Some("白棋小目"));
+        let sgf = game.to_sgf();
+        assert!(sgf.contains("CA[UTF-8]") && !sgf.contains("GB2312"));
+        assert!(sgf.contains("PB[柯洁]"));
+        assert_eq!(comment(&Game::from_sgf(&sgf).unwrap(), 1).as_deref(), Some("天元"));
+
+        // The second byte of 表 is a backslash, the text is decoded before it's parsed.
+        let (game, warning) =
+            Game::from_sgf_bytes(include_bytes!("../sgf/charset_shift_jis.sgf")).unwrap();
+        assert_eq!(warning, None);
+        assert_eq!(comment(&game, 0).as_deref(), Some("表示"));
+        assert_eq!(comment(&game, 2).as_deref(), Some("ソフト"));
+        assert_eq!(game.moves().count(), 2);
+        let sgf = game.to_sgf();
+        assert!(sgf.contains("CA[UTF-8]") && sgf.contains("PW[井上因碩]"));
+    }
+
+    #[test]
+    fn sgf_charset_fallback() {
+        let bytes = include_bytes!("../sgf/charset_gb2312.sgf");
+        let (game, warning) = Game::from_sgf_bytes(bytes).unwrap();
+        assert_eq!(game.moves().count(), 2);
+        #[cfg(not(feature = "sgf-encodings"))]
+        assert_eq!(warning, Some(SgfCharsetWarning::Unsupported("GB2312".to_string())));
+        #[cfg(feature = "sgf-encodings")]
+        assert_eq!(warning, None);
+
+        let (game, warning) = Game::from_sgf_bytes(b"(;SZ[9]C[caf\xe9];B[ee])").unwrap();
+        assert_eq!(warning, Some(SgfCharsetWarning::Malformed("UTF-8".to_string())));
+        assert_eq!(game.annotation(0).unwrap().comment.as_deref(), Some("caf\u{fffd}"));
+        let (_, warning) = Game::from_sgf_bytes("(;CA[utf-8]SZ[9]C[café])".as_bytes()).unwrap();
+        assert_eq!(warning, None);
+        assert!(Game::new(GobanSizes::Nine, CHINESE).to_sgf().contains("CA[UTF-8]"));
+    }
+
+    #[test]
+    fn sgf_clock_times() {
+        use goban::rules::clock::{TimeControl, TimeLeft};
+        use std::time::Duration;
+
+        let game = Game::from_sgf(include_str!("../sgf/clock_byoyomi.sgf")).unwrap();
+        let control = TimeControl::ByoYomi {
+            main: Duration::from_secs(60),
+            period: Duration::from_secs(30),
+            periods: 5,
+        };
+        assert_eq!(game.clock().control(), control);
+        assert_eq!(game.sgf_overtime(), None);
+        let seconds = |time: f64| Duration::from_secs_f64(time);
+        let times: Vec<_> = (0..=8)
+            .map(|move_number| {
+                let clock = game.clock_at(move_number);
+                (clock.time_left(Color::Black), clock.time_left(Color::White))
+            })
+            .collect();
+        let main = |time| TimeLeft::main(seconds(time));
+        let overtime = |time, stones| TimeLeft::overtime(seconds(time), stones);
+        assert_eq!(
+            times,
+            [
+                (main(60.), main(60.)),
+                (main(55.3), main(60.)),
+                (main(55.3), main(52.)),
+                (main(31.), main(52.)),
+                (main(31.), main(12.5)),
+                (overtime(25., 5), main(12.5)),
+                (overtime(25., 5), overtime(30., 4)),
+                (overtime(18., 5), overtime(30., 4)),
+                (overtime(18., 5), overtime(30., 3)),
+            ]
+        );
+        assert_eq!(*game.clock(), game.clock_at(8));
+
+        // A game without the source writes the time control and the time left of each move.
+        let mut new_game = Game::new(GobanSizes::Nine, JAPANESE);
+        new_game.clock_mut().set_control(control);
+        new_game.play(Play(4, 4));
+        new_game.record_time_left(Color::Black, TimeLeft::main(Duration::from_secs(48)));
+        new_game.play(Play(2, 2));
+        new_game.record_time_left(Color::White, TimeLeft::overtime(Duration::from_secs(30), 5));
+        let sgf = new_game.to_sgf();
+        assert!(sgf.contains("TM[60]") && sgf.contains("OT[5x30 byo-yomi]"));
+        assert!(sgf.contains(";BL[48]B[ee];OW[5]WL[30]W[cc])"));
+        let loaded = Game::from_sgf(&sgf).unwrap();
+        assert_eq!(loaded.clock_at(1), new_game.clock_at(1));
+        assert_eq!(loaded.clock_at(2), new_game.clock_at(2));
+
+        let game = Game::from_sgf("(;SZ[9]TM[900]OT[3 moves / 1 min];B[ee]BL[880])").unwrap();
+        assert_eq!(game.sgf_overtime(), Some("3 moves / 1 min"));
+        let absolute = TimeControl::Absolute { main: Duration::from_secs(900) };
+        assert_eq!(game.clock().control(), absolute);
+        assert!(game.transform(Symmetry::Transpose).to_sgf().contains("OT[3 moves / 1 min]"));
+        let canadian = Game::from_sgf("(;SZ[9]TM[0]OT[25/600 Canadian])").unwrap();
+        assert_eq!(
+            canadian.clock().time_left(Color::Black),
+            TimeLeft::overtime(Duration::from_secs(600), 25)
+        );
+    }
+
+    #[test]
+    fn debug_shows_the_board() {
+        let mut game = Game::new(GobanSizes::Custom(4, 5), JAPANESE);
+        game.play(Play(1, 1)).play(Play(0, 1)).play(Play(0, 2));
+        game.play(Play(2, 2)).play(Play(0, 0));
+        let hash = game.goban().zobrist_hash();
+        assert_eq!(
+            format!("{:?}", game.goban()),
+            format!(
+                "Goban 4x5 {hash:#018x} 3 black 1 white\n\
+                 ●┯●┯┓\n\
+                 ┠●┼┼┨\n\
+                 ┠┼○┼┨\n\
+                 ┗┷┷┷┛"
+            )
+        );
+        assert_eq!(
+            format!("{game:?}"),
+            format!(
+                "Game White to play, prisoners (1, 0), last move Some(Play(0, 0)), outcome None\n\
+                 {:?}",
+                game.goban()
+            )
+        );
+        assert_eq!(
+            format!("{game:#?}"),
+            format!(
+                "Game {{
+    turn: White,
+    prisoners: (
+        1,
+        0,
+    ),
+    last_move: Some(
+        Play(
+            0,
+            0,
+        ),
+    ),
+    outcome: None,
+    goban: Goban {{
+        size: 4x5,
+        hash: {hash:#018x},
+        black: 3,
+        white: 1,
+        board:\u{20}
+        ●┯●┯┓
+        ┠●┼┼┨
+        ┠┼○┼┨
+        ┗┷┷┷┛,
+    }},
+}}"
+            )
+        );
+    }
+
+    #[test]
+    fn point_liberties_are_not_chain_liberties() {
+        let mut goban = Goban::new((9, 9));
+        goban.push((4, 4), Color::Black).push((4, 5), Color::Black);
+        for point in [(3, 4), (5, 4), (4, 3)] {
+            goban.push(point, Color::White);
+        }
+        // The stone is surrounded by its chain and white, the chain still has 3 liberties.
+        assert_eq!(goban.liberties_of_point((4, 4)).count(), 0);
+        assert!(!goban.point_has_liberty((4, 4)));
+        assert_eq!(
+            goban.liberties_of_point((4, 5)).collect::<BTreeSet<_>>(),
+            BTreeSet::from([(3, 5), (4, 6), (5, 5)])
+        );
+        let chain = goban.get_groups().find(|(_, group)| group.color == Color::Black);
+        assert_eq!(chain.unwrap().1.number_of_liberties(), 3);
+
+        // An empty point has liberties whatever the chains around.
+        assert_eq!(goban.liberties_of_point((3, 3)).count(), 2);
+        assert!(goban.point_has_liberty((3, 3)));
+    }
+
+    #[test]
+    fn score_with_dead_chains() {
+        // A black wall on the 4th column and a white wall on the 6th, with a dead white stone
+        // in the corner of black.
+        let finished = |rule: Rule| {
+            let mut game = Game::new(GobanSizes::Nine, rule);
+            for row in 0..9 {
+                game.play(Play(row, 3)).play(Play(row, 5));
+            }
+            game.play(Move::Pass).play(Play(0, 0));
+            game
+        };
+        let dead_stone = |game: &Game| {
+            let (chain, _) = game
+                .goban()
+                .get_groups()
+                .find(|&(chain, _)| game.goban().chain_stones(chain).any(|s| s.coord == (0, 0)))
+                .unwrap();
+            vec![chain]
+        };
+
+        let japanese = finished(JAPANESE);
+        let komi = japanese.komi();
+        let rule = JAPANESE.flag_score;
+        // The region of black touches the white stone, it's dame.
+        assert_eq!(japanese.calculate_score_with_dead(rule, &[]), (0., 27. + komi));
+        assert_eq!(japanese.calculate_score_by(rule), (0., 27. + komi));
+        // The dead stone is a prisoner and its region the territory of black.
+        let dead = dead_stone(&japanese);
+        assert_eq!(japanese.calculate_score_with_dead(rule, &dead), (28., 27. + komi));
+
+        let chinese = finished(CHINESE);
+        let komi = chinese.komi();
+        let rule = CHINESE.flag_score;
+        assert_eq!(chinese.calculate_score_with_dead(rule, &[]), (9., 10. + 27. + komi));
+        // The dead stone is removed, its point is in the area of black.
+        let dead = dead_stone(&chinese);
+        assert_eq!(chinese.calculate_score_with_dead(rule, &dead), (9. + 27., 9. + 27. + komi));
+
+        // The scoring phase counts the same.
+        for mut game in [japanese, chinese] {
+            let dead = dead_stone(&game);
+            let expected = game.calculate_score_with_dead(game.rule().flag_score, &dead);
+            game.play(Move::Pass).play(Move::Pass);
+            let session = game.enter_scoring().unwrap();
+            session.toggle_dead((0, 0));
+            assert_eq!(session.dead_chains(), dead);
+            assert_eq!(session.score(), expected);
+        }
+    }
+
+    #[test]
+    fn chain_info_matches_the_stones() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut game = Game::new(GobanSizes::Nine, CHINESE);
+            for _ in 0..120 {
+                let legals: Vec<Coord> = game.legals().collect();
+                match legals.choose(&mut rng) {
+                    Some(&point) => game.play(Play(point.0, point.1)),
+                    None => break,
+                };
+            }
+            let goban = game.goban();
+            for point in goban.get_empty_coords() {
+                assert_eq!(goban.chain_info(point), None);
+            }
+            for stone in goban.get_stones() {
+                let chain = goban.get_group_from_point(Point {
+                    coord: stone.coord,
+                    color: Some(stone.color),
+                });
+                let liberties: BTreeSet<Coord> = chain
+                    .iter()
+                    .flat_map(|point| goban.liberties_of_point(point.coord))
+                    .collect();
+                let expected = ChainInfo {
+                    color: stone.color,
+                    stone_count: chain.len(),
+                    liberty_count: liberties.len(),
+                    first_stone: chain.first().unwrap().coord,
+                };
+                assert_eq!(goban.chain_info(stone.coord), Some(expected), "seed {seed}");
+            }
+        }
+    }
+
+    #[test]
+    fn goban_snapshot_bytes() {
+        let mut goban = Goban::new((2, 3));
+        goban
+            .push((0, 1), Color::Black)
+            .push((1, 0), Color::White)
+            .push((1, 2), Color::Black);
+        assert_eq!(goban.snapshot(), [0, 1, 0, 2, 0, 1]);
+        let mut out = [9; 6];
+        assert_eq!(goban.snapshot_into(&mut out), Ok(()));
+        assert_eq!(out, [0, 1, 0, 2, 0, 1]);
+        assert_eq!(out[goban.index_of((1, 0))], 2);
+        assert_eq!(
+            goban.snapshot_into(&mut [0; 9]),
+            Err(GobanError::BufferLength {
+                expected: 6,
+                actual: 9
+            })
+        );
+        assert_eq!(Goban::new((19, 19)).snapshot(), vec![0; 361]);
+    }
+
+    #[test]
+    fn sgf_non_square_round_trip() {
+        // SZ is width:height, the point ea is on the first row and the fifth column.
+        let game = Game::from_sgf("(;SZ[5:3];B[ea])").unwrap();
+        assert_eq!(game.size(), (3, 5));
+        assert_eq!(game.goban().get_color((0, 4)), Some(Color::Black));
+
+        let mut game = Game::new(GobanSizes::Custom(3, 5), CHINESE);
+        game.play(Play(0, 4)).play(Play(2, 1));
+        let reloaded = Game::from_sgf(&game.to_sgf()).unwrap();
+        assert_eq!(reloaded.size(), (3, 5));
+        assert!(reloaded.moves().eq(game.moves()));
+        assert_eq!(reloaded.goban(), game.goban());
+    }
+
+    #[test]
+    fn board_idx_non_square() {
+        let size = (9, 13);
+        let mut goban = Goban::new(size);
+        let indexes: HashSet<usize> = goban.get_empty_coords().map(|c| goban.index_of(c)).collect();
+        assert_eq!(indexes.len(), 9 * 13);
+        assert_eq!(goban.index_of((1, 0)), 13);
+        assert_eq!(goban.coord_of(13), (1, 0));
+        assert_eq!(goban.index_of((8, 12)), 116);
+
+        goban.push((0, 12), Color::White);
+        goban.push_many(&[(0, 11), (2, 12)], Color::Black);
+        assert_eq!(goban.get_color((1, 0)), None);
+        assert_eq!(goban.get_color((1, 12)), None);
+        let white = goban.get_stones_by_color(Some(Color::White)).next().unwrap();
+        assert_eq!(white.coord, (0, 12));
+        assert!(goban.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn sgf_export_new_game() {
+        let mut g = Game::builder()
+            .size((9, 9))
+            .put_handicap(&[(2, 2), (6, 6)])
+            .komi(0.5)
+            .rule(JAPANESE)
+            .build()
+            .unwrap();
+        g.play(Play(2, 6)).play(Play(6, 2)).play(Move::Pass);
+        let reloaded = Game::from_sgf(&g.to_sgf()).unwrap();
+        assert_eq!(reloaded.size(), (9, 9));
+        assert_eq!(reloaded.komi(), 0.5);
+        assert_eq!(reloaded.rule().flag_illegal, JAPANESE.flag_illegal);
+        assert_eq!(reloaded.rule().flag_score, JAPANESE.flag_score);
+        assert_eq!(reloaded.goban(), g.goban());
+        assert_eq!(reloaded.turn(), g.turn());
+        assert_eq!(reloaded.moves().collect::<Vec<_>>(), g.moves().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn resign() {
+        let mut g = Game::new(GobanSizes::Nine, JAPANESE);
+        g.play(Play(4, 4)).play(Play(3, 3));
+        let before = g.clone();
+        g.try_play(Move::Resign(Color::Black)).unwrap();
+        assert!(g.is_over());
+        assert_eq!(g.outcome(), Some(EndGame::WinnerByResign(Color::White)));
+        assert_eq!(g.try_play(Play(0, 0)).unwrap_err(), PlayError::GameOver);
+        assert_eq!(g.try_play(Move::Pass).unwrap_err(), PlayError::GameOver);
+        assert_eq!(g.moves().last(), Some(Move::Resign(Color::Black)));
+
+        let sgf = g.to_sgf();
+        assert!(sgf.contains("RE[W+R]"));
+        let reloaded = Game::from_sgf(&sgf).unwrap();
+        assert_eq!(reloaded.outcome(), Some(EndGame::WinnerByResign(Color::White)));
+        assert_eq!(reloaded.goban(), g.goban());
+
+        assert_eq!(g.undo(), Some(Move::Resign(Color::Black)));
+        assert!(!g.is_over());
+        assert_eq!(g.outcome(), None);
+        assert_eq!(g.goban(), before.goban());
+        assert_eq!(g.turn(), before.turn());
+        assert!(g.try_play(Play(0, 0)).is_ok());
+    }
+
+    #[test]
+    fn scoring_session_resume_and_finalize() {
+        let mut g = Game::new(GobanSizes::Nine, CHINESE);
+        for row in 0..9 {
+            g.play(Play(row, 3)).play(Play(row, 5));
+        }
+        g.play(Move::Pass).play(Play(1, 1)).play(Move::Pass);
+        assert_eq!(g.enter_scoring().unwrap_err(), ScoringError::NotPaused);
+        g.play(Move::Pass);
+
+        // First scoring, the players disagree.
+        let before = g.clone();
+        let session = g.enter_scoring().unwrap();
+        assert!(session.toggle_dead((1, 1)));
+        assert!(session.is_dead((1, 1)));
+        assert!(!session.toggle_dead((2, 2)));
+        assert_eq!(session.score(), (36., 36. + CHINESE.komi));
+        assert_eq!(g.enter_scoring().unwrap_err(), ScoringError::AlreadyScoring);
+        assert_eq!(g.try_play(Play(0, 0)).unwrap_err(), PlayError::Scoring);
+        assert_eq!(g.goban(), before.goban());
+        g.resume_play().unwrap();
+        assert_eq!(g.resume_play().unwrap_err(), ScoringError::NotScoring);
+        assert_eq!(g.goban(), before.goban());
+        assert_eq!(g.hash_history(), before.hash_history());
+        assert_eq!(g.get_color((1, 1)), Some(Color::White));
+        assert!(!g.is_over());
+
+        // The play continues, then the second scoring is accepted.
+        g.try_play(Play(0, 0)).unwrap();
+        g.try_play(Move::Pass).unwrap();
+        g.try_play(Move::Pass).unwrap();
+        let session = g.enter_scoring().unwrap();
+        session.toggle_dead((1, 1));
+        assert_eq!(
+            g.scoring_session().unwrap().dead_stones().collect::<Vec<_>>(),
+            vec![(1, 1)]
+        );
+        let outcome = g.finalize_score().unwrap();
+        assert_eq!(outcome, EndGame::WinnerByScore(Color::White, CHINESE.komi));
+        assert_eq!(g.outcome(), Some(outcome));
+        assert_eq!(g.get_color((1, 1)), None);
+        assert_eq!(g.prisoners(), (1, 0));
+        assert_eq!(g.finalize_score().unwrap_err(), ScoringError::NotScoring);
+        assert_eq!(g.enter_scoring().unwrap_err(), ScoringError::GameOver);
+        assert_eq!(g.try_play(Play(2, 2)).unwrap_err(), PlayError::GameOver);
+        assert_eq!(g.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn estimate_score_split_goban() {
+        // Black owns the columns 0 to 3, white the columns 5 to 8, the white stone with two
+        // liberties in the black area is dead.
+        let mut g = Game::new(GobanSizes::Nine, CHINESE);
+        for row in 0..9 {
+            g.play(Play(row, 3)).play(Play(row, 5));
+        }
+        g.play(Move::Pass).play(Play(1, 1));
+        g.play(Play(0, 1)).play(Move::Pass).play(Play(1, 0));
+        let estimate = estimate_score(g.goban(), 7.5);
+        assert_eq!(estimate.dead, vec![(1, 1)]);
+        assert_eq!(estimate.owner((1, 1)), Some(Color::Black));
+        assert_eq!(estimate.owner((4, 0)), Some(Color::Black));
+        assert_eq!(estimate.owner((4, 4)), None);
+        assert_eq!(estimate.owner((4, 8)), Some(Color::White));
+        assert_eq!(estimate.black, 36.);
+        assert_eq!(estimate.white, 36. + 7.5);
+        assert_eq!(estimate_score(g.goban(), 7.5), estimate);
+    }
+
+    #[test]
+    fn estimate_score_fixtures() {
+        // Final positions with the result of the game, the estimate is by area so a few points
+        // of difference with the territory counting are expected.
+        let fixtures = [
+            // B+2 without komi.
+            (include_str!("../sgf/ShusakuvsInseki.sgf"), 2.),
+            // B+1 without komi.
+            (include_str!("../sgf/sgf_2_2ha.sgf"), 1.),
+        ];
+        for (sgf, result) in fixtures {
+            let g = Game::from_sgf(sgf).unwrap();
+            let estimate = estimate_score(g.goban(), g.komi());
+            assert!(
+                (estimate.margin() - result).abs() <= 10.,
+                "estimated {} for a result of {}",
+                estimate.margin(),
+                result
+            );
+            assert!(estimate.margin() > 0.);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn mc_ownership_settled_position() {
+        use goban::analysis::{mc_ownership, mc_ownership_for};
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        use std::time::Duration;
+
+        // Black fills the columns 0 to 3 with two eyes and a space of two points, white fills
+        // the columns 4 to 8 with two eyes.
+        let mut g = Game::new(GobanSizes::Nine, CHINESE);
+        for row in 0..9 {
+            for col in 0..9 {
+                let color = if col < 4 { Color::Black } else { Color::White };
+                if ![(2, 1), (6, 1), (4, 1), (4, 2), (2, 7), (6, 7)].contains(&(row, col)) {
+                    g.put_stone((row, col), color);
+                }
+            }
+        }
+        let ownership = mc_ownership(&g, 50, &mut StdRng::seed_from_u64(3));
+        assert_eq!(ownership.playouts, 50);
+        for row in 0..9 {
+            for col in 0..9 {
+                if col < 4 {
+                    assert!(ownership.black_probability((row, col)) > 0.95);
+                } else {
+                    assert!(ownership.white_probability((row, col)) > 0.95);
+                }
+            }
+        }
+        assert!((ownership.expected_score - (36. - 45. - CHINESE.komi)).abs() < 1.);
+        assert_eq!(ownership, mc_ownership(&g, 50, &mut StdRng::seed_from_u64(3)));
+        let single = mc_ownership(&g, 0, &mut StdRng::seed_from_u64(3));
+        assert_eq!(single.playouts, 1);
+        assert!(!single.expected_score.is_nan() && !single.ownership((4, 1)).is_nan());
+
+        let budget = mc_ownership_for(&g, Duration::from_millis(5), &mut StdRng::seed_from_u64(3));
+        assert!(budget.playouts >= 1);
+        assert!(budget.ownership((0, 0)) > 0.95);
+        assert!(budget.ownership((0, 8)) < -0.95);
+        assert_eq!(g.goban().get_color((4, 1)), None);
+    }
+
+    #[test]
+    fn legal_move_cache_equals_computation() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let fill_eye = Rule {
+            flag_illegal: JAPANESE.flag_illegal | IllegalRules::FILLEYE,
+            ..JAPANESE
+        };
+        let mut rng = StdRng::seed_from_u64(11);
+        for (size, rule) in [
+            (GobanSizes::Nine, CHINESE),
+            (GobanSizes::Nine, JAPANESE),
+            (GobanSizes::Nine, fill_eye),
+            (GobanSizes::Nine, ING),
+            (GobanSizes::Nineteen, CHINESE),
+        ] {
+            let mut g = Game::new(size, rule);
+            g.enable_legal_move_cache();
+            for _ in 0..400 {
+                let mut scratch = g.clone();
+                scratch.disable_legal_move_cache();
+                for color in [Color::Black, Color::White] {
+                    let legals = g.legal_moves_cached(color).collect::<Vec<_>>();
+                    assert_eq!(legals, scratch.legal_moves_cached(color).collect::<Vec<_>>());
+                    assert_eq!(g.legal_move_count(color), legals.len());
+                    assert_eq!(scratch.legal_move_count(color), legals.len());
+                }
+                assert_eq!(
+                    g.legal_moves_cached(g.turn()).collect::<Vec<_>>(),
+                    g.legals().collect::<Vec<_>>()
+                );
+
+                if g.moves().count() > 0 && rng.random_bool(0.1) {
+                    g.undo();
+                } else {
+                    let mut moves: Vec<Move> = g.legals().map(Move::from).collect();
+                    if moves.is_empty() || rng.random_bool(0.02) {
+                        moves.push(Move::Pass);
+                    }
+                    g.play(*moves.choose(&mut rng).unwrap());
+                    if g.is_over() {
+                        g.undo();
+                    }
+                }
+            }
+        }
+
+        // Under ING the chain suicide at (0, 1) repeats the position after white (8, 6).
+        let mut builder = Game::builder();
+        builder.size((9, 9)).rule(ING);
+        for coord in [(1, 0), (1, 1), (0, 2)] {
+            builder.add(Stone {
+                coord,
+                color: Color::White,
+            });
+        }
+        let mut g = builder
+            .moves(&[Play(8, 8), Play(8, 6), Play(0, 0), Move::Pass])
+            .build()
+            .unwrap();
+        g.enable_legal_move_cache();
+        assert_eq!(g.is_legal(Play(0, 1)), Err(PlayError::Superko((0, 1))));
+        assert!(!g.legal_moves_cached(Color::Black).any(|coord| coord == (0, 1)));
+        assert_eq!(g.legal_move_count(Color::Black), g.legals().count());
+        assert_eq!(g.legal_move_count(Color::Black), 74);
+    }
+
+    #[test]
+    fn ordered_moves_captures_first() {
+        let mut g = Game::new(GobanSizes::Nine, JAPANESE);
+        for m in [
+            (3, 4),
+            (4, 4),
+            (4, 3),
+            (0, 4),
+            (5, 4),
+            (0, 5),
+            (1, 4),
+            (0, 0),
+            (1, 5),
+            (8, 8),
+            (0, 3),
+            (8, 7),
+        ] {
+            g.play(m.into());
+        }
+        let ordered = ordered_moves(&g);
+        assert_eq!(ordered.len(), g.legals().count());
+        assert_eq!(ordered[..4], [(0, 6), (4, 5), (0, 1), (1, 0)]);
+        assert_eq!(captured_stones(&g, (0, 6)), 2);
+        assert_eq!(move_category(&g, (4, 5)), MoveCategory::Capture(Reverse(1)));
+        assert!(gives_atari(&g, (0, 1)));
+        assert!(!gives_atari(&g, (7, 7)));
+        assert_eq!(move_category(&g, (7, 7)), MoveCategory::Proximity);
+        assert!(near_last_moves(&g, (2, 2)));
+        assert_eq!(move_category(&g, (4, 8)), MoveCategory::Other);
+
+        let categories: Vec<MoveCategory> = ordered.iter().map(|&p| move_category(&g, p)).collect();
+        assert!(categories.windows(2).all(|w| w[0] <= w[1]));
+        let others: Vec<Coord> = ordered
+            .iter()
+            .copied()
+            .filter(|&p| move_category(&g, p) == MoveCategory::Other)
+            .collect();
+        assert!(others.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn ordered_moves_save_group() {
+        let mut g = Game::new(GobanSizes::Nine, JAPANESE);
+        for m in [(4, 4), (3, 4), (0, 0), (4, 3), (8, 8), (5, 4)] {
+            g.play(m.into());
+        }
+        assert!(escapes_atari(&g, (4, 5)));
+        assert!(!escapes_atari(&g, (0, 1)));
+        assert_eq!(ordered_moves(&g)[0], (4, 5));
+        assert_eq!(move_category(&g, (4, 5)), MoveCategory::EscapeAtari);
+    }
+
+    #[test]
+    fn setup_position() {
+        let mut setup = Game::setup((9, 9));
+        // The white stone has no liberties until the black stone is removed.
+        setup
+            .put((0, 0), Color::White)
+            .put((0, 1), Color::Black)
+            .put((1, 0), Color::Black)
+            .put((4, 4), Color::White)
+            .remove((1, 0))
+            .set_to_move(Color::White);
+        let mut g = setup.finish().unwrap();
+        assert_eq!(g.turn(), Color::White);
+        assert_eq!(g.goban().get_color((0, 0)), Some(Color::White));
+        assert_eq!(g.goban().get_color((1, 0)), None);
+        assert_eq!(g.goban().number_of_stones(), (1, 2));
+        assert_eq!(g.moves().count(), 0);
+        g.try_play(Play(1, 0)).unwrap();
+        g.undo();
+        assert_eq!(g.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn setup_validation() {
+        let mut setup = Game::setup((9, 9));
+        setup
+            .put((0, 1), Color::White)
+            .put((0, 0), Color::White)
+            .put((1, 0), Color::Black)
+            .put((1, 1), Color::Black)
+            .put((0, 2), Color::Black);
+        assert_eq!(setup.finish().unwrap_err(), SetupError::NoLiberties((0, 0)));
+        // The failed setup is kept, so it can be fixed.
+        assert_eq!(setup.goban().number_of_stones(), (3, 2));
+
+        let g = setup.auto_capture(true).finish().unwrap();
+        assert_eq!(g.goban().number_of_stones(), (3, 0));
+        assert_eq!(g.prisoners(), (0, 0));
+        assert_eq!(g.check_invariants(), Ok(()));
+
+        assert_eq!(
+            Game::setup((9, 9)).put((9, 0), Color::Black).finish().unwrap_err(),
+            SetupError::OutOfBounds((9, 0))
+        );
+        assert!(Game::from_sgf("(;SZ[9]AW[aa]AB[ba][ab])").is_err());
+        let g = Game::from_sgf("(;SZ[9]AW[aa]AB[ba][ab]AE[ba])").unwrap();
+        assert_eq!(g.goban().get_color((0, 1)), None);
+        assert_eq!(g.goban().get_color((1, 0)), Some(Color::Black));
+    }
+
+    #[test]
+    fn goban_flat_indexing() {
+        let mut goban = Goban::new((19, 19));
+        goban.push((0, 1), Color::Black).push((18, 18), Color::White);
+        assert_eq!(goban.index_of((0, 0)), 0);
+        assert_eq!(goban.index_of((0, 1)), 1);
+        assert_eq!(goban.index_of((1, 0)), 19);
+        assert_eq!(goban.index_of((18, 18)), 360);
+        assert_eq!(goban.coord_of(19), (1, 0));
+        assert_eq!(goban.coord_of(360), (18, 18));
+        assert_eq!(goban.get_coord(361), None);
+        assert_eq!(goban.get_index((19, 0)), None);
+        assert_eq!(goban.get_index((0, 19)), None);
+        for index in 0..361 {
+            assert_eq!(goban.index_of(goban.coord_of(index)), index);
+        }
+        assert_eq!(goban[1], Some(Color::Black));
+        assert_eq!(goban[360], Some(Color::White));
+        assert_eq!(goban[0], None);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of the goban")]
+    fn goban_flat_indexing_out_of_bounds() {
+        let _ = Goban::new((9, 9))[81];
+    }
+
+    #[test]
+    fn goban_sgf_indexing() {
+        assert_eq!(sgf_to_coord("aa", (19, 19)), Ok((0, 0)));
+        assert_eq!(sgf_to_coord("cb", (19, 19)), Ok((1, 2)));
+        assert_eq!(sgf_to_coord("ss", (19, 19)), Ok((18, 18)));
+        assert_eq!(sgf_to_coord("ta", (19, 19)), Err(SgfCoordError::OutOfBounds((0, 19))));
+        assert_eq!(sgf_to_coord("Aa", (19, 19)), Err(SgfCoordError::Uppercase));
+        assert_eq!(sgf_to_coord("a", (19, 19)), Err(SgfCoordError::Malformed));
+        assert_eq!(coord_to_sgf((1, 2)), "cb");
+
+        let mut goban = Goban::new((9, 9));
+        goban.put_sgf("dc", Color::Black).unwrap();
+        assert_eq!(goban.get_color((2, 3)), Some(Color::Black));
+        assert_eq!(goban.at_sgf("dc"), Ok(Some(Color::Black)));
+        assert_eq!(goban.at_sgf("cd"), Ok(None));
+        assert_eq!(goban.at_sgf("jj"), Err(SgfCoordError::OutOfBounds((9, 9))));
+        assert_eq!(goban["dc"], Some(Color::Black));
+
+        // The SGF import and export agree with the helpers.
+        let g = Game::from_sgf("(;SZ[9]AB[dc]AW[ha];B[cg])").unwrap();
+        assert_eq!(g.goban().at_sgf("dc"), Ok(Some(Color::Black)));
+        assert_eq!(g.goban().at_sgf("ha"), Ok(Some(Color::White)));
+        assert_eq!(g.goban().at_sgf("cg"), Ok(Some(Color::Black)));
+        let mut setup = Game::setup((9, 9));
+        setup.put(sgf_to_coord("ha", (9, 9)).unwrap(), Color::Black);
+        let mut g = setup.finish().unwrap();
+        g.play(sgf_to_coord("cg", (9, 9)).unwrap().into());
+        let sgf = g.to_sgf();
+        assert!(sgf.contains("AB[ha]"));
+        assert!(sgf.contains(";B[cg]"));
+    }
+
+    #[test]
+    #[should_panic(expected = "uppercase")]
+    fn goban_sgf_indexing_uppercase() {
+        let _ = Goban::new((9, 9))["AA"];
+    }
+
+    #[test]
+    fn adjacent_chains() {
+        let mut goban = Goban::new((9, 9));
+        goban.push_many(&[(4, 4), (4, 5), (4, 2), (0, 0)], Color::Black);
+        goban.push_many(&[(3, 4), (3, 5), (5, 5), (7, 7)], Color::White);
+        let stones = |chains: Vec<usize>| -> Vec<Vec<Coord>> {
+            chains
+                .into_iter()
+                .map(|idx| {
+                    let mut stones: Vec<Coord> = goban.chain_stones(idx).map(|s| s.coord).collect();
+                    stones.sort();
+                    stones
+                })
+                .collect()
+        };
+        assert_eq!(
+            stones(goban.adjacent_enemy_chains((4, 5))),
+            [vec![(3, 4), (3, 5)], vec![(5, 5)]]
+        );
+        assert_eq!(stones(goban.adjacent_friendly_chains((4, 4))), [vec![(4, 2)]]);
+        assert_eq!(
+            stones(goban.adjacent_enemy_chains((5, 5))),
+            [vec![(4, 4), (4, 5)]]
+        );
+        assert!(goban.adjacent_friendly_chains((0, 0)).is_empty());
+        assert!(goban.adjacent_enemy_chains((8, 8)).is_empty());
+    }
+
+    #[test]
+    fn chains_of_color() {
+        let mut goban = Goban::new((9, 9));
+        goban.push_many(&[(0, 0), (0, 1), (2, 2), (5, 5), (6, 5), (5, 6)], Color::Black);
+        goban.push_many(&[(8, 8), (1, 1), (1, 2)], Color::White);
+        let black: Vec<_> = goban.chains_of_color(Color::Black).collect();
+        let white: Vec<_> = goban.chains_of_color(Color::White).collect();
+        assert_eq!(black.len(), 3);
+        assert_eq!(white.len(), 2);
+        let (black_stones, white_stones) = goban.number_of_stones();
+        assert_eq!(black.iter().map(|c| c.num_stones as u32).sum::<u32>(), black_stones);
+        assert_eq!(white.iter().map(|c| c.num_stones as u32).sum::<u32>(), white_stones);
+        assert!(black.iter().all(|c| c.color == Color::Black));
+        let origins: Vec<_> = black.iter().map(|c| c.origin).collect();
+        assert!(origins.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn count_territory() {
+        let mut goban = Goban::new((9, 9));
+        assert_eq!(
+            goban.count_territory(),
+            TerritoryCount {
+                black: 0,
+                white: 0,
+                dame: 81
+            }
+        );
+        // The black corner is closed by the two edges of the goban.
+        goban.push_many(&[(0, 3), (1, 3), (2, 3), (3, 0), (3, 1), (3, 2)], Color::Black);
+        goban.push((8, 8), Color::White);
+        assert_eq!(
+            goban.count_territory(),
+            TerritoryCount {
+                black: 9,
+                white: 0,
+                dame: 65
+            }
+        );
+        assert_eq!(goban.count_territory_for(Color::Black), 9);
+        assert_eq!(goban.count_territory_for(Color::White), 0);
+        let (black, white) = goban.get_territories();
+        assert_eq!((black.count(), white.count()), goban.calculate_territories());
+    }
+
+    #[test]
+    fn play_errors() {
+        let mut setup = Game::setup((9, 9));
+        setup.rule(JAPANESE);
+        for point in [(0, 1), (1, 0), (2, 1), (8, 7), (7, 8)] {
+            setup.put(point, Color::Black);
+        }
+        for point in [(0, 2), (1, 1), (1, 3), (2, 2)] {
+            setup.put(point, Color::White);
+        }
+        let mut game = setup.finish().unwrap();
+        game.play(Play(1, 2));
+        assert_eq!(game.try_play(Play(9, 0)).unwrap_err(), PlayError::OutsideBoard((9, 0)));
+        assert_eq!(game.try_play(Play(0, 1)).unwrap_err(), PlayError::Occupied((0, 1)));
+        assert_eq!(game.try_play(Play(8, 8)).unwrap_err(), PlayError::Suicide((8, 8)));
+        assert_eq!(game.try_play(Play(1, 1)).unwrap_err(), PlayError::Ko((1, 1)));
+        assert_eq!(
+            game.try_play_as(Color::Black, Play(4, 4)).unwrap_err(),
+            PlayError::WrongTurn(Color::Black)
+        );
+        let err: Box<dyn std::error::Error> = Box::new(PlayError::Ko((1, 1)));
+        assert_eq!(err.to_string(), "playing at (1, 1) retakes the ko");
+        game.try_play_as(Color::White, Play(4, 4)).unwrap();
+
+        let mut eyes = Game::new(GobanSizes::Nine, Rule {
+            flag_illegal: IllegalRules::FILLEYE,
+            ..JAPANESE
+        });
+        for m in [(0, 1), (8, 8), (1, 0), (8, 6), (1, 1), (6, 8)] {
+            eyes.play(m.into());
+        }
+        assert_eq!(eyes.try_play(Play(0, 0)).err(), Some(PlayError::FillEye((0, 0))));
+
+        let sgf = "(;GM[1]FF[4]SZ[11]PL[W]
+        AB[dc][dd][de][df][cg][eg][dh][di][dj]
+        AW[ef][ff][gf][hf][gg][eh][fh][gh][hh]
+        ;W[ig];B[fg];W[dg])";
+        let mut game = Game::from_sgf(sgf).unwrap();
+        assert_eq!(game.try_play(Play(6, 4)).unwrap_err(), PlayError::Superko((6, 4)));
+
+        game.play(Move::Pass).play(Move::Pass);
+        assert_eq!(game.try_play(Play(0, 0)).unwrap_err(), PlayError::GamePaused);
+        game.resume();
+        game.play(Move::Resign(Color::Black));
+        assert_eq!(game.try_play(Play(0, 0)).unwrap_err(), PlayError::GameOver);
+    }
+
+    #[test]
+    fn sgf_annotations_round_trip() {
+        let mut game = Game::new(GobanSizes::Nine, CHINESE);
+        game.play(Play(2, 2)).play(Play(6, 6)).play(Play(2, 6));
+        game.annotate(0, Annotation::comment("A game [to review]"));
+        game.annotate(
+            2,
+            Annotation {
+                comment: Some(r"Path C:\games\ and a trailing \".to_string()),
+                markup: Markup {
+                    triangles: BTreeSet::from([(2, 2), (6, 6)]),
+                    squares: BTreeSet::from([(0, 0)]),
+                    circles: BTreeSet::from([(8, 8), (4, 4)]),
+                    labels: BTreeMap::from([((3, 3), "a".to_string()), ((5, 5), "x]".to_string())]),
+                },
+                quality: None,
+            },
+        );
+        let mut markup = Markup::default();
+        markup.labels.insert((1, 1), "1".to_string());
+        game.annotate(
+            3,
+            Annotation {
+                markup,
+                ..Default::default()
+            },
+        );
+
+        let sgf = game.to_sgf();
+        assert!(sgf.contains(r"C[A game [to review\]]"));
+        assert!(sgf.contains(r";CR[ee][ii]C[Path C:\\games\\ and a trailing \\\"));
+        assert!(sgf.contains("LB[dd:a][ff:x\\]]SQ[aa]TR[cc][gg]W[gg]"));
+        assert!(sgf.contains(";B[gc]LB[bb:1]"));
+
+        let imported = Game::from_sgf(&sgf).unwrap();
+        assert_eq!(imported.annotations(), game.annotations());
+        assert_eq!(imported.moves().collect::<Vec<_>>(), game.moves().collect::<Vec<_>>());
+        // The annotations of the record are replaced, not duplicated.
+        assert_eq!(imported.to_sgf(), sgf);
+
+        let mut imported = imported;
+        imported.annotate(2, Annotation::default());
+        imported.annotate(1, Annotation::comment("First"));
+        let sgf = imported.to_sgf();
+        assert!(sgf.contains(";B[cc]C[First];W[gg];B[gc]"));
+        let imported = Game::from_sgf(&sgf).unwrap();
+        assert_eq!(imported.annotation(2), None);
+        assert_eq!(imported.annotation(1), Some(&Annotation::comment("First")));
+    }
+
+    #[test]
+    fn sgf_move_quality() {
+        let sgf = "(;GM[1]FF[4]SZ[9]C[Review];B[cc]TE[2];W[gg]BM[1];B[gc]DO[];W[cg]IT[]\
+                   ;B[ee]TE[];W[ff]BM[3.5];B[fe])";
+        let game = Game::from_sgf(sgf).unwrap();
+        let qualities: Vec<_> = (0..=7).map(|n| game.quality_of(n)).collect();
+        assert_eq!(
+            qualities,
+            vec![
+                None,
+                Some(MoveQuality::Good { emphasized: true }),
+                Some(MoveQuality::Bad { emphasized: false }),
+                Some(MoveQuality::Doubtful),
+                Some(MoveQuality::Interesting),
+                Some(MoveQuality::Good { emphasized: false }),
+                Some(MoveQuality::Bad { emphasized: false }),
+                None,
+            ]
+        );
+        assert_eq!(game.quality_of(1).unwrap().symbol(), "!!");
+        assert_eq!(game.quality_of(3).unwrap().symbol(), "?!");
+
+        let mut game = game;
+        game.annotate(7, Annotation::quality(MoveQuality::Bad { emphasized: true }));
+        let sgf = game.to_sgf();
+        assert!(sgf.contains(";B[ee]TE[1];BM[1]W[ff];BM[2]B[fe]"));
+        assert!(sgf.contains(";B[gc]DO[];IT[]W[cg]"));
+        let imported = Game::from_sgf(&sgf).unwrap();
+        assert_eq!(imported.annotations(), game.annotations());
+    }
+
+    #[test]
+    fn move_list() {
+        let paste = "\
+            # Reported position\n\
+            1. B D4\n\
+            2. W E4   # contact\n\
+            \n\
+            3 b D5\n\
+            4 white pass\n\
+            B resign\n";
+        let game = Game::from_move_list(GobanSizes::Nine, CHINESE, paste).unwrap();
+        assert_eq!(game.goban().get_color((5, 3)), Some(Color::Black));
+        assert_eq!(game.goban().get_color((5, 4)), Some(Color::White));
+        assert_eq!(game.goban().get_color((4, 3)), Some(Color::Black));
+        assert_eq!(game.outcome(), Some(EndGame::WinnerByResign(Color::White)));
+        let list = game.to_move_list();
+        assert_eq!(list, "B D4\nW E4\nB D5\nW pass\nB resign\n");
+        let again = Game::from_move_list(GobanSizes::Nine, CHINESE, &list).unwrap();
+        assert_eq!(again.moves().collect::<Vec<_>>(), game.moves().collect::<Vec<_>>());
+
+        let error = |text| Game::from_move_list(GobanSizes::Nine, CHINESE, text).unwrap_err();
+        assert_eq!(error("B D4\nW Z1\n"), (2, MoveListError::Syntax));
+        assert_eq!(error("B D4\n\nW\n"), (3, MoveListError::Syntax));
+        assert_eq!(error("B D4 W E4\n"), (1, MoveListError::Syntax));
+        assert_eq!(error("X D4\n"), (1, MoveListError::Syntax));
+        assert_eq!(
+            error("B D4\nW D4\n"),
+            (2, MoveListError::IllegalMove(PlayError::Occupied((5, 3))))
+        );
+        assert_eq!(
+            error("B D4\nB E4\n"),
+            (2, MoveListError::IllegalMove(PlayError::WrongTurn(Color::Black)))
+        );
+        assert_eq!(Game::default().to_move_list(), "");
+    }
+
+    #[test]
+    fn psgo_export() {
+        let mut goban = Goban::new((9, 9));
+        goban.push((2, 2), Color::Black).push((6, 6), Color::White);
+        let mut options = PsgoOptions::default();
+        options.markup.triangles.insert((2, 2));
+        options.markup.squares.insert((4, 4));
+        options.markup.labels.insert((0, 8), "A".to_string());
+        assert_eq!(
+            goban.to_psgo(&options),
+            "\\begin{psgoboard}[9]\n\
+             \\markpos{\\marklb{A}}{j}{9}\n\
+             \\stone[\\marktr]{black}{c}{7}\n\
+             \\markpos{\\marksq}{e}{5}\n\
+             \\stone{white}{g}{3}\n\
+             \\end{psgoboard}\n"
+        );
+        options.crop = Crop::Region((3, 3), (0, 0));
+        assert_eq!(
+            goban.to_psgo(&options),
+            "\\begin{psgopartialboard}[9]{(a,6)(d,9)}\n\
+             \\stone[\\marktr]{black}{c}{7}\n\
+             \\end{psgopartialboard}\n"
+        );
+
+        // The white stone of 2 is captured by 3, then 5 is played on the same point.
+        let mut game = Game::new(GobanSizes::Nine, CHINESE);
+        game.play(Play(0, 1))
+            .play(Play(0, 0))
+            .play(Play(1, 0))
+            .play(Play(4, 4))
+            .play(Play(0, 0))
+            .play(Move::Pass);
+        let options = PsgoOptions {
+            crop: Crop::Fit { margin: 1 },
+            ..Default::default()
+        };
+        assert_eq!(
+            game.moves_to_psgo(0..6, &options),
+            "\\begin{psgopartialboard}[9]{(a,4)(f,9)}\n\
+             \\stone[\\marklb{2}]{white}{a}{9}\n\
+             \\stone[\\marklb{1}]{black}{b}{9}\n\
+             \\stone[\\marklb{3}]{black}{a}{8}\n\
+             \\stone[\\marklb{4}]{white}{e}{5}\n\
+             \\end{psgopartialboard}\n\
+             5 at 2, 6 pass\n"
+        );
+        assert_eq!(
+            game.moves_to_psgo(3..5, &options),
+            "\\begin{psgopartialboard}[9]{(a,4)(f,9)}\n\
+             \\stone[\\marklb{5}]{black}{a}{9}\n\
+             \\stone{black}{b}{9}\n\
+             \\stone{black}{a}{8}\n\
+             \\stone[\\marklb{4}]{white}{e}{5}\n\
+             \\end{psgopartialboard}\n"
+        );
+        // Close to the edge the crop goes to the edge.
+        let options = PsgoOptions {
+            crop: Crop::Fit { margin: 3 },
+            ..Default::default()
+        };
+        assert!(game.moves_to_psgo(0..6, &options).starts_with("\\begin{psgoboard}[9]\n"));
+    }
+
     #[test]
     #[ignore]
     #[cfg(feature = "deadstones")]
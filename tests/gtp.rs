@@ -0,0 +1,442 @@
+use std::time::Duration;
+
+use goban::gtp::{BoardStringError, GoPlayer, GtpEngine};
+use goban::pieces::goban::Goban;
+use goban::pieces::stones::Color;
+use goban::pieces::util::coord::Coord;
+use goban::rules::clock::{TimeControl, TimeLeft};
+use goban::rules::game::Game;
+use goban::rules::Move;
+
+/// Plays the first legal move, puts the free handicap stones on the first row.
+struct FirstLegal;
+
+impl GoPlayer for FirstLegal {
+    fn genmove(&mut self, game: &Game, _color: Color) -> Move {
+        game.legals().next().map_or(Move::Pass, Move::from)
+    }
+
+    fn place_free_handicap(&mut self, _game: &Game, stones: usize) -> Option<Vec<Coord>> {
+        (stones <= 9).then(|| (0..stones as u8).map(|column| (0, column)).collect())
+    }
+}
+
+/// Resigns when it has less than a minute left.
+struct Hurried;
+
+impl GoPlayer for Hurried {
+    fn genmove(&mut self, game: &Game, color: Color) -> Move {
+        if game.clock().time_left(color).time < Duration::from_secs(60) {
+            Move::Resign(color)
+        } else {
+            Move::Pass
+        }
+    }
+}
+
+/// Uses the default free handicap placement.
+struct Fixed;
+
+impl GoPlayer for Fixed {
+    fn genmove(&mut self, _game: &Game, _color: Color) -> Move {
+        Move::Pass
+    }
+}
+
+fn transcript<P: GoPlayer>(engine: &mut GtpEngine<P>, input: &str) -> String {
+    let mut output = vec![];
+    engine.run(input.as_bytes(), &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn fixed_handicap() {
+    let mut engine = GtpEngine::new(Fixed);
+    let output = transcript(
+        &mut engine,
+        "boardsize 19\n1 fixed_handicap 5\n2 fixed_handicap 2\n3 genmove w\n",
+    );
+    assert_eq!(
+        output,
+        "= \n\n=1 D4 Q16 D16 Q4 K10\n\n?2 board not empty\n\n=3 pass\n\n"
+    );
+    assert_eq!(engine.game().goban().number_of_stones(), (5, 0));
+    assert!(engine.game().to_sgf().contains("HA[5]"));
+
+    let output = transcript(
+        &mut engine,
+        "boardsize 9\nfixed_handicap 1\nfixed_handicap 10\nfixed_handicap x\nfixed_handicap 9\n",
+    );
+    assert_eq!(
+        output,
+        "= \n\n? invalid number of stones\n\n? invalid number of stones\n\n? syntax error\n\n\
+         = C3 G7 C7 G3 C5 G5 E3 E7 E5\n\n"
+    );
+    assert_eq!(engine.game().turn(), Color::White);
+
+    let output = transcript(
+        &mut engine,
+        "boardsize 8\nfixed_handicap 5\nfixed_handicap 4\n",
+    );
+    assert_eq!(
+        output,
+        "= \n\n? invalid number of stones\n\n= C3 F6 C6 F3\n\n"
+    );
+}
+
+#[test]
+fn place_free_handicap() {
+    let mut engine = GtpEngine::new(FirstLegal);
+    let output = transcript(&mut engine, "boardsize 9\nplace_free_handicap 3\n");
+    assert_eq!(output, "= \n\n= A9 B9 C9\n\n");
+    assert_eq!(engine.game().goban().get_color((0, 2)), Some(Color::Black));
+
+    // The player can't place the stones, the fixed points are used and there are less.
+    let output = transcript(&mut engine, "clear_board\nplace_free_handicap 12\n");
+    assert_eq!(output, "= \n\n= C3 G7 C7 G3 C5 G5 E3 E7 E5\n\n");
+
+    let mut engine = GtpEngine::new(Fixed);
+    let output = transcript(
+        &mut engine,
+        "boardsize 13\nplace_free_handicap 1\nplace_free_handicap 2\nplace_free_handicap 2\n",
+    );
+    assert_eq!(
+        output,
+        "= \n\n? invalid number of stones\n\n= D4 K10\n\n? board not empty\n\n"
+    );
+}
+
+#[test]
+fn set_free_handicap() {
+    let mut engine = GtpEngine::new(Fixed);
+    let output = transcript(
+        &mut engine,
+        "boardsize 9\nset_free_handicap A1\nset_free_handicap A1 A1\nset_free_handicap A1 Z9\n\
+         set_free_handicap A1 J9 e5\nset_free_handicap C3 G7\n",
+    );
+    assert_eq!(
+        output,
+        "= \n\n? bad vertex list\n\n? bad vertex list\n\n? bad vertex list\n\n= \n\n\
+         ? board not empty\n\n"
+    );
+    let goban = engine.game().goban();
+    assert_eq!(goban.get_color((8, 0)), Some(Color::Black));
+    assert_eq!(goban.get_color((0, 8)), Some(Color::Black));
+    assert_eq!(goban.get_color((4, 4)), Some(Color::Black));
+    let sgf = engine.game().to_sgf();
+    assert!(sgf.contains("HA[3]"));
+    assert!(sgf.contains("AB[ai]"));
+
+    let output = transcript(&mut engine, "play b D4\nplay w D4\nplay w pass\n");
+    assert_eq!(output, "? illegal move\n\n= \n\n? illegal move\n\n");
+}
+
+#[test]
+fn time_settings() {
+    let secs = Duration::from_secs;
+    let mut engine = GtpEngine::new(Fixed);
+    let output = transcript(&mut engine, "1 time_settings 600 300 25\n");
+    assert_eq!(output, "=1 \n\n");
+    let clock = engine.game().clock();
+    assert_eq!(
+        clock.control(),
+        TimeControl::Canadian {
+            main: secs(600),
+            period: secs(300),
+            stones: 25
+        }
+    );
+    assert_eq!(clock.time_left(Color::White), TimeLeft::main(secs(600)));
+
+    let output = transcript(
+        &mut engine,
+        "time_left b 140 12\ntime_left white 500 0\ntime_left b x 0\ntime_left b 10\n",
+    );
+    assert_eq!(output, "= \n\n= \n\n? syntax error\n\n? syntax error\n\n");
+    let clock = engine.game().clock();
+    assert_eq!(
+        clock.time_left(Color::Black),
+        TimeLeft::overtime(secs(140), 12)
+    );
+    assert!(clock.time_left(Color::Black).in_overtime());
+    assert_eq!(clock.time_left(Color::White), TimeLeft::main(secs(500)));
+
+    // The settings are kept for the next games, the time left is reset.
+    transcript(&mut engine, "boardsize 9\n");
+    assert_eq!(
+        engine.game().clock().time_left(Color::Black),
+        TimeLeft::main(secs(600))
+    );
+
+    let output = transcript(&mut engine, "time_settings 1800 0 0\n");
+    assert_eq!(output, "= \n\n");
+    assert_eq!(
+        engine.game().clock().control(),
+        TimeControl::Absolute { main: secs(1800) }
+    );
+    for settings in ["0 0 0", "0 0", "600 300 0"] {
+        transcript(&mut engine, "time_settings 600 300 25\n");
+        let output = transcript(&mut engine, &format!("time_settings {settings}\n"));
+        assert_eq!(output, "= \n\n");
+        assert_eq!(engine.game().clock().control(), TimeControl::None);
+    }
+    let output = transcript(&mut engine, "time_settings 600\ntime_settings -1 0 0\n");
+    assert_eq!(output, "? syntax error\n\n? syntax error\n\n");
+}
+
+#[test]
+fn kgs_time_settings() {
+    let secs = Duration::from_secs;
+    let mut engine = GtpEngine::new(Fixed);
+    let output = transcript(&mut engine, "1 kgs-time_settings byoyomi 600 30 5
+");
+    assert_eq!(output, "=1 \n\n");
+    let clock = engine.game().clock();
+    assert_eq!(
+        clock.control(),
+        TimeControl::ByoYomi {
+            main: secs(600),
+            period: secs(30),
+            periods: 5
+        }
+    );
+    assert_eq!(clock.time_left(Color::Black), TimeLeft::main(secs(600)));
+
+    // The canadian overtime configures the same clock as time_settings.
+    transcript(&mut engine, "kgs-time_settings canadian 600 300 25
+");
+    let canadian = engine.game().clock().control();
+    transcript(&mut engine, "time_settings 600 300 25
+");
+    assert_eq!(engine.game().clock().control(), canadian);
+    assert_eq!(
+        canadian,
+        TimeControl::Canadian {
+            main: secs(600),
+            period: secs(300),
+            stones: 25
+        }
+    );
+
+    let output = transcript(&mut engine, "kgs-time_settings absolute 1800
+");
+    assert_eq!(output, "= \n\n");
+    assert_eq!(
+        engine.game().clock().control(),
+        TimeControl::Absolute { main: secs(1800) }
+    );
+    let output = transcript(&mut engine, "kgs-time_settings byoyomi 900 30 0
+");
+    assert_eq!(output, "= \n\n");
+    assert_eq!(
+        engine.game().clock().control(),
+        TimeControl::Absolute { main: secs(900) }
+    );
+    let output = transcript(&mut engine, "kgs-time_settings none
+");
+    assert_eq!(output, "= \n\n");
+    assert_eq!(engine.game().clock().control(), TimeControl::None);
+
+    let output = transcript(
+        &mut engine,
+        "kgs-time_settings\nkgs-time_settings byoyomi 600 30\nkgs-time_settings absolute x\n\
+         kgs-time_settings fischer 600 10\nkgs-time_settings none 5\n",
+    );
+    assert_eq!(output, "? syntax error\n\n".repeat(5));
+    let output = transcript(&mut engine, "known_command kgs-time_settings\n");
+    assert_eq!(output, "= true\n\n");
+}
+
+#[test]
+fn time_left_seen_by_player() {
+    let mut engine = GtpEngine::new(Hurried);
+    let output = transcript(
+        &mut engine,
+        "time_settings 300 30 10\ngenmove b\ntime_left w 20 3\ngenmove w\n",
+    );
+    assert_eq!(output, "= \n\n= pass\n\n= \n\n= resign\n\n");
+}
+
+/// A finished 9x9 game: a black wall on the E column, a white wall on the G column, a dead
+/// white stone in the corner of black and a dead black stone in the corner of white.
+fn finished_game() -> String {
+    let mut input: String = (1..=9)
+        .map(|row| format!("play b E{row}\nplay w G{row}\n"))
+        .collect();
+    input += "play b J1\nplay w A9\nplay b pass\nplay w pass\n";
+    input
+}
+
+#[test]
+fn final_score() {
+    let mut engine = GtpEngine::new(Fixed);
+    transcript(&mut engine, &format!("boardsize 9\n{}", finished_game()));
+    let output = transcript(&mut engine, "1 final_score\n2 final_status_list dead\n");
+    assert_eq!(output, "=1 B+10.5\n\n=2 A9\nJ1\n\n");
+
+    let output = transcript(
+        &mut engine,
+        "final_status_list alive\nfinal_status_list seki\n",
+    );
+    assert_eq!(
+        output,
+        "= E9 E8 E7 E6 E5 E4 E3 E2 E1\nG9 G8 G7 G6 G5 G4 G3 G2 G1\n\n= \n\n"
+    );
+    let output = transcript(&mut engine, "final_status_list\nfinal_status_list dame\n");
+    assert_eq!(output, "? syntax error\n\n? syntax error\n\n");
+
+    // The score uses the komi set by the controller.
+    let output = transcript(&mut engine, "komi 0.5\nfinal_score\n");
+    assert_eq!(output, "= \n\n= B+17.5\n\n");
+
+    let mut engine = GtpEngine::new(Hurried);
+    let output = transcript(
+        &mut engine,
+        "time_settings 300 30 10\ntime_left b 10 3\ngenmove b\nfinal_score\n",
+    );
+    assert_eq!(output, "= \n\n= \n\n= resign\n\n= W+Resign\n\n");
+}
+
+#[test]
+fn final_score_ing() {
+    // The finished game of the test above, saved with the Ing rules.
+    let mut sgf = String::from("(;GM[1]FF[4]SZ[9]KM[8]RU[GOE]");
+    for row in (b'a'..=b'i').rev().map(char::from) {
+        sgf += &format!(";B[e{row}];W[g{row}]");
+    }
+    sgf += ";B[ii];W[aa];B[];W[])";
+    let path = std::env::temp_dir().join("goban_final_score_ing.sgf");
+    std::fs::write(&path, sgf).unwrap();
+    let mut engine = GtpEngine::new(Fixed);
+    let output = transcript(&mut engine, &format!("loadsgf {}
+final_score
+", path.display()));
+    assert_eq!(output, "= \n\n= B+10\n\n");
+}
+
+#[test]
+fn loadsgf() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/sgf/gtp_9x9.sgf");
+    let mut engine = GtpEngine::new(FirstLegal);
+    let output = transcript(
+        &mut engine,
+        &format!("time_settings 300 0 0\n1 loadsgf {path} 4\n2 showboard\n3 reg_genmove w\n"),
+    );
+    assert_eq!(
+        output,
+        "= \n\n=1 \n\n=2 \n   A B C D E F G H J\n 9 . . . . . . . . . 9\n 8 . . . . . . . . . 8\n \
+         7 . . O . . . X . . 7\n 6 . . . . . . . . . 6\n 5 . . . . X . . . . 5\n 4 . . . . . . . \
+         . . 4\n 3 . . . . . . . . . 3\n 2 . . . . . . . . . 2\n 1 . . . . . . . . . 1\n   A B C \
+         D E F G H J\n\n=3 A9\n\n"
+    );
+    // The move of reg_genmove isn't played.
+    let game = engine.game();
+    assert_eq!(game.moves().count(), 3);
+    assert_eq!(game.turn(), Color::White);
+    assert_eq!(game.komi(), 6.5);
+    assert_eq!(
+        game.clock().control(),
+        TimeControl::Absolute {
+            main: Duration::from_secs(300)
+        }
+    );
+
+    let output = transcript(&mut engine, &format!("loadsgf {path}\nloadsgf {path} 0\n"));
+    assert_eq!(output, "= \n\n? syntax error\n\n");
+    assert_eq!(engine.game().moves().count(), 5);
+
+    let missing = concat!(env!("CARGO_MANIFEST_DIR"), "/sgf/missing.sgf");
+    let broken = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+    let output = transcript(
+        &mut engine,
+        &format!("loadsgf {missing}\nloadsgf {broken}\n"),
+    );
+    assert_eq!(output, "? cannot load file\n\n? cannot load file\n\n");
+    assert_eq!(engine.game().moves().count(), 5);
+}
+
+#[test]
+fn loadsgf_legacy_charset() {
+    let mut engine = GtpEngine::new(FirstLegal);
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/sgf/charset_gb2312.sgf");
+    let output = transcript(&mut engine, &format!("loadsgf {path}\n"));
+    assert_eq!(output, "= \n\n");
+    assert_eq!(engine.game().moves().count(), 2);
+    #[cfg(feature = "sgf-encodings")]
+    {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/sgf/charset_shift_jis.sgf");
+        let output = transcript(&mut engine, &format!("loadsgf {path} 2\n"));
+        assert_eq!(output, "= \n\n");
+        assert_eq!(engine.game().moves().collect::<Vec<_>>(), [Move::Play(4, 4)]);
+    }
+}
+
+#[test]
+fn gtp_board_string() {
+    let mut goban = Goban::new((9, 9));
+    goban
+        .push((0, 0), Color::Black)
+        .push((8, 0), Color::White)
+        .push((6, 2), Color::Black)
+        .push((2, 7), Color::White);
+    let board = goban.to_gtp_board_string();
+    assert!(board.contains(" 9 X . . . . . . . . 9\n"));
+    assert!(board.contains(" 3 . . X . . . . . . 3\n"));
+    assert!(board.contains(" 1 O . . . . . . . . 1\n"));
+    assert_eq!(Goban::from_gtp_board_string(&board), Ok(goban));
+
+    let mut goban = Goban::new((13, 13));
+    goban.push((3, 3), Color::White).push((9, 9), Color::Black);
+    let board = goban.to_gtp_board_string();
+    assert_eq!(Goban::from_gtp_board_string(&board), Ok(goban));
+}
+
+#[test]
+fn gnugo_showboard() {
+    // showboard of GNU Go 3.8 after black D4 (last move), white F6 and black C7.
+    let capture = "= \n\
+                   \x20  A B C D E F G H J\n\
+                   \x209 . . . . . . . . . 9\n\
+                   \x208 . . . . . . . . . 8\n\
+                   \x207 . . X . . . + . . 7     WHITE (O) has captured 0 stones\n\
+                   \x206 . . . . . O . . . 6\n\
+                   \x205 . . . . + . . . . 5\n\
+                   \x204 . . .(X). . . . . 4\n\
+                   \x203 . . + . . . + . . 3     BLACK (X) has captured 0 stones\n\
+                   \x202 . . . . . . . . . 2\n\
+                   \x201 . . . . . . . . . 1\n\
+                   \x20  A B C D E F G H J\n\
+                   \n";
+    let goban = Goban::from_gtp_board_string(capture).unwrap();
+    assert_eq!(goban.size(), (9, 9));
+    assert_eq!(goban.number_of_stones(), (2, 1));
+    assert_eq!(goban.get_color((5, 3)), Some(Color::Black));
+    assert_eq!(goban.get_color((3, 5)), Some(Color::White));
+    assert_eq!(goban.get_color((2, 2)), Some(Color::Black));
+    assert_eq!(goban.get_color((2, 6)), None);
+
+    let mut engine = GtpEngine::new(Fixed);
+    let output = transcript(
+        &mut engine,
+        "boardsize 9\nplay b D4\nplay w F6\nplay b C7\nshowboard\n",
+    );
+    let shown = output.rsplit("= ").next().unwrap();
+    assert_eq!(Goban::from_gtp_board_string(shown), Ok(goban));
+
+    assert_eq!(
+        Goban::from_gtp_board_string("   A B\n"),
+        Err(BoardStringError::NoRows)
+    );
+    assert_eq!(
+        Goban::from_gtp_board_string(" 2 . . 2\n 1 . 1\n"),
+        Err(BoardStringError::RowWidth(1))
+    );
+    assert_eq!(
+        Goban::from_gtp_board_string(" 1 . . 1\n 2 . . 2\n"),
+        Err(BoardStringError::RowNumber(1))
+    );
+    assert_eq!(
+        Goban::from_gtp_board_string(" 2 . # 2\n 1 . . 1\n"),
+        Err(BoardStringError::Point('#'))
+    );
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "proptest")]
+
+use std::collections::BTreeSet;
+
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+use goban::pieces::util::coord::{two_to_1dim, Coord};
+use goban::pieces::zobrist::index_zobrist;
+use goban::rules::game::Game;
+use goban::rules::{GobanSizes, IllegalReason, Move, ScoreRules, CHINESE, JAPANESE};
+use goban::testing::{coord, game, goban, legal_moves};
+
+proptest! {
+    #[test]
+    fn incremental_hash_equals_recomputed(goban in goban(3..=9, JAPANESE, 80)) {
+        let size = goban.size();
+        let hash = goban
+            .get_stones()
+            .fold(0, |hash, s| hash ^ index_zobrist(two_to_1dim(size, s.coord), s.color));
+        prop_assert_eq!(goban.zobrist_hash(), hash);
+    }
+
+    #[test]
+    fn generated_chains_have_liberties(goban in goban(3..=9, CHINESE, 80)) {
+        prop_assert!(goban.chains().all(|chain| !chain.is_dead()));
+    }
+
+    #[test]
+    fn generated_games_hold_invariants(game in game(3..=9, JAPANESE, 80)) {
+        prop_assert_eq!(game.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn coords_are_in_the_goban(c in coord((7, 5))) {
+        prop_assert!(c.0 < 7 && c.1 < 5);
+    }
+
+    #[test]
+    fn undo_play_is_identity(game in game(3..=9, CHINESE, 60), pick in any::<Index>()) {
+        let legals: Vec<Move> = game.legals().map(Move::from).collect();
+        prop_assume!(!game.is_over() && !legals.is_empty());
+        let mut played = game.clone();
+        played.play(*pick.get(&legals));
+        played.undo();
+        prop_assert_eq!(played.goban(), game.goban());
+        prop_assert_eq!(played.zobrist_hash(), game.zobrist_hash());
+        prop_assert_eq!(played.turn(), game.turn());
+        prop_assert_eq!(played.prisoners(), game.prisoners());
+        prop_assert_eq!(played.hash_history(), game.hash_history());
+    }
+
+    #[test]
+    fn area_scores_cover_the_goban(game in game(3..=9, CHINESE, 120)) {
+        let (height, width) = game.size();
+        let (black, white) = game.calculate_score_by(ScoreRules::STONES);
+        prop_assert!(black >= 0. && white >= 0.);
+        let (black_territory, white_territory) = game.calculate_territories();
+        let dame = game.get_empty_coords().count() - black_territory - white_territory;
+        prop_assert_eq!((black + white) as usize + dame, height as usize * width as usize);
+    }
+
+    #[test]
+    fn forbidden_points_complement_legals(game in game(3..=9, CHINESE, 80)) {
+        let (height, width) = game.size();
+        let legals: BTreeSet<Coord> = game.legals().collect();
+        let forbidden: BTreeSet<Coord> = game.forbidden_points().map(|(coord, _)| coord).collect();
+        prop_assert!(legals.is_disjoint(&forbidden));
+        prop_assert_eq!(legals.len() + forbidden.len(), height as usize * width as usize);
+        for (coord, reason) in game.forbidden_points() {
+            prop_assert_eq!(reason == IllegalReason::Occupied, game.goban().get_color(coord).is_some());
+        }
+    }
+
+    #[test]
+    fn legal_moves_replay(moves in legal_moves((9, 9), CHINESE, 40)) {
+        let mut game = Game::new(GobanSizes::Nine, CHINESE);
+        for &m in &moves {
+            prop_assert!(game.try_play(m).is_ok());
+        }
+        prop_assert_eq!(game.moves().collect::<Vec<_>>(), moves);
+    }
+}